@@ -0,0 +1,228 @@
+//! Ownership and admin actions signed by the owner.
+//!
+//! Besides calling the setters, the owner can sign an EIP-712 `AdminAction`
+//! (see [`ADMIN_ACTION_TYPEHASH`]) over its next admin nonce, and have
+//! anyone execute it through `executeSignedAdminAction`.
+use alloy_primitives::{b256, keccak256, Address, B256, U256};
+use alloy_sol_types::SolValue;
+use stylus_sdk::{abi::Bytes, prelude::*};
+
+use crate::{
+    ConstantSumCurve, Error, InvalidAdminAction, MultisigRequired, SignatureExpired, Unauthorized,
+};
+
+/// Admin action that can be executed with an EIP-712 signature of the owner,
+/// applying `value` (and `subject`, if any) like the matching setter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AdminAction {
+    /// [`ConstantSumCurve::set_currency_policy`] with `value` as the policy.
+    SetCurrencyPolicy = 0,
+    /// [`ConstantSumCurve::set_currency_listed`] with `subject` as the
+    /// currency and a non-zero `value` as listed.
+    SetCurrencyListed = 1,
+    /// [`ConstantSumCurve::set_min_swap_amount`] with `value` as the amount.
+    SetMinSwapAmount = 2,
+    /// [`ConstantSumCurve::set_router_only`] with a non-zero `value` as
+    /// enabled.
+    SetRouterOnly = 3,
+    /// [`ConstantSumCurve::set_router`] with `subject` as the router and a
+    /// non-zero `value` as allowed.
+    SetRouter = 4,
+}
+
+impl TryFrom<u8> for AdminAction {
+    type Error = Error;
+
+    fn try_from(action: u8) -> Result<Self, Self::Error> {
+        match action {
+            0 => Ok(Self::SetCurrencyPolicy),
+            1 => Ok(Self::SetCurrencyListed),
+            2 => Ok(Self::SetMinSwapAmount),
+            3 => Ok(Self::SetRouterOnly),
+            4 => Ok(Self::SetRouter),
+            _ => Err(Error::InvalidAdminAction(InvalidAdminAction { action })),
+        }
+    }
+}
+
+/// EIP-712 type hash of a signed admin action,
+/// `keccak256("AdminAction(uint8 action,address subject,uint256 value,uint256 nonce,uint64 deadline)")`.
+pub const ADMIN_ACTION_TYPEHASH: B256 =
+    b256!("ca9affecf25b6f57459c2e8b3af5ad22b7e786ea3d6a5daf8af685d49189e6e5");
+
+/// Interface of the ownership and the signed admin actions.
+pub trait IAdmin {
+    /// The error type associated to the trait implementation.
+    type Error: Into<alloc::vec::Vec<u8>>;
+
+    /// Returns the owner of the contract.
+    fn owner(&self) -> Address;
+
+    /// Transfers the ownership to `new_owner`.
+    ///
+    /// Once the multisig is configured, the ownership can only be
+    /// transferred through a [`crate::multisig::CriticalAction::TransferOwnership`]
+    /// proposal.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `new_owner` - The new owner.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::MultisigRequired`] - If the multisig is configured.
+    ///
+    /// # Events
+    ///
+    /// * [`OwnershipTransferred`](crate::OwnershipTransferred).
+    fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Self::Error>;
+
+    /// Returns the nonce the owner must sign for the next signed admin
+    /// action.
+    fn admin_nonce(&self) -> U256;
+
+    /// Executes an [`AdminAction`] signed by the owner, so facilitators can
+    /// manage the contract without funding the owner's key. Anyone can
+    /// relay the signature.
+    ///
+    /// The owner signs the EIP-712 `AdminAction` message (see
+    /// [`ADMIN_ACTION_TYPEHASH`]) over the current [`Self::admin_nonce`],
+    /// which is then incremented.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `action` - The `u8` discriminant of the [`AdminAction`].
+    /// * `subject` - The affected currency or router, or zero if none.
+    /// * `value` - The new value.
+    /// * `deadline` - The time after which the signature expires.
+    /// * `signature` - The owner's `r || s || v` signature.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::SignatureExpired`] - If `deadline` has passed.
+    /// * [`Error::InvalidSignature`] - If `signature` cannot be recovered.
+    /// * [`Error::Unauthorized`] - If the signer is not the owner.
+    /// * [`Error::InvalidAdminAction`] - If `action` is not a valid
+    ///   [`AdminAction`].
+    /// * Any error of the matching setter.
+    ///
+    /// # Events
+    ///
+    /// * The event of the matching setter.
+    fn execute_signed_admin_action(
+        &mut self,
+        action: u8,
+        subject: Address,
+        value: U256,
+        deadline: u64,
+        signature: Bytes,
+    ) -> Result<(), Self::Error>;
+}
+
+#[public]
+impl IAdmin for ConstantSumCurve {
+    type Error = Error;
+
+    fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Self::Error> {
+        meter!("transferOwnership", Address);
+        self.check_active()?;
+        self.only_owner()?;
+        if self.multisig_enabled() {
+            return Err(Error::MultisigRequired(MultisigRequired {}));
+        }
+        self.update_owner(new_owner);
+        Ok(())
+    }
+
+    fn admin_nonce(&self) -> U256 {
+        self.admin_nonce.get()
+    }
+
+    fn execute_signed_admin_action(
+        &mut self,
+        action: u8,
+        subject: Address,
+        value: U256,
+        deadline: u64,
+        signature: Bytes,
+    ) -> Result<(), Self::Error> {
+        meter!("executeSignedAdminAction", u8, Address, U256, u64, Bytes);
+        self.check_active()?;
+        if self.vm().block_timestamp() > deadline {
+            return Err(Error::SignatureExpired(SignatureExpired { deadline }));
+        }
+
+        let nonce = self.admin_nonce.get();
+        let struct_hash = keccak256(
+            (
+                ADMIN_ACTION_TYPEHASH,
+                U256::from(action),
+                subject,
+                value,
+                nonce,
+                deadline,
+            )
+                .abi_encode(),
+        );
+        let signer = self.recover_signer(self.typed_data_digest(struct_hash), &signature)?;
+        if signer != self.owner.get() {
+            return Err(Error::Unauthorized(Unauthorized { account: signer }));
+        }
+        self.admin_nonce.set(nonce + U256::from(1));
+
+        match AdminAction::try_from(action)? {
+            AdminAction::SetCurrencyPolicy => {
+                self.update_currency_policy(value.saturating_to::<u8>())?;
+            }
+            AdminAction::SetCurrencyListed => {
+                self.update_currency_listed(subject, !value.is_zero());
+            }
+            AdminAction::SetMinSwapAmount => self.update_min_swap_amount(value),
+            AdminAction::SetRouterOnly => self.update_router_only(!value.is_zero()),
+            AdminAction::SetRouter => self.update_router(subject, !value.is_zero()),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::uint;
+
+    use super::*;
+    use crate::{
+        testing::{deploy, hook},
+        InvalidSignature,
+    };
+
+    #[motsu::test]
+    fn signed_admin_action_rejects_malformed_signature(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+
+        let err = contract
+            .sender(bob)
+            .execute_signed_admin_action(
+                AdminAction::SetMinSwapAmount as u8,
+                Address::ZERO,
+                uint!(1_U256),
+                u64::MAX,
+                Bytes::from(vec![0u8; 64]),
+            )
+            .expect_err("should reject a 64-byte signature");
+        assert!(matches!(err, Error::InvalidSignature(_)));
+        assert_eq!(U256::ZERO, contract.sender(alice).admin_nonce());
+    }
+}
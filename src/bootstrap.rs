@@ -0,0 +1,192 @@
+//! Bootstrap auction selling `token0` before constant-sum trading, like a
+//! Balancer liquidity bootstrapping pool.
+//!
+//! During the bootstrap, swaps can only buy `token0` with `token1`. They are
+//! priced by the weighted curve, with the weight of `token0` declining
+//! linearly from `start_weight` to `end_weight`, so its price falls until
+//! buyers step in. Once the bootstrap ends, the curve trades at its exchange
+//! rate for good.
+use alloy_primitives::{aliases::U64, U256};
+use stylus_sdk::{
+    prelude::*,
+    storage::{StorageU256, StorageU64},
+};
+
+use crate::{
+    bounds,
+    math::WAD,
+    weighted::{MAX_WEIGHT, MIN_WEIGHT},
+    BootstrapBuyOnly, ConstantSumCurve, Currency, Error, InvalidBootstrap, InvalidPhase,
+};
+
+/// Phase of the curve's bootstrap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Phase {
+    /// No bootstrap is pending or running, so the curve trades at its
+    /// exchange rate.
+    Trading = 0,
+    /// A bootstrap is scheduled and swaps wait for it to start.
+    Scheduled = 1,
+    /// The bootstrap is running and swaps can only buy `token0`.
+    Bootstrapping = 2,
+}
+
+/// A bootstrap: its start, end, and start and end weights of `token0`.
+pub type BootstrapView = (u64, u64, U256, U256);
+
+/// State of the bootstrap auction.
+#[storage]
+pub struct Bootstrap {
+    start: StorageU64,
+    end: StorageU64,
+    start_weight: StorageU256,
+    end_weight: StorageU256,
+}
+
+/// Returns the [`Phase`] at `timestamp` of a bootstrap running from `start`
+/// to `end`, where an `end` of zero means no bootstrap.
+pub fn phase_at(timestamp: u64, start: u64, end: u64) -> Phase {
+    if end == 0 || timestamp >= end {
+        Phase::Trading
+    } else if timestamp < start {
+        Phase::Scheduled
+    } else {
+        Phase::Bootstrapping
+    }
+}
+
+/// Returns the weight of `token0` at `timestamp`, interpolated linearly
+/// from `start_weight` at `start` to `end_weight` at `end`.
+pub fn weight_at(
+    timestamp: u64,
+    start: u64,
+    end: u64,
+    start_weight: U256,
+    end_weight: U256,
+) -> U256 {
+    let elapsed = U256::from(timestamp.clamp(start, end) - start);
+    let duration = U256::from(end - start);
+    start_weight - (start_weight - end_weight) * elapsed / duration
+}
+
+impl ConstantSumCurve {
+    /// Returns the current [`Phase`].
+    pub(crate) fn current_phase(&self) -> Phase {
+        phase_at(
+            self.vm().block_timestamp(),
+            self.bootstrap.start.get().to::<u64>(),
+            self.bootstrap.end.get().to::<u64>(),
+        )
+    }
+
+    /// Returns the scheduled bootstrap.
+    pub(crate) fn bootstrap_view(&self) -> BootstrapView {
+        (
+            self.bootstrap.start.get().to::<u64>(),
+            self.bootstrap.end.get().to::<u64>(),
+            self.bootstrap.start_weight.get(),
+            self.bootstrap.end_weight.get(),
+        )
+    }
+
+    /// Stores the bootstrap, or clears it if `end` is zero.
+    pub(crate) fn update_bootstrap(
+        &mut self,
+        start: u64,
+        end: u64,
+        start_weight: U256,
+        end_weight: U256,
+    ) {
+        self.bootstrap.start.set(U64::from(start));
+        self.bootstrap.end.set(U64::from(end));
+        self.bootstrap.start_weight.set(start_weight);
+        self.bootstrap.end_weight.set(end_weight);
+    }
+
+    /// Checks that the bootstrap can still be scheduled or cancelled, i.e.
+    /// that none has started yet.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidPhase`] - If a bootstrap is running or has ended.
+    pub(crate) fn check_bootstrap_pending(&self) -> Result<(), Error> {
+        let phase = self.current_phase();
+        let started = match phase {
+            Phase::Trading => !self.bootstrap.end.get().is_zero(),
+            Phase::Scheduled => false,
+            Phase::Bootstrapping => true,
+        };
+        if started {
+            return Err(Error::InvalidPhase(InvalidPhase { phase: phase as u8 }));
+        }
+        Ok(())
+    }
+
+    /// Checks that a bootstrap from `start` to `end` has not ended yet, and
+    /// has declining weights leaving at least [`MIN_WEIGHT`] to each token.
+    /// A `start` in the past starts the bootstrap right away, and the
+    /// bootstrap must then run for at least [`bounds::MIN_LOCK`].
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidBootstrap`] - If the bootstrap is invalid.
+    /// * [`Error::ParameterOutOfBounds`] - If the bootstrap is too short.
+    pub(crate) fn check_bootstrap(
+        &self,
+        start: u64,
+        end: u64,
+        start_weight: U256,
+        end_weight: U256,
+    ) -> Result<(), Error> {
+        let valid = end > self.vm().block_timestamp()
+            && end > start
+            && start_weight >= end_weight
+            && end_weight >= MIN_WEIGHT
+            && start_weight <= MAX_WEIGHT - MIN_WEIGHT;
+        if !valid {
+            return Err(Error::InvalidBootstrap(InvalidBootstrap {
+                start,
+                end,
+                start_weight,
+                end_weight,
+            }));
+        }
+        bounds::check_lock(start.max(self.vm().block_timestamp()), end)
+    }
+
+    /// Returns the current weights of `input` and `output` if the bootstrap
+    /// is running, or `None` if the curve trades at its exchange rate.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidPhase`] - If the bootstrap has not started yet.
+    /// * [`Error::BootstrapBuyOnly`] - If the swap does not buy `token0`
+    ///   during the bootstrap.
+    pub(crate) fn bootstrap_weights(
+        &self,
+        input: Currency,
+        output: Currency,
+    ) -> Result<Option<(U256, U256)>, Error> {
+        match self.current_phase() {
+            Phase::Trading => Ok(None),
+            phase @ Phase::Scheduled => {
+                Err(Error::InvalidPhase(InvalidPhase { phase: phase as u8 }))
+            }
+            Phase::Bootstrapping => {
+                if input < output {
+                    return Err(Error::BootstrapBuyOnly(BootstrapBuyOnly { input }));
+                }
+                let (start, end, start_weight, end_weight) = self.bootstrap_view();
+                let weight0 = weight_at(
+                    self.vm().block_timestamp(),
+                    start,
+                    end,
+                    start_weight,
+                    end_weight,
+                );
+                Ok(Some((WAD - weight0, weight0)))
+            }
+        }
+    }
+}
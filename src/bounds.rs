@@ -0,0 +1,136 @@
+//! Bounds of the curve's parameters, checked by every setter.
+//!
+//! Each bound is a constant, checked against the unit of its parameter at
+//! compile time, and exposed on-chain by `bounds()`. Setters check
+//! their arguments with the functions of this module, so a parameter out of
+//! its bound cannot be stored by any path.
+//!
+//! * Fees, in basis points, are at most [`MAX_FEE_BPS`].
+//! * The spread of a price report from the median of all reports is at most
+//!   [`MAX_SPREAD_BPS`].
+//! * A bootstrap auction locks the pool to buys of `token0` for at least
+//!   [`MIN_LOCK`] seconds.
+//! * An exchange rate skews the 1:1 price by at most [`MAX_SKEW`] either way.
+use alloy_primitives::{uint, U256};
+
+use crate::{Error, InvalidFee, ParameterOutOfBounds, FEE_DENOMINATOR};
+
+/// Maximum fee in basis points, a 10% fee.
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Maximum deviation of a price report from the median of all reports, in
+/// basis points: 10% either way.
+pub const MAX_SPREAD_BPS: u16 = 1_000;
+
+/// Minimum duration of a bootstrap auction, in seconds.
+pub const MIN_LOCK: u64 = 60;
+
+/// Maximum ratio between the numerator and the denominator of an exchange
+/// rate, either way.
+pub const MAX_SKEW: U256 = uint!(1_000_000_U256);
+
+const _: () = assert!(MAX_FEE_BPS < FEE_DENOMINATOR);
+const _: () = assert!(MAX_SPREAD_BPS > 0 && MAX_SPREAD_BPS <= FEE_DENOMINATOR);
+const _: () = assert!(MIN_LOCK > 0);
+
+/// Parameter checked against its bound, reported by
+/// [`Error::ParameterOutOfBounds`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Parameter {
+    /// A fee, bounded by [`MAX_FEE_BPS`].
+    Fee = 0,
+    /// The maximum deviation of a report, bounded by [`MAX_SPREAD_BPS`].
+    Spread = 1,
+    /// The duration of a bootstrap auction, bounded by [`MIN_LOCK`].
+    Lock = 2,
+    /// The skew of an exchange rate, bounded by [`MAX_SKEW`].
+    Skew = 3,
+}
+
+/// Bounds of the curve's parameters: [`MAX_FEE_BPS`], [`MAX_SPREAD_BPS`],
+/// [`MIN_LOCK`] and [`MAX_SKEW`].
+pub type BoundsView = (u16, u16, u64, U256);
+
+/// Returns the bounds of the curve's parameters.
+pub fn bounds() -> BoundsView {
+    (MAX_FEE_BPS, MAX_SPREAD_BPS, MIN_LOCK, MAX_SKEW)
+}
+
+/// Checks that `fee_bps` is at most [`MAX_FEE_BPS`].
+///
+/// # Errors
+///
+/// * [`Error::InvalidFee`] - If `fee_bps` exceeds [`MAX_FEE_BPS`].
+pub fn check_fee(fee_bps: u16) -> Result<(), Error> {
+    if fee_bps > MAX_FEE_BPS {
+        return Err(Error::InvalidFee(InvalidFee { fee_bps }));
+    }
+    Ok(())
+}
+
+/// Checks that `spread_bps` is at most [`MAX_SPREAD_BPS`].
+///
+/// # Errors
+///
+/// * [`Error::ParameterOutOfBounds`] - If `spread_bps` exceeds
+///   [`MAX_SPREAD_BPS`].
+pub fn check_spread(spread_bps: u16) -> Result<(), Error> {
+    if spread_bps > MAX_SPREAD_BPS {
+        return Err(out_of_bounds(
+            Parameter::Spread,
+            U256::from(spread_bps),
+            U256::from(MAX_SPREAD_BPS),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that an auction from `start` to `end` lasts at least
+/// [`MIN_LOCK`] seconds. Auctions ending before they start are left to the
+/// caller.
+///
+/// # Errors
+///
+/// * [`Error::ParameterOutOfBounds`] - If the auction is shorter than
+///   [`MIN_LOCK`].
+pub fn check_lock(start: u64, end: u64) -> Result<(), Error> {
+    let duration = end.saturating_sub(start);
+    if duration < MIN_LOCK {
+        return Err(out_of_bounds(
+            Parameter::Lock,
+            U256::from(duration),
+            U256::from(MIN_LOCK),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that the rate `numerator / denominator` is within [`MAX_SKEW`]
+/// of 1:1. Unset rates, with a zero numerator or denominator, are not
+/// skewed.
+///
+/// # Errors
+///
+/// * [`Error::ParameterOutOfBounds`] - If the larger of `numerator` and
+///   `denominator` exceeds [`MAX_SKEW`] times the smaller.
+pub fn check_skew(numerator: U256, denominator: U256) -> Result<(), Error> {
+    let (low, high) = if numerator < denominator {
+        (numerator, denominator)
+    } else {
+        (denominator, numerator)
+    };
+    if !low.is_zero() && high > low.saturating_mul(MAX_SKEW) {
+        return Err(out_of_bounds(Parameter::Skew, high / low, MAX_SKEW));
+    }
+    Ok(())
+}
+
+/// Returns [`Error::ParameterOutOfBounds`] for `parameter`.
+fn out_of_bounds(parameter: Parameter, value: U256, bound: U256) -> Error {
+    Error::ParameterOutOfBounds(ParameterOutOfBounds {
+        parameter: parameter as u8,
+        value,
+        bound,
+    })
+}
@@ -0,0 +1,148 @@
+//! Changelog of the latest state changes.
+//!
+//! Every state change gets a monotonically increasing sequence number
+//! (`op_seq`), which is also emitted in the change's event, so off-chain
+//! consumers can detect missed or reordered updates.
+//!
+//! The last [`CHANGELOG_CAPACITY`] state changes are kept in a ring buffer,
+//! so lightweight indexers recovering from downtime can catch up through
+//! `recent_changes` instead of scanning logs.
+use alloc::vec::Vec;
+
+use alloy_primitives::{
+    aliases::{U64, U8},
+    B256, U256,
+};
+use stylus_sdk::{
+    prelude::*,
+    storage::{StorageB256, StorageU256, StorageU8},
+};
+
+use crate::ConstantSumCurve;
+
+/// Number of state changes kept in the changelog.
+pub const CHANGELOG_CAPACITY: u64 = 64;
+
+/// Kind of a recorded state change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ChangeKind {
+    /// The currency policy was set.
+    CurrencyPolicy = 0,
+    /// A currency was added to or removed from the currency list.
+    CurrencyListed = 1,
+    /// The minimum swap amount was set.
+    MinSwapAmount = 2,
+    /// The router-only mode was enabled or disabled.
+    RouterOnly = 3,
+    /// A router was added to or removed from the router whitelist.
+    Router = 4,
+    /// The decommission was scheduled or cancelled.
+    DecommissionScheduled = 5,
+    /// The contract was decommissioned.
+    Decommissioned = 6,
+    /// The ownership was transferred.
+    Owner = 7,
+    /// A signer was added to or removed from the multisig.
+    Signer = 8,
+    /// The multisig threshold was set.
+    Threshold = 9,
+    /// The guardian or its sunset was set.
+    Guardian = 10,
+    /// Swaps were paused or unpaused.
+    Paused = 11,
+    /// The shadow mode was enabled or disabled.
+    ShadowMode = 12,
+    /// A price reporter was registered or unregistered.
+    Reporter = 13,
+    /// The maximum price age was set.
+    MaxPriceAge = 14,
+    /// The exchange rate of a pair was set.
+    ExchangeRate = 15,
+    /// The maximum deviation of a price report was set.
+    MaxDeviation = 16,
+    /// The reserves were set.
+    Reserves = 17,
+    /// A bootstrap auction was scheduled or cancelled.
+    Bootstrap = 18,
+    /// The fee taken after each swap was set.
+    AfterSwapFee = 19,
+    /// The pool moved to another lifecycle state.
+    PoolState = 20,
+    /// The reserves of a registered pool were set.
+    PoolReserves = 21,
+    /// The swap fee of a registered pool was set.
+    PoolFee = 22,
+    /// Swaps in a registered pool were paused or unpaused.
+    PoolPaused = 23,
+    /// The hook started or stopped receiving ERC-6909 claims.
+    ClaimsMode = 24,
+    /// The fee manager was changed.
+    FeeManager = 25,
+    /// The maximum price age of a registered pool was set.
+    PoolMaxPriceAge = 26,
+    /// The currencies of the default pool were set.
+    Currencies = 27,
+    /// The post-deploy self-test passed.
+    SelfTest = 28,
+}
+
+/// A recorded state change: its sequence number, [`ChangeKind`], subject
+/// and new value.
+///
+/// The subject is the affected pool id, or the affected address left-padded
+/// to 32 bytes, or zero if none.
+pub type Change = (u64, u8, B256, U256);
+
+/// State of a recorded state change.
+#[storage]
+pub struct ChangeRecord {
+    kind: StorageU8,
+    subject: StorageB256,
+    value: StorageU256,
+}
+
+impl ConstantSumCurve {
+    /// Appends a state change to the changelog, overwriting the oldest
+    /// record once it is full, and returns its `op_seq`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `kind` - The kind of the state change.
+    /// * `subject` - The affected pool id or address, or zero if none.
+    /// * `value` - The new value.
+    pub(crate) fn record_change(&mut self, kind: ChangeKind, subject: B256, value: U256) -> u64 {
+        let seq = self.change_count.get().to::<u64>();
+        let mut record = self.changelog.setter(U64::from(seq % CHANGELOG_CAPACITY));
+        record.kind.set(U8::from(kind as u8));
+        record.subject.set(subject);
+        record.value.set(value);
+        self.change_count.set(U64::from(seq + 1));
+        seq
+    }
+
+    /// Returns the recorded state changes with a sequence number of at least
+    /// `from_seq`, oldest first. Changes evicted from the changelog are
+    /// skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `from_seq` - The sequence number of the first change to return.
+    pub(crate) fn changes_since(&self, from_seq: u64) -> Vec<Change> {
+        let count = self.change_count.get().to::<u64>();
+        let oldest = count.saturating_sub(CHANGELOG_CAPACITY);
+        (from_seq.max(oldest)..count)
+            .map(|seq| {
+                let record = self.changelog.getter(U64::from(seq % CHANGELOG_CAPACITY));
+                (
+                    seq,
+                    record.kind.get().to::<u8>(),
+                    record.subject.get(),
+                    record.value.get(),
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,341 @@
+//! Constant-Product Curve calculation for Uniswap V4 Hooks.
+//!
+//! Prices swaps along the `x * y = k` invariant of the tracked reserves,
+//! using full-precision `mulDiv` so intermediate products cannot overflow.
+//!
+//! The first deposit into empty reserves sets the price of the pool to the
+//! ratio of its amounts, so depositors confirm the price they expect along
+//! with a tolerance, and fat-fingered amounts revert instead.
+//!
+//! Only one contract can be the entrypoint of the deployed WASM, so this
+//! curve is deployed instead of [`crate::ConstantSumCurve`] when built with
+//! `--cfg curve="constant-product"`.
+use alloc::string::String;
+
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    evm,
+    prelude::*,
+    storage::{StorageAddress, StorageMap, StorageString, StorageU256},
+};
+
+use crate::{
+    bounds, math, AmountInCalculated, AmountOutCalculated, AmountOverflow, Currency, Error,
+    IUniswapV4Curve, InsufficientLiquidity, PriceOutOfTolerance, Unauthorized, UnsortedCurrencies,
+    ZeroAmount, FEE_DENOMINATOR,
+};
+
+sol! {
+    /// Emitted when the reserve of `currency` is set by the owner.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event ReserveSet(address indexed currency, uint256 reserve);
+}
+
+/// Constant-product curve pricing swaps with the reserves of each currency.
+#[storage]
+#[cfg_attr(curve = "constant-product", entrypoint)]
+pub struct ConstantProductCurve {
+    version: StorageString,
+    owner: StorageAddress,
+    reserves: StorageMap<Currency, StorageU256>,
+}
+
+// SAFETY: Unless it is the entrypoint, the contract is only instantiated as
+// top-level storage in tests.
+#[cfg(not(curve = "constant-product"))]
+unsafe impl TopLevelStorage for ConstantProductCurve {}
+
+#[public]
+#[implements(IUniswapV4Curve<Error = Error>)]
+impl ConstantProductCurve {
+    /// Initializes the contract.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `version` - The version of the curve.
+    /// * `owner` - The owner of the contract.
+    #[constructor]
+    pub fn constructor(&mut self, version: String, owner: Address) {
+        self.version.set_str(version);
+        self.owner.set(owner);
+    }
+
+    /// Returns the owner of the contract.
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Returns the reserve of `currency`.
+    pub fn reserve(&self, currency: Currency) -> U256 {
+        self.reserves.get(currency)
+    }
+
+    /// Sets the reserve of `currency`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `currency` - The currency to update.
+    /// * `reserve` - The new reserve.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`ReserveSet`].
+    pub fn set_reserve(&mut self, currency: Currency, reserve: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.update_reserve(currency, reserve);
+        Ok(())
+    }
+
+    /// Deposits `amount0` of `currency0` and `amount1` of `currency1` into
+    /// the reserves, and returns the resulting price of `currency0` in
+    /// `currency1` as an 18-decimal fixed-point number.
+    ///
+    /// The first deposit sets the price to the ratio of its amounts, and
+    /// later ones move it unless they are proportional to the reserves.
+    /// Either way, the price must end within `tolerance_bps` of
+    /// `expected_price`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `currency0` - The lower currency of the pair.
+    /// * `currency1` - The higher currency of the pair.
+    /// * `amount0` - The amount of `currency0` to deposit.
+    /// * `amount1` - The amount of `currency1` to deposit.
+    /// * `expected_price` - The price the depositor expects after the
+    ///   deposit, scaled by 1e18.
+    /// * `tolerance_bps` - The accepted deviation from `expected_price`, in
+    ///   basis points of it.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::UnsortedCurrencies`] - If `currency0` does not sort before
+    ///   `currency1`.
+    /// * [`Error::ParameterOutOfBounds`] - If `tolerance_bps` exceeds
+    ///   [`bounds::MAX_SPREAD_BPS`].
+    /// * [`Error::ZeroAmount`] - If the deposit leaves either reserve empty.
+    /// * [`Error::AmountOverflow`] - If a reserve or the price does not fit
+    ///   into `uint256`.
+    /// * [`Error::PriceOutOfTolerance`] - If the price ends outside the
+    ///   tolerance.
+    ///
+    /// # Events
+    ///
+    /// * [`ReserveSet`] for each currency.
+    pub fn deposit(
+        &mut self,
+        currency0: Currency,
+        currency1: Currency,
+        amount0: U256,
+        amount1: U256,
+        expected_price: U256,
+        tolerance_bps: u16,
+    ) -> Result<U256, Error> {
+        self.only_owner()?;
+        if currency0 >= currency1 {
+            return Err(Error::UnsortedCurrencies(UnsortedCurrencies {
+                currency0,
+                currency1,
+            }));
+        }
+        bounds::check_spread(tolerance_bps)?;
+        let overflow = || Error::AmountOverflow(AmountOverflow {});
+        let reserve0 = self
+            .reserves
+            .get(currency0)
+            .checked_add(amount0)
+            .ok_or_else(overflow)?;
+        let reserve1 = self
+            .reserves
+            .get(currency1)
+            .checked_add(amount1)
+            .ok_or_else(overflow)?;
+        if reserve0.is_zero() || reserve1.is_zero() {
+            return Err(Error::ZeroAmount(ZeroAmount {}));
+        }
+
+        let price = math::mul_div(reserve1, math::WAD, reserve0).ok_or_else(overflow)?;
+        Self::check_price(expected_price, tolerance_bps, price)?;
+        self.update_reserve(currency0, reserve0);
+        self.update_reserve(currency1, reserve1);
+
+        Ok(price)
+    }
+}
+
+#[public]
+impl IUniswapV4Curve for ConstantProductCurve {
+    type Error = Error;
+
+    fn version(&self) -> String {
+        self.version.get_string()
+    }
+
+    fn get_amount_in_for_exact_output(
+        &mut self,
+        amount_out: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Self::Error> {
+        let reserve_in = self.reserves.get(input);
+        let reserve_out = self.reserves.get(output);
+        let amount_in = Self::calculate_amount_in(reserve_in, reserve_out, amount_out)?;
+        self.reserves.setter(input).set(reserve_in + amount_in);
+        self.reserves.setter(output).set(reserve_out - amount_out);
+
+        #[allow(deprecated)]
+        evm::log(AmountInCalculated {
+            amount_out,
+            input,
+            output,
+            zero_for_one,
+        });
+
+        Ok(amount_in)
+    }
+
+    fn get_amount_out_from_exact_input(
+        &mut self,
+        amount_in: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Self::Error> {
+        let reserve_in = self.reserves.get(input);
+        let reserve_out = self.reserves.get(output);
+        let amount_out = Self::calculate_amount_out(reserve_in, reserve_out, amount_in)?;
+        self.reserves.setter(input).set(reserve_in + amount_in);
+        self.reserves.setter(output).set(reserve_out - amount_out);
+
+        #[allow(deprecated)]
+        evm::log(AmountOutCalculated {
+            amount_in,
+            input,
+            output,
+            zero_for_one,
+        });
+
+        Ok(amount_out)
+    }
+}
+
+impl ConstantProductCurve {
+    /// Checks that the caller is the owner.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    fn only_owner(&self) -> Result<(), Error> {
+        let account = self.vm().msg_sender();
+        if account != self.owner.get() {
+            return Err(Error::Unauthorized(Unauthorized { account }));
+        }
+        Ok(())
+    }
+
+    /// Sets the reserve of `currency` without access control.
+    ///
+    /// # Events
+    ///
+    /// * [`ReserveSet`].
+    fn update_reserve(&mut self, currency: Currency, reserve: U256) {
+        self.reserves.setter(currency).set(reserve);
+
+        #[allow(deprecated)]
+        evm::log(ReserveSet { currency, reserve });
+    }
+
+    /// Checks that `price` is within `tolerance_bps` of `expected_price`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PriceOutOfTolerance`] - If it is not.
+    fn check_price(expected_price: U256, tolerance_bps: u16, price: U256) -> Result<(), Error> {
+        // The tolerance is at most `expected_price`, so it always fits.
+        let tolerance = math::mul_div(
+            expected_price,
+            U256::from(tolerance_bps),
+            U256::from(FEE_DENOMINATOR),
+        )
+        .unwrap_or_default();
+        if price.abs_diff(expected_price) > tolerance {
+            return Err(Error::PriceOutOfTolerance(PriceOutOfTolerance {
+                expected_price,
+                price,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Returns the amount of input tokens for an exact-output swap, rounded
+    /// up, keeping `reserve_in * reserve_out` constant.
+    ///
+    /// # Arguments
+    ///
+    /// * `reserve_in` - The reserve of the input token.
+    /// * `reserve_out` - The reserve of the output token.
+    /// * `amount_out` - The amount of output tokens the user expects to receive.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InsufficientLiquidity`] - If `amount_out` does not leave
+    ///   any output reserve.
+    /// * [`Error::AmountOverflow`] - If the input amount or the new input
+    ///   reserve does not fit into `uint256`.
+    pub fn calculate_amount_in(
+        reserve_in: U256,
+        reserve_out: U256,
+        amount_out: U256,
+    ) -> Result<U256, Error> {
+        if amount_out >= reserve_out {
+            return Err(Error::InsufficientLiquidity(InsufficientLiquidity {
+                available: reserve_out,
+                requested: amount_out,
+            }));
+        }
+        let overflow = || Error::AmountOverflow(AmountOverflow {});
+        let amount_in = math::mul_div_up(reserve_in, amount_out, reserve_out - amount_out)
+            .ok_or_else(overflow)?;
+        reserve_in.checked_add(amount_in).ok_or_else(overflow)?;
+        Ok(amount_in)
+    }
+
+    /// Returns the amount of output tokens for an exact-input swap, rounded
+    /// down, keeping `reserve_in * reserve_out` constant.
+    ///
+    /// # Arguments
+    ///
+    /// * `reserve_in` - The reserve of the input token.
+    /// * `reserve_out` - The reserve of the output token.
+    /// * `amount_in` - The amount of input tokens.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If the new input reserve does not fit
+    ///   into `uint256`.
+    pub fn calculate_amount_out(
+        reserve_in: U256,
+        reserve_out: U256,
+        amount_in: U256,
+    ) -> Result<U256, Error> {
+        let reserve_in_after = reserve_in
+            .checked_add(amount_in)
+            .ok_or(Error::AmountOverflow(AmountOverflow {}))?;
+        if reserve_in_after.is_zero() {
+            return Ok(U256::ZERO);
+        }
+        // `reserve_out * amount_in / reserve_in_after` is at most
+        // `reserve_out`, so it always fits.
+        Ok(math::mul_div(reserve_out, amount_in, reserve_in_after).unwrap_or_default())
+    }
+}
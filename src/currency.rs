@@ -0,0 +1,135 @@
+//! Currencies moved between accounts and the hook: ERC-20 tokens, and native
+//! ETH as the zero address, like v4's `CurrencyLibrary.ADDRESS_ZERO`.
+//!
+//! Deposits pull ERC-20 tokens with `transferFrom`, and take native ETH as the
+//! value of the call. The value must match the native amount exactly, and
+//! pools of two ERC-20 tokens reject any value. Withdrawals send ERC-20 tokens
+//! with `transfer`, and native ETH as a plain value transfer.
+use alloy_primitives::{Address, B256, U256};
+use stylus_sdk::{call::Call, prelude::*};
+
+use crate::{
+    ConstantSumCurve, Currency, Error, NativeValueMismatch, TransferFailed, UnexpectedNativeValue,
+};
+
+/// Native ETH as a [`Currency`]. It sorts before every token, so it can only
+/// be the `currency0` of a pool.
+pub const NATIVE: Currency = Address::ZERO;
+
+sol_interface! {
+    /// Subset of ERC-20 used to move tokens to and from the hook.
+    interface IERC20 {
+        function transfer(address to, uint256 amount) external returns (bool);
+        function transferFrom(address from, address to, uint256 amount) external returns (bool);
+    }
+}
+
+/// Returns true if `currency` is native ETH.
+pub fn is_native(currency: Currency) -> bool {
+    currency == NATIVE
+}
+
+impl ConstantSumCurve {
+    /// Checks that the value of the call pays exactly the native amounts of
+    /// a deposit of `amount0` of `currency0` and `amount1` of `currency1`
+    /// into the pool `pool_id`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::UnexpectedNativeValue`] - If value is sent for a pool of
+    ///   two ERC-20 tokens.
+    /// * [`Error::NativeValueMismatch`] - If the value differs from the
+    ///   native amount.
+    pub(crate) fn check_native_value(
+        &self,
+        pool_id: B256,
+        (currency0, amount0): (Currency, U256),
+        (currency1, amount1): (Currency, U256),
+    ) -> Result<(), Error> {
+        let value = self.vm().msg_value();
+        let expected = if is_native(currency0) {
+            amount0
+        } else if is_native(currency1) {
+            amount1
+        } else {
+            if !value.is_zero() {
+                return Err(Error::UnexpectedNativeValue(UnexpectedNativeValue {
+                    pool_id,
+                    value,
+                }));
+            }
+            return Ok(());
+        };
+        if value != expected {
+            return Err(Error::NativeValueMismatch(NativeValueMismatch {
+                expected,
+                value,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Pulls `amount` of `currency` from `from` to the hook. Native ETH is
+    /// already paid by the value of the call, checked by
+    /// [`Self::check_native_value`].
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::TransferFailed`] - If the ERC-20 transfer fails.
+    pub(crate) fn pull_currency(
+        &mut self,
+        currency: Currency,
+        from: Address,
+        amount: U256,
+    ) -> Result<(), Error> {
+        if amount.is_zero() || is_native(currency) {
+            return Ok(());
+        }
+
+        let hook = self.vm().contract_address();
+        let call = Call::new_mutating(self);
+        let transferred = IERC20::new(currency)
+            .transfer_from(self.vm(), call, from, hook, amount)
+            .map_err(|_| transfer_failed(currency))?;
+        if !transferred {
+            return Err(transfer_failed(currency));
+        }
+        Ok(())
+    }
+
+    /// Sends `amount` of `currency` from the hook to `to`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::TransferFailed`] - If the transfer fails.
+    pub(crate) fn push_currency(
+        &mut self,
+        currency: Currency,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), Error> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        if is_native(currency) {
+            self.vm()
+                .transfer_eth(to, amount)
+                .map_err(|_| transfer_failed(currency))?;
+            return Ok(());
+        }
+        let call = Call::new_mutating(self);
+        let transferred = IERC20::new(currency)
+            .transfer(self.vm(), call, to, amount)
+            .map_err(|_| transfer_failed(currency))?;
+        if !transferred {
+            return Err(transfer_failed(currency));
+        }
+        Ok(())
+    }
+}
+
+/// Returns [`Error::TransferFailed`] for `currency`.
+fn transfer_failed(currency: Currency) -> Error {
+    Error::TransferFailed(TransferFailed { currency })
+}
@@ -0,0 +1,89 @@
+//! Exchange rates between the currencies of a pair.
+//!
+//! A rate of `numerator / denominator` means that one unit of `currency0`
+//! trades for `numerator / denominator` units of `currency1`, where
+//! `currency0` is the lower address. Pairs without a rate use the default
+//! rate set in the constructor, and a rate with a zero numerator or
+//! denominator is unset, falling back to 1:1.
+use alloy_primitives::{aliases::U160, U256};
+use stylus_sdk::{prelude::*, storage::StorageU256};
+
+use crate::{math, AmountOverflow, ConstantSumCurve, Currency, Error};
+
+/// Storage of an exchange rate.
+#[storage]
+pub struct ExchangeRate {
+    numerator: StorageU256,
+    denominator: StorageU256,
+}
+
+impl ExchangeRate {
+    /// Returns the rate as `(numerator, denominator)`, or `None` if unset.
+    pub(crate) fn rate(&self) -> Option<(U256, U256)> {
+        let numerator = self.numerator.get();
+        let denominator = self.denominator.get();
+        if numerator.is_zero() || denominator.is_zero() {
+            return None;
+        }
+        Some((numerator, denominator))
+    }
+
+    /// Sets the rate to `numerator / denominator`.
+    pub(crate) fn set_rate(&mut self, numerator: U256, denominator: U256) {
+        self.numerator.set(numerator);
+        self.denominator.set(denominator);
+    }
+}
+
+impl ConstantSumCurve {
+    /// Returns the rate at which `input` trades for `output`, as
+    /// `(numerator, denominator)`.
+    pub(crate) fn directed_rate(&self, input: Currency, output: Currency) -> (U256, U256) {
+        if input < output {
+            self.exchange_rate(input, output)
+        } else {
+            let (numerator, denominator) = self.exchange_rate(output, input);
+            (denominator, numerator)
+        }
+    }
+
+    /// Returns the square root of the price of `currency0` in `currency1` as
+    /// a Q64.96 number.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If the price does not fit into
+    ///   `uint160`.
+    pub(crate) fn sqrt_price_x96(
+        &self,
+        currency0: Currency,
+        currency1: Currency,
+    ) -> Result<U160, Error> {
+        let overflow = || Error::AmountOverflow(AmountOverflow {});
+        let (numerator, denominator) = self.exchange_rate(currency0, currency1);
+        let price_x192 =
+            math::mul_div(numerator, U256::from(1) << 192, denominator).ok_or_else(overflow)?;
+        let sqrt_price_x96 = math::sqrt(price_x192);
+        if sqrt_price_x96.bit_len() > 160 {
+            return Err(overflow());
+        }
+        Ok(sqrt_price_x96.to::<U160>())
+    }
+
+    /// Returns the price of `currency0` in `currency1` as an 18-decimal
+    /// fixed-point number, rounded down.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If the price does not fit into
+    ///   `uint256`.
+    pub(crate) fn price_wad(
+        &self,
+        currency0: Currency,
+        currency1: Currency,
+    ) -> Result<U256, Error> {
+        let (numerator, denominator) = self.exchange_rate(currency0, currency1);
+        math::mul_div(numerator, math::WAD, denominator)
+            .ok_or(Error::AmountOverflow(AmountOverflow {}))
+    }
+}
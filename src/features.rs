@@ -0,0 +1,53 @@
+//! Bitmap of the optional modules compiled into a deployment.
+//!
+//! Each Cargo feature adding behavior to the curve has a bit, set in
+//! [`FEATURE_BITMAP`] when the feature is enabled. The constructor publishes
+//! the bitmap in `Deployed`, so explorers and scripts can tell what a given
+//! deployment was built with.
+//!
+//! `capabilities()` extends the bitmap with the subsystems routers may adapt
+//! to, in the upper byte: [`ORACLE`] and [`GOVERNANCE`] are part of every
+//! build of the curve. The curve has no limit orders or rewards, so
+//! [`LIMIT_ORDERS`] and [`REWARDS`] are reserved and never set.
+
+/// Gas usage telemetry: metered entrypoints emit `GasUsed`.
+pub const TELEMETRY: u32 = 1 << 0;
+
+/// Decision-path tracing: reverts carry the trace of the failed call.
+pub const TRACE: u32 = 1 << 1;
+
+/// Stylus debug host functions.
+pub const DEBUG: u32 = 1 << 2;
+
+/// ABI export of the contract's interface.
+pub const EXPORT_ABI: u32 = 1 << 3;
+
+/// Bits of the features enabled in this build.
+pub const FEATURE_BITMAP: u32 = bit(cfg!(feature = "telemetry"), TELEMETRY)
+    | bit(cfg!(feature = "trace"), TRACE)
+    | bit(cfg!(feature = "debug"), DEBUG)
+    | bit(cfg!(feature = "export-abi"), EXPORT_ABI);
+
+/// Push oracle: signed price reports and pools pegged to the oracle.
+pub const ORACLE: u32 = 1 << 8;
+
+/// Limit orders, reserved.
+pub const LIMIT_ORDERS: u32 = 1 << 9;
+
+/// Liquidity mining rewards, reserved.
+pub const REWARDS: u32 = 1 << 10;
+
+/// Governance: signed admin actions and the multisig.
+pub const GOVERNANCE: u32 = 1 << 11;
+
+/// Bits of the features and subsystems compiled into this build.
+pub const CAPABILITIES: u32 = FEATURE_BITMAP | ORACLE | GOVERNANCE;
+
+/// Returns `flag` if `enabled`, or zero.
+const fn bit(enabled: bool, flag: u32) -> u32 {
+    if enabled {
+        flag
+    } else {
+        0
+    }
+}
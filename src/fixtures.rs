@@ -0,0 +1,130 @@
+//! Deterministic JSON fixtures generated from the curve math, enabled by the
+//! `fixtures` feature.
+//!
+//! The workshop's TypeScript tests consume these fixtures, so every layer
+//! stays consistent with [`ConstantSumCurve::quote_with_state`] and
+//! [`ConstantSumCurve::quote_in_with_state`].
+use alloc::{string::String, vec::Vec};
+
+use alloy_primitives::{uint, U256};
+
+use crate::{ConstantSumCurve, Error};
+
+/// Reserves `(reserve0, reserve1)` to quote against.
+const RESERVES: [(U256, U256); 2] = [
+    (
+        uint!(1_000_000_000_000_000_000_000_U256),
+        uint!(1_000_000_000_000_000_000_000_U256),
+    ),
+    (uint!(1_000_U256), uint!(1_000_000_000_000_000_000_000_U256)),
+];
+
+/// Fees in basis points to quote with.
+const FEES_BPS: [u16; 6] = [0, 1, 5, 30, 100, 1_000];
+
+/// Exchange rates `(numerator, denominator)` of `token0` in `token1` to
+/// quote at.
+const RATES: [(U256, U256); 3] = [
+    (uint!(1_U256), uint!(1_U256)),
+    (uint!(3_U256), uint!(2_U256)),
+    (uint!(1_U256), uint!(1_000_U256)),
+];
+
+/// Amounts to quote.
+const AMOUNTS: [U256; 5] = [
+    uint!(0_U256),
+    uint!(1_U256),
+    uint!(999_U256),
+    uint!(1_000_U256),
+    uint!(1_000_000_000_000_000_000_U256),
+];
+
+/// Returns the fixtures as a JSON array.
+///
+/// Amounts and rates are encoded as decimal strings to be parsed as `bigint`s.
+/// Failing quotes have an `error` field with the name of the [`Error`]
+/// instead of an `expected` field.
+pub fn to_json() -> String {
+    let mut cases = Vec::new();
+    for (reserve0, reserve1) in RESERVES {
+        for fee_bps in FEES_BPS {
+            for (rate_numerator, rate_denominator) in RATES {
+                for amount in AMOUNTS {
+                    for zero_for_one in [true, false] {
+                        let case = Case {
+                            reserve0,
+                            reserve1,
+                            fee_bps,
+                            rate_numerator,
+                            rate_denominator,
+                            amount,
+                            zero_for_one,
+                        };
+                        cases.push(case.to_json(
+                            "exactInput",
+                            ConstantSumCurve::quote_with_state(
+                                reserve0,
+                                reserve1,
+                                fee_bps,
+                                rate_numerator,
+                                rate_denominator,
+                                amount,
+                                zero_for_one,
+                            ),
+                        ));
+                        cases.push(case.to_json(
+                            "exactOutput",
+                            ConstantSumCurve::quote_in_with_state(
+                                reserve0,
+                                reserve1,
+                                fee_bps,
+                                rate_numerator,
+                                rate_denominator,
+                                amount,
+                                zero_for_one,
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    format!("[\n{}\n]\n", cases.join(",\n"))
+}
+
+/// Inputs of a quote.
+struct Case {
+    reserve0: U256,
+    reserve1: U256,
+    fee_bps: u16,
+    rate_numerator: U256,
+    rate_denominator: U256,
+    amount: U256,
+    zero_for_one: bool,
+}
+
+impl Case {
+    /// Returns the case and the `result` of its quote as a JSON object.
+    fn to_json(&self, kind: &str, result: Result<U256, Error>) -> String {
+        let Self {
+            reserve0,
+            reserve1,
+            fee_bps,
+            rate_numerator,
+            rate_denominator,
+            amount,
+            zero_for_one,
+        } = self;
+        let outcome = match result {
+            Ok(expected) => format!(r#""expected": "{expected}""#),
+            Err(err) => {
+                let err = format!("{err:?}");
+                let name = err.split('(').next().unwrap_or_default();
+                format!(r#""error": "{name}""#)
+            }
+        };
+        format!(
+            r#"  {{ "kind": "{kind}", "reserve0": "{reserve0}", "reserve1": "{reserve1}", "feeBps": {fee_bps}, "rateNumerator": "{rate_numerator}", "rateDenominator": "{rate_denominator}", "amount": "{amount}", "zeroForOne": {zero_for_one}, {outcome} }}"#
+        )
+    }
+}
@@ -0,0 +1,133 @@
+//! Solidity test harness for the exported ABI.
+//!
+//! Prints the contract's interface followed by a mock caller contract, so
+//! Foundry tests can integrate with a deployed curve without hand-written
+//! bindings.
+//!
+//! NOTE: [`CALLER`] mirrors the public functions of `ConstantSumCurve` and
+//! must be updated together with them. [`missing_functions`] lists the ones
+//! it calls that are no longer exported, and tests check that it is empty.
+use core::{fmt, marker::PhantomData};
+
+use stylus_sdk::abi::{export, GenerateAbi};
+
+use crate::ConstantSumCurve;
+
+/// License of the generated Solidity sources.
+const LICENSE: &str = "// SPDX-License-Identifier: MIT-OR-APACHE-2.0";
+
+/// Pragma of the generated Solidity sources.
+const PRAGMA: &str = "pragma solidity ^0.8.23;";
+
+/// Mock contract forwarding calls to a deployed curve and recording the
+/// results.
+const CALLER: &str = r#"
+/// @notice Forwards calls to a deployed `ConstantSumCurve` and records the
+/// latest results, for use in Foundry integration tests.
+contract ConstantSumCurveCaller {
+    IConstantSumCurve public immutable curve;
+
+    uint256 public lastAmountIn;
+    uint256 public lastAmountOut;
+
+    constructor(IConstantSumCurve curve_) {
+        curve = curve_;
+    }
+
+    function version() external view returns (string memory) {
+        return curve.version();
+    }
+
+    function getAmountInForExactOutput(
+        uint256 amountOut,
+        address input,
+        address output,
+        bool zeroForOne
+    ) external returns (uint256 amountIn) {
+        amountIn = curve.getAmountInForExactOutput(amountOut, input, output, zeroForOne);
+        lastAmountIn = amountIn;
+    }
+
+    function getAmountOutFromExactInput(
+        uint256 amountIn,
+        address input,
+        address output,
+        bool zeroForOne
+    ) external returns (uint256 amountOut) {
+        amountOut = curve.getAmountOutFromExactInput(amountIn, input, output, zeroForOne);
+        lastAmountOut = amountOut;
+    }
+
+    function quoteWithState(
+        uint256 reserve0,
+        uint256 reserve1,
+        uint16 feeBps,
+        uint256 rateNumerator,
+        uint256 rateDenominator,
+        uint256 amountIn,
+        bool zeroForOne
+    ) external view returns (uint256) {
+        return curve.quoteWithState(
+            reserve0, reserve1, feeBps, rateNumerator, rateDenominator, amountIn, zeroForOne
+        );
+    }
+
+    function quoteInWithState(
+        uint256 reserve0,
+        uint256 reserve1,
+        uint16 feeBps,
+        uint256 rateNumerator,
+        uint256 rateDenominator,
+        uint256 amountOut,
+        bool zeroForOne
+    ) external view returns (uint256) {
+        return curve.quoteInWithState(
+            reserve0, reserve1, feeBps, rateNumerator, rateDenominator, amountOut, zeroForOne
+        );
+    }
+}
+"#;
+
+/// Prints the contract's Solidity interface and the mock caller contract
+/// as a single Foundry-compatible source file.
+pub fn print_harness() {
+    export::print_abi::<ConstantSumCurve>(LICENSE, PRAGMA);
+    println!("{CALLER}");
+}
+
+/// Returns the functions of [`CALLER`] missing from the exported interface
+/// of `ConstantSumCurve`, as `name(type,...)` signatures.
+pub fn missing_functions() -> Vec<String> {
+    let exported = signatures(&Interface::<ConstantSumCurve>(PhantomData).to_string());
+    signatures(CALLER)
+        .into_iter()
+        .filter(|signature| !exported.contains(signature))
+        .collect()
+}
+
+/// Exported Solidity interface of `T`.
+struct Interface<T>(PhantomData<T>);
+
+impl<T: GenerateAbi> fmt::Display for Interface<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt_abi(f)
+    }
+}
+
+/// Returns the signatures of the functions declared in the Solidity
+/// `source`, keeping only the type of each parameter.
+fn signatures(source: &str) -> Vec<String> {
+    source
+        .split("function ")
+        .skip(1)
+        .filter_map(|declaration| {
+            let (name, rest) = declaration.split_once('(')?;
+            let (params, _) = rest.split_once(')')?;
+            let types: Vec<_> = params
+                .split(',')
+                .filter_map(|param| param.split_whitespace().next())
+                .collect();
+            Some(format!("{}({})", name.trim(), types.join(",")))
+        })
+        .collect()
+}
@@ -0,0 +1,194 @@
+//! Uniswap V4 hook callbacks.
+//!
+//! Wired as a v4 hook, the curve replaces the pool's concentrated-liquidity
+//! math: `beforeSwap` prices the swap and returns a [`BeforeSwapDelta`]
+//! consuming the whole specified amount, so the pool manager settles the
+//! swap against the hook instead of the pool. `afterSwap` then reads the
+//! swap's [`BalanceDelta`] for accounting.
+//!
+//! Both deltas pack two `int128` values into an `int256`, the first one in
+//! the upper 128 bits.
+//!
+//! The pool manager only calls the hooks whose flag is set in the lowest 14
+//! bits of the hook's address, so the curve must be deployed at an address
+//! encoding [`hook_permissions`].
+//!
+//! [`BeforeSwapDelta`]: https://github.com/Uniswap/v4-core/blob/main/src/types/BeforeSwapDelta.sol
+//! [`BalanceDelta`]: https://github.com/Uniswap/v4-core/blob/main/src/types/BalanceDelta.sol
+use alloy_primitives::{fixed_bytes, Address, FixedBytes, I256, U256};
+
+use crate::{AmountOverflow, Error, HookAddressNotValid, HookPermissions};
+
+/// Flag of the `beforeInitialize` hook in the hook's address.
+pub const BEFORE_INITIALIZE_FLAG: u16 = 1 << 13;
+/// Flag of the `afterInitialize` hook in the hook's address.
+pub const AFTER_INITIALIZE_FLAG: u16 = 1 << 12;
+/// Flag of the `beforeAddLiquidity` hook in the hook's address.
+pub const BEFORE_ADD_LIQUIDITY_FLAG: u16 = 1 << 11;
+/// Flag of the `afterAddLiquidity` hook in the hook's address.
+pub const AFTER_ADD_LIQUIDITY_FLAG: u16 = 1 << 10;
+/// Flag of the `beforeRemoveLiquidity` hook in the hook's address.
+pub const BEFORE_REMOVE_LIQUIDITY_FLAG: u16 = 1 << 9;
+/// Flag of the `afterRemoveLiquidity` hook in the hook's address.
+pub const AFTER_REMOVE_LIQUIDITY_FLAG: u16 = 1 << 8;
+/// Flag of the `beforeSwap` hook in the hook's address.
+pub const BEFORE_SWAP_FLAG: u16 = 1 << 7;
+/// Flag of the `afterSwap` hook in the hook's address.
+pub const AFTER_SWAP_FLAG: u16 = 1 << 6;
+/// Flag of the `beforeDonate` hook in the hook's address.
+pub const BEFORE_DONATE_FLAG: u16 = 1 << 5;
+/// Flag of the `afterDonate` hook in the hook's address.
+pub const AFTER_DONATE_FLAG: u16 = 1 << 4;
+/// Flag allowing `beforeSwap` to return a delta.
+pub const BEFORE_SWAP_RETURNS_DELTA_FLAG: u16 = 1 << 3;
+/// Flag allowing `afterSwap` to return a delta.
+pub const AFTER_SWAP_RETURNS_DELTA_FLAG: u16 = 1 << 2;
+/// Flag allowing `afterAddLiquidity` to return a delta.
+pub const AFTER_ADD_LIQUIDITY_RETURNS_DELTA_FLAG: u16 = 1 << 1;
+/// Flag allowing `afterRemoveLiquidity` to return a delta.
+pub const AFTER_REMOVE_LIQUIDITY_RETURNS_DELTA_FLAG: u16 = 1;
+
+/// Mask of all hook flags in the hook's address.
+pub const ALL_HOOK_MASK: u16 = (1 << 14) - 1;
+
+/// `IHooks.beforeInitialize.selector`, returned by `beforeInitialize` to
+/// acknowledge the call.
+pub const BEFORE_INITIALIZE_SELECTOR: FixedBytes<4> = fixed_bytes!("dc98354e");
+
+/// `IHooks.afterInitialize.selector`, returned by `afterInitialize` to
+/// acknowledge the call.
+pub const AFTER_INITIALIZE_SELECTOR: FixedBytes<4> = fixed_bytes!("6fe7e6eb");
+
+/// `IHooks.beforeSwap.selector`, returned by `beforeSwap` to acknowledge the
+/// call.
+pub const BEFORE_SWAP_SELECTOR: FixedBytes<4> = fixed_bytes!("575e24b4");
+
+/// `IHooks.afterSwap.selector`, returned by `afterSwap` to acknowledge the
+/// call.
+pub const AFTER_SWAP_SELECTOR: FixedBytes<4> = fixed_bytes!("b47b2fb1");
+
+/// Packs the delta of the specified and unspecified currencies into a
+/// `BeforeSwapDelta`.
+pub fn to_before_swap_delta(specified: i128, unspecified: i128) -> I256 {
+    pack(specified, unspecified)
+}
+
+/// Returns the delta of the specified currency of a `BeforeSwapDelta`.
+pub fn specified_delta(delta: I256) -> i128 {
+    upper(delta)
+}
+
+/// Returns the delta of the unspecified currency of a `BeforeSwapDelta`.
+pub fn unspecified_delta(delta: I256) -> i128 {
+    lower(delta)
+}
+
+/// Packs the amounts of `currency0` and `currency1` owed to the swapper
+/// into a `BalanceDelta`.
+pub fn to_balance_delta(amount0: i128, amount1: i128) -> I256 {
+    pack(amount0, amount1)
+}
+
+/// Returns the amounts of `currency0` and `currency1` of a `BalanceDelta`.
+pub fn balance_delta_amounts(delta: I256) -> (i128, i128) {
+    (upper(delta), lower(delta))
+}
+
+/// Packs `upper` and `lower` into an `int256`.
+fn pack(upper: i128, lower: i128) -> I256 {
+    I256::from_raw((U256::from(upper as u128) << 128) | U256::from(lower as u128))
+}
+
+/// Returns the upper 128 bits of `delta`.
+fn upper(delta: I256) -> i128 {
+    (delta.into_raw() >> 128usize).to::<u128>() as i128
+}
+
+/// Returns the lower 128 bits of `delta`.
+fn lower(delta: I256) -> i128 {
+    (delta.into_raw() & U256::from(u128::MAX)).to::<u128>() as i128
+}
+
+/// Returns the hooks implemented by the curve, like v4's
+/// `BaseHook.getHookPermissions`.
+pub fn hook_permissions() -> HookPermissions {
+    HookPermissions {
+        before_initialize: true,
+        after_initialize: true,
+        before_add_liquidity: true,
+        after_add_liquidity: false,
+        before_remove_liquidity: true,
+        after_remove_liquidity: false,
+        before_swap: true,
+        after_swap: true,
+        before_donate: false,
+        after_donate: false,
+        before_swap_return_delta: true,
+        after_swap_return_delta: true,
+        after_add_liquidity_return_delta: false,
+        after_remove_liquidity_return_delta: false,
+    }
+}
+
+/// Returns the address flags encoding `permissions`.
+pub fn permission_flags(permissions: &HookPermissions) -> u16 {
+    [
+        (permissions.before_initialize, BEFORE_INITIALIZE_FLAG),
+        (permissions.after_initialize, AFTER_INITIALIZE_FLAG),
+        (permissions.before_add_liquidity, BEFORE_ADD_LIQUIDITY_FLAG),
+        (permissions.after_add_liquidity, AFTER_ADD_LIQUIDITY_FLAG),
+        (
+            permissions.before_remove_liquidity,
+            BEFORE_REMOVE_LIQUIDITY_FLAG,
+        ),
+        (
+            permissions.after_remove_liquidity,
+            AFTER_REMOVE_LIQUIDITY_FLAG,
+        ),
+        (permissions.before_swap, BEFORE_SWAP_FLAG),
+        (permissions.after_swap, AFTER_SWAP_FLAG),
+        (permissions.before_donate, BEFORE_DONATE_FLAG),
+        (permissions.after_donate, AFTER_DONATE_FLAG),
+        (
+            permissions.before_swap_return_delta,
+            BEFORE_SWAP_RETURNS_DELTA_FLAG,
+        ),
+        (
+            permissions.after_swap_return_delta,
+            AFTER_SWAP_RETURNS_DELTA_FLAG,
+        ),
+        (
+            permissions.after_add_liquidity_return_delta,
+            AFTER_ADD_LIQUIDITY_RETURNS_DELTA_FLAG,
+        ),
+        (
+            permissions.after_remove_liquidity_return_delta,
+            AFTER_REMOVE_LIQUIDITY_RETURNS_DELTA_FLAG,
+        ),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| *enabled)
+    .fold(0, |flags, (_, flag)| flags | flag)
+}
+
+/// Checks that the flags encoded in `hook` match [`hook_permissions`].
+///
+/// # Errors
+///
+/// * [`Error::HookAddressNotValid`] - If the flags of `hook` differ.
+pub fn check_hook_address(hook: Address) -> Result<(), Error> {
+    let flags = u16::from_be_bytes([hook[18], hook[19]]) & ALL_HOOK_MASK;
+    if flags != permission_flags(&hook_permissions()) {
+        return Err(Error::HookAddressNotValid(HookAddressNotValid { hook }));
+    }
+    Ok(())
+}
+
+/// Returns `amount` as an `int128` delta.
+///
+/// # Errors
+///
+/// * [`Error::AmountOverflow`] - If `amount` does not fit into `int128`.
+pub(crate) fn to_int128(amount: U256) -> Result<i128, Error> {
+    i128::try_from(amount).map_err(|_| Error::AmountOverflow(AmountOverflow {}))
+}
@@ -0,0 +1,142 @@
+//! Storage layout of `ConstantSumCurve`, enabled by the `storage-layout`
+//! feature.
+//!
+//! Upgrader tooling and auditors compare the exported layouts of two
+//! versions to check that they are compatible. Slots are assigned like the
+//! `#[storage]` macro does: fields are laid out in declaration order, values
+//! smaller than a word are packed into the current slot while they fit, and
+//! nested storage structs start at a fresh slot and span their
+//! `REQUIRED_SLOTS`. Offsets count bytes from the lowest-order end of the
+//! slot, as in `solc`'s storage layout.
+//!
+//! NOTE: [`FIELDS`] mirrors the fields of `ConstantSumCurve` and must be
+//! updated together with them.
+use alloc::{string::String, vec::Vec};
+
+use alloy_primitives::{aliases::U64, Address, B256, U256};
+use stylus_sdk::storage::{
+    StorageAddress, StorageB256, StorageBool, StorageMap, StorageString, StorageType, StorageU16,
+    StorageU256, StorageU64, StorageU8, StorageVec,
+};
+
+use crate::{
+    bootstrap::Bootstrap, changelog::ChangeRecord, exchange_rate::ExchangeRate, multisig::Proposal,
+    oracle::Report, pool::RegisteredPool, Currency,
+};
+
+/// Size of a storage slot in bytes.
+const WORD_BYTES: usize = 32;
+
+/// A storage field: its name, its size within a slot in bytes, and the
+/// number of slots it spans if it is a nested storage struct.
+type Field = (&'static str, usize, usize);
+
+/// Returns the [`Field`] `name` of type `T`.
+const fn field<T: StorageType>(name: &'static str) -> Field {
+    (name, T::SLOT_BYTES, T::REQUIRED_SLOTS)
+}
+
+/// Fields of `ConstantSumCurve`, in declaration order.
+const FIELDS: [Field; 49] = [
+    field::<StorageString>("version"),
+    field::<StorageAddress>("owner"),
+    field::<StorageU8>("currency_policy"),
+    field::<StorageMap<Currency, StorageBool>>("listed_currencies"),
+    field::<StorageU256>("min_swap_amount"),
+    field::<StorageBool>("router_only"),
+    field::<StorageMap<Address, StorageBool>>("routers"),
+    field::<StorageMap<U64, ChangeRecord>>("changelog"),
+    field::<StorageU64>("change_count"),
+    field::<StorageU64>("decommission_eta"),
+    field::<StorageBool>("decommissioned"),
+    field::<StorageU256>("admin_nonce"),
+    field::<StorageMap<Address, StorageBool>>("signers"),
+    field::<StorageU8>("signer_count"),
+    field::<StorageU8>("threshold"),
+    field::<StorageMap<U256, Proposal>>("proposals"),
+    field::<StorageU256>("proposal_count"),
+    field::<StorageAddress>("guardian"),
+    field::<StorageU64>("guardian_sunset"),
+    field::<StorageBool>("paused"),
+    field::<StorageBool>("shadow_mode"),
+    field::<StorageU16>("shadow_fee_bps"),
+    field::<StorageMap<Address, StorageBool>>("reporters"),
+    field::<StorageVec<StorageAddress>>("reporter_list"),
+    field::<StorageMap<Address, Report>>("reports"),
+    field::<StorageU16>("max_deviation_bps"),
+    field::<StorageU64>("max_price_age"),
+    field::<StorageU256>("price"),
+    field::<StorageU64>("price_timestamp"),
+    field::<ExchangeRate>("default_rate"),
+    field::<StorageMap<Currency, StorageMap<Currency, ExchangeRate>>>("exchange_rates"),
+    field::<StorageU16>("fee_bps"),
+    field::<StorageU256>("reserve0"),
+    field::<StorageU256>("reserve1"),
+    field::<Bootstrap>("bootstrap"),
+    field::<StorageU16>("after_swap_fee_bps"),
+    field::<StorageMap<B256, RegisteredPool>>("pools"),
+    field::<StorageAddress>("pool_manager"),
+    field::<StorageBool>("claims_mode"),
+    field::<StorageMap<Currency, StorageU256>>("claim_balances"),
+    field::<StorageAddress>("fee_manager"),
+    field::<StorageAddress>("currency0"),
+    field::<StorageAddress>("currency1"),
+    field::<StorageU256>("total_shares"),
+    field::<StorageMap<Address, StorageU256>>("shares"),
+    field::<StorageBool>("self_test_passed"),
+    field::<StorageMap<Address, StorageMap<Address, StorageU256>>>("allowances"),
+    field::<StorageU8>("shadow_curve"),
+    field::<StorageMap<B256, StorageB256>>("pool_index"),
+];
+
+/// Slot assignment of a storage field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Slot {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The slot of the field, or its first slot if it spans several.
+    pub slot: usize,
+    /// The offset of the field within its slot, in bytes from the
+    /// lowest-order end.
+    pub offset: usize,
+    /// The size of the field in bytes.
+    pub bytes: usize,
+}
+
+/// Returns the slot assignments of the fields of `ConstantSumCurve`.
+pub fn storage_layout() -> Vec<Slot> {
+    let mut layout = Vec::with_capacity(FIELDS.len());
+    let mut slot = 0;
+    let mut space = WORD_BYTES;
+    for (name, bytes, slots) in FIELDS {
+        if space < bytes {
+            space = WORD_BYTES;
+            slot += 1;
+        }
+        space -= bytes;
+        layout.push(Slot {
+            name,
+            slot,
+            offset: WORD_BYTES - space - bytes,
+            bytes: if slots > 0 { slots * WORD_BYTES } else { bytes },
+        });
+        if slots > 0 {
+            slot += slots;
+            space = WORD_BYTES;
+        }
+    }
+    layout
+}
+
+/// Returns the storage layout as a JSON array.
+pub fn to_json() -> String {
+    let slots: Vec<_> = storage_layout()
+        .into_iter()
+        .map(|Slot { name, slot, offset, bytes }| {
+            format!(
+                r#"  {{ "label": "{name}", "slot": {slot}, "offset": {offset}, "bytes": {bytes} }}"#
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", slots.join(",\n"))
+}
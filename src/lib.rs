@@ -7,14 +7,89 @@
 #[macro_use]
 extern crate alloc;
 
+#[cfg(any(
+    all(curve = "constant-product", curve = "stable-swap"),
+    all(curve = "constant-product", curve = "weighted"),
+    all(curve = "stable-swap", curve = "weighted"),
+))]
+compile_error!("only one `curve` can be selected");
+
 use alloc::string::String;
 use alloc::vec::Vec;
 
-use alloy_primitives::{Address, U256};
-use alloy_sol_types::sol;
-use stylus_sdk::{evm, prelude::*, storage::StorageString};
+use admin::IAdmin;
+use alloy_primitives::{
+    aliases::{I24, U16, U160, U24, U64, U8},
+    uint, Address, FixedBytes, B256, I256, U256,
+};
+use alloy_sol_types::{sol, SolValue};
+use bootstrap::{Bootstrap, BootstrapView, Phase};
+use bounds::BoundsView;
+use changelog::{Change, ChangeKind, ChangeRecord};
+use constant_product::ConstantProductCurve;
+use exchange_rate::ExchangeRate;
+use lifecycle::PoolState;
+use liquidity::ILiquidity;
+use lp_token::ILpToken;
+use multisig::{IMultisig, Proposal};
+use oracle::{IOracle, Report};
+use pool::{IPool, RegisteredPool};
+use pool_manager::UnlockAction;
+use quoter::IQuoter;
+use stylus_sdk::{
+    abi::Bytes,
+    evm,
+    prelude::*,
+    storage::{
+        StorageAddress, StorageB256, StorageBool, StorageMap, StorageString, StorageU16,
+        StorageU256, StorageU64, StorageU8, StorageVec,
+    },
+};
+use weighted::WeightedCurve;
+
+#[macro_use]
+mod telemetry;
+#[macro_use]
+mod trace;
+
+pub mod admin;
+pub mod bootstrap;
+pub mod bounds;
+pub mod changelog;
+pub mod constant_product;
+pub mod currency;
+pub mod exchange_rate;
+pub mod features;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(feature = "export-abi")]
+pub mod harness;
+pub mod hooks;
+#[cfg(any(test, feature = "storage-layout"))]
+pub mod layout;
+pub mod lifecycle;
+pub mod liquidity;
+pub mod lp_token;
+pub mod math;
+#[cfg(test)]
+mod mocks;
+pub mod multisig;
+pub mod oracle;
+pub mod pool;
+pub mod pool_manager;
+pub mod quoter;
+pub mod reentrancy;
+#[cfg(test)]
+mod scenarios;
+pub mod self_test;
+pub mod signature;
+pub mod stable_swap;
+#[cfg(test)]
+mod testing;
+pub mod weighted;
 
-/// The currency data type.
+/// The currency data type: an ERC-20 token, or native ETH as
+/// [`currency::NATIVE`].
 pub type Currency = Address;
 
 sol! {
@@ -39,269 +114,6641 @@ sol! {
         address output,
         bool zero_for_one
     );
-}
 
-sol! {
-    /// Indicates a custom error.
+    /// Emitted when the currency policy is changed.
+    #[allow(missing_docs)]
     #[derive(Debug)]
+    event CurrencyPolicySet(uint64 indexed op_seq, uint8 policy);
+
+    /// Emitted when a currency is added to or removed from the currency
+    /// list.
     #[allow(missing_docs)]
-    error CurveCustomError();
-}
+    #[derive(Debug)]
+    event CurrencyListed(uint64 indexed op_seq, address indexed currency, bool listed);
 
-#[derive(SolidityError, Debug)]
-pub enum Error {
-    /// Indicates a custom error.
-    CustomError(CurveCustomError),
-}
-#[storage]
-#[entrypoint]
-struct ConstantSumCurve {
-    version: StorageString,
-}
+    /// Emitted when the minimum swap amount is changed.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event MinSwapAmountSet(uint64 indexed op_seq, uint256 min_swap_amount);
 
-/// Interface of an [`UniswapCurve`] contract.
-///
-/// NOTE: The contract's interface can be modified in any way.
-pub trait IUniswapV4Curve {
-    /// The error type associated to the trait implementation.
-    type Error: Into<alloc::vec::Vec<u8>>;
+    /// Emitted when the router-only mode is enabled or disabled.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event RouterOnlySet(uint64 indexed op_seq, bool enabled);
 
-    /// Returns the version of the curve.
-    ///
-    /// # Arguments
-    ///
-    /// * `&self` - Read access to the contract's state.
-    fn version(&self) -> String;
+    /// Emitted when a router is added to or removed from the router
+    /// whitelist.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event RouterSet(uint64 indexed op_seq, address indexed router, bool allowed);
 
-    /// Returns the amount of input tokens for an exact-output swap.
-    ///
-    /// # Arguments
-    ///
-    /// * `&mut self` - Write access to the contract's state.
-    /// * `amount_out` the amount of output tokens the user expects to receive.
-    /// * `input` - The input token.
-    /// * `output` - The output token.
-    /// * `zero_for_one` - True if the input token is token0.
-    ///
-    /// # Errors
-    ///
-    /// May return an [`Error`].
-    ///
-    /// # Events
-    ///
-    /// May emit any event.
-    fn get_amount_in_for_exact_output(
-        &mut self,
-        amount_out: U256,
-        input: Currency,
-        output: Currency,
-        zero_for_one: bool,
-    ) -> Result<U256, Self::Error>;
+    /// Emitted when the decommission is scheduled for `eta`, or cancelled if
+    /// `eta` is zero.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event DecommissionScheduled(uint64 indexed op_seq, uint64 eta);
 
-    /// Returns the amount of output tokens for an exact-input swap.
-    ///
-    /// # Arguments
-    ///
-    /// * `&mut self` - Write access to the contract's state.
-    /// * `amount_in` - The amount of input tokens.
-    /// * `input` - The input token.
-    /// * `output` - The output token.
-    /// * `zero_for_one` - True if the input token is `token_0`.
-    ///
-    /// # Errors
-    ///
-    /// May return an [`Error`].
-    ///
-    /// # Events
-    ///
-    /// May emit any event.
-    fn get_amount_out_from_exact_input(
-        &mut self,
-        amount_in: U256,
-        input: Currency,
-        output: Currency,
-        zero_for_one: bool,
-    ) -> Result<U256, Self::Error>;
-}
+    /// Emitted once the contract is decommissioned, with a snapshot of its
+    /// final state.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event Decommissioned(
+        uint64 indexed op_seq,
+        string version,
+        address owner,
+        uint8 currency_policy,
+        uint256 min_swap_amount,
+        bool router_only
+    );
 
-#[public]
-#[implements(IUniswapV4Curve<Error = Error>)]
-impl ConstantSumCurve {
-    #[constructor]
-    pub fn constructor(&mut self, version: String) {
-        self.version.set_str(version);
-    }
-}
+    /// Emitted when the ownership is transferred.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event OwnershipTransferred(
+        uint64 indexed op_seq,
+        address indexed previous_owner,
+        address indexed new_owner
+    );
 
-#[public]
-impl IUniswapV4Curve for ConstantSumCurve {
-    type Error = Error;
+    /// Emitted when a signer is added to or removed from the multisig.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event SignerSet(uint64 indexed op_seq, address indexed signer, bool enabled);
 
-    fn version(&self) -> String {
-        self.version.get_string()
-    }
+    /// Emitted when the multisig threshold is changed.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event ThresholdSet(uint64 indexed op_seq, uint8 threshold);
 
-    fn get_amount_in_for_exact_output(
-        &mut self,
-        amount_out: U256,
-        input: Currency,
-        output: Currency,
-        zero_for_one: bool,
-    ) -> Result<U256, Self::Error> {
-        // Calculate `amount_in` based on swap params.
-        let amount_in = self.calculate_amount_in(amount_out, input, output, zero_for_one);
+    /// Emitted when a signer proposes a critical action.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event CriticalActionProposed(
+        uint256 indexed id,
+        address indexed proposer,
+        uint8 action,
+        address subject,
+        uint256 value
+    );
 
-        #[allow(deprecated)]
-        evm::log(AmountInCalculated {
-            amount_out,
-            input,
-            output,
-            zero_for_one,
-        });
+    /// Emitted when a signer approves a critical action.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event CriticalActionApproved(uint256 indexed id, address indexed signer, uint8 approvals);
 
-        Ok(amount_in)
-    }
+    /// Emitted when a critical action is executed.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event CriticalActionExecuted(uint256 indexed id);
 
-    fn get_amount_out_from_exact_input(
-        &mut self,
-        amount_in: U256,
-        input: Currency,
-        output: Currency,
-        zero_for_one: bool,
-    ) -> Result<U256, Self::Error> {
-        let amount_out = self.calculate_amount_out(amount_in, input, output, zero_for_one);
+    /// Emitted when the guardian or its sunset is changed.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event GuardianSet(uint64 indexed op_seq, address indexed guardian, uint64 sunset);
 
-        #[allow(deprecated)]
-        evm::log(AmountOutCalculated {
-            amount_in,
-            input,
-            output,
-            zero_for_one,
-        });
+    /// Emitted when `account` pauses swaps.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event Paused(uint64 indexed op_seq, address indexed account);
 
-        Ok(amount_out)
-    }
-}
+    /// Emitted when `account` unpauses swaps.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event Unpaused(uint64 indexed op_seq, address indexed account);
 
-impl ConstantSumCurve {
-    /// Calculates the amount of input tokens for an exact-output swap.
-    ///
-    /// # Arguments
-    ///
-    /// * `&self` - Read access to the contract's state.
-    /// * `amount_out` the amount of output tokens the user expects to receive.
-    /// * `input` - The input token.
-    /// * `output` - The output token.
-    /// * `zero_for_one` - True if the input token is `token0`.
-    fn calculate_amount_in(
-        &self,
-        amount_out: U256,
-        _input: Currency,
-        _output: Currency,
-        _zero_for_one: bool,
-    ) -> U256 {
-        // In constant-sum curve, tokens trade exactly 1:1
-        let amount_in = amount_out;
+    /// Emitted when the shadow mode is enabled or disabled.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event ShadowModeSet(uint64 indexed op_seq, bool enabled, uint8 curve, uint16 fee_bps);
 
-        amount_in
-    }
+    /// Emitted on each swap in the pool `pool_id` while the shadow mode is
+    /// enabled, comparing the quote of the active curve with the quote of
+    /// the candidate one.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event ShadowQuoteDiff(
+        bytes32 indexed pool_id,
+        bool exact_input,
+        uint256 amount,
+        uint256 active,
+        uint256 shadow
+    );
 
-    /// Returns the amount of output tokens for an exact-input swap.
-    ///
-    /// # Arguments
-    ///
-    /// * `&mut self` - Write access to the contract's state.
-    /// * `amount_in` - The amount of input tokens.
-    /// * `input` - The input token.
-    /// * `output` - The output token.
-    /// * `zero_for_one` - True if the input token is `token_0`.
-    fn calculate_amount_out(
-        &self,
-        amount_in: U256,
-        _input: Currency,
-        _output: Currency,
-        _zero_for_one: bool,
-    ) -> U256 {
-        // in constant-sum curve, tokens trade exactly 1:1
-        let amount_out = amount_in;
+    /// Emitted when a price reporter is registered or unregistered.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event ReporterSet(uint64 indexed op_seq, address indexed reporter, bool allowed);
 
-        amount_out
-    }
-}
+    /// Emitted when the maximum age of a price is changed.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event MaxPriceAgeSet(uint64 indexed op_seq, uint64 max_price_age);
 
-/// Unit tests
-#[cfg(test)]
-mod tests {
-    use alloy_primitives::{address, uint, Address};
-    use motsu::prelude::Contract;
+    /// Emitted when a signed price report is posted.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event PricePosted(address indexed reporter, uint256 price, uint64 timestamp);
 
-    use super::*;
+    /// Emitted when the aggregate price is updated from the reports of
+    /// `reporters` reporters.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event PriceAggregated(uint256 price, uint64 timestamp, uint8 reporters);
 
-    const CURRENCY_1: Address = address!("A11CEacF9aa32246d767FCCD72e02d6bCbcC375d");
-    const CURRENCY_2: Address = address!("B0B0cB49ec2e96DF5F5fFB081acaE66A2cBBc2e2");
+    /// Emitted when the maximum deviation of a report from the median is
+    /// changed.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event MaxDeviationSet(uint64 indexed op_seq, uint16 max_deviation_bps);
 
-    #[test]
-    fn sample_test() {
-        assert_eq!(4, 2 + 2);
-    }
+    /// Emitted when the exchange rate of a pair is set, or unset if
+    /// `numerator` and `denominator` are zero.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event ExchangeRateSet(
+        uint64 indexed op_seq,
+        address indexed currency0,
+        address indexed currency1,
+        uint256 numerator,
+        uint256 denominator
+    );
 
-    #[motsu::test]
-    fn calculates_amount_in(contract: Contract<ConstantSumCurve>, alice: Address) {
-        let amount_out = uint!(1_U256);
-        let expected_amount_in = amount_out; // 1:1 swap
-        let amount_in = contract
-            .sender(alice)
-            .calculate_amount_in(amount_out, CURRENCY_1, CURRENCY_2, true);
-        assert_eq!(expected_amount_in, amount_in);
-    }
+    /// Emitted when a swap fee of `fee` units of `currency` is charged.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event FeeCharged(address indexed currency, uint256 fee);
 
-    #[motsu::test]
-    fn calculates_amount_out(contract: Contract<ConstantSumCurve>, alice: Address) {
-        let amount_in = uint!(2_U256);
-        let expected_amount_out = amount_in; // 1:1 swap
-        let amount_out = contract
-            .sender(alice)
-            .calculate_amount_out(amount_in, CURRENCY_1, CURRENCY_2, true);
-        assert_eq!(expected_amount_out, amount_out);
-    }
+    /// Emitted when the reserves are set by the owner.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event ReservesSet(uint64 indexed op_seq, uint256 reserve0, uint256 reserve1);
 
-    #[motsu::test]
-    fn returns_amount_in_for_exact_output(contract: Contract<ConstantSumCurve>, alice: Address) {
-        let amount_out = uint!(1_U256);
-        let expected_amount_in = amount_out; // 1:1 swap
-        let zero_for_one = true;
-        let amount_in = contract
-            .sender(alice)
-            .get_amount_in_for_exact_output(amount_out, CURRENCY_1, CURRENCY_2, zero_for_one)
-            .expect("should calculate `amount_in`");
-        assert_eq!(expected_amount_in, amount_in);
+    /// Emitted when a bootstrap auction is scheduled.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event BootstrapScheduled(
+        uint64 indexed op_seq,
+        uint64 start,
+        uint64 end,
+        uint256 start_weight,
+        uint256 end_weight
+    );
 
-        // Assert emitted events.
-        contract.assert_emitted(&AmountInCalculated {
-            amount_out,
-            input: CURRENCY_1,
-            output: CURRENCY_2,
-            zero_for_one,
-        });
-    }
+    /// Emitted when a scheduled bootstrap auction is cancelled.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event BootstrapCancelled(uint64 indexed op_seq);
+
+    /// Emitted when the pool moves to another lifecycle state.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event PoolStateChanged(uint64 indexed op_seq, uint8 from, uint8 to);
+
+    /// Emitted when the pool manager initializes the pool served by the
+    /// hook.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event PoolRegistered(
+        bytes32 indexed pool_id,
+        address indexed currency0,
+        address indexed currency1,
+        uint24 fee,
+        int24 tick_spacing
+    );
+
+    /// Emitted when the fee taken after each swap is set.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event AfterSwapFeeSet(uint64 indexed op_seq, uint16 fee_bps);
+
+    /// Emitted after a swap of the pool manager, with the amounts owed to
+    /// the swapper and the fee taken by the hook.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event SwapAccounted(bytes32 indexed pool_id, int128 amount0, int128 amount1, uint256 fee);
+
+    /// Emitted when the reserves of a registered pool are set.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event PoolReservesSet(
+        uint64 indexed op_seq,
+        bytes32 indexed pool_id,
+        uint256 reserve0,
+        uint256 reserve1
+    );
+
+    /// Emitted when the swap fee of a registered pool is set.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event PoolFeeSet(uint64 indexed op_seq, bytes32 indexed pool_id, uint16 fee_bps);
+
+    /// Emitted when swaps in a registered pool are paused or unpaused.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event PoolPausedSet(uint64 indexed op_seq, bytes32 indexed pool_id, bool paused);
+
+    /// Emitted when the hook runs a pool operation in `unlockCallback`,
+    /// with the `BalanceDelta` it cleared.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event UnlockActionExecuted(bytes32 indexed pool_id, uint8 action, int256 delta);
+
+    /// Emitted when the hook starts or stops holding its reserves as
+    /// ERC-6909 claims of the pool manager.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event ClaimsModeSet(uint64 indexed op_seq, bool enabled);
+
+    /// Emitted when the fee manager is changed.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event FeeManagerSet(uint64 indexed op_seq, address indexed fee_manager);
+
+    /// Emitted when the maximum price age of a registered pool is changed.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event PoolMaxPriceAgeSet(uint64 indexed op_seq, bytes32 indexed pool_id, uint64 max_price_age);
+
+    /// Emitted when the currencies of the default pool are set.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event CurrenciesSet(uint64 indexed op_seq, address currency0, address currency1);
+
+    /// Emitted when `provider` deposits `amount0` and `amount1` into the
+    /// default pool for `shares` LP shares.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event LiquidityAdded(address indexed provider, uint256 amount0, uint256 amount1, uint256 shares);
+
+    /// Emitted when `provider` burns `shares` LP shares of the default pool
+    /// for `amount0` and `amount1`.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event LiquidityRemoved(address indexed provider, uint256 amount0, uint256 amount1, uint256 shares);
+
+    /// Emitted when `value` LP shares move from `from` to `to`, or are
+    /// minted or burnt if either is zero.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event Transfer(address indexed from, address indexed to, uint256 value);
+
+    /// Emitted when `owner` lets `spender` move `value` of its LP shares.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+
+    /// Emitted by the constructor with the [`features::FEATURE_BITMAP`] of
+    /// the build.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event Deployed(string version, address indexed owner, uint64 chain_id, uint32 feature_bitmap);
+
+    /// Emitted when the post-deploy self-test passes.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event SelfTestPassed(uint64 indexed op_seq);
+}
+
+sol! {
+    /// Parameters of [`quoter::IQuoter::quote_exact_input_single`], shaped
+    /// like Uniswap's `IQuoterV2.QuoteExactInputSingleParams`.
+    #[allow(missing_docs)]
+    #[derive(Debug, AbiType)]
+    struct QuoteExactInputSingleParams {
+        address token_in;
+        address token_out;
+        uint256 amount_in;
+        uint24 fee;
+        uint160 sqrt_price_limit_x96;
+    }
+
+    /// Parameters of [`quoter::IQuoter::quote_exact_output_single`], shaped
+    /// like Uniswap's `IQuoterV2.QuoteExactOutputSingleParams`.
+    #[allow(missing_docs)]
+    #[derive(Debug, AbiType)]
+    struct QuoteExactOutputSingleParams {
+        address token_in;
+        address token_out;
+        uint256 amount;
+        uint24 fee;
+        uint160 sqrt_price_limit_x96;
+    }
+
+    /// Key identifying a Uniswap V4 pool, like v4's `PoolKey`.
+    #[allow(missing_docs)]
+    #[derive(Debug, AbiType)]
+    struct PoolKey {
+        address currency0;
+        address currency1;
+        uint24 fee;
+        int24 tick_spacing;
+        address hooks;
+    }
+
+    /// Parameters of a Uniswap V4 swap, like v4's `IPoolManager.SwapParams`.
+    /// A negative `amount_specified` is an exact input, a positive one an
+    /// exact output.
+    #[allow(missing_docs)]
+    #[derive(Debug, AbiType)]
+    struct SwapParams {
+        bool zero_for_one;
+        int256 amount_specified;
+        uint160 sqrt_price_limit_x96;
+    }
+
+    /// Hooks implemented by a Uniswap V4 hook, like v4's
+    /// `Hooks.Permissions`.
+    #[allow(missing_docs)]
+    #[derive(Debug, AbiType)]
+    struct HookPermissions {
+        bool before_initialize;
+        bool after_initialize;
+        bool before_add_liquidity;
+        bool after_add_liquidity;
+        bool before_remove_liquidity;
+        bool after_remove_liquidity;
+        bool before_swap;
+        bool after_swap;
+        bool before_donate;
+        bool after_donate;
+        bool before_swap_return_delta;
+        bool after_swap_return_delta;
+        bool after_add_liquidity_return_delta;
+        bool after_remove_liquidity_return_delta;
+    }
+
+    /// Parameters of a Uniswap V4 liquidity change, like v4's
+    /// `IPoolManager.ModifyLiquidityParams`.
+    #[allow(missing_docs)]
+    #[derive(Debug, AbiType)]
+    struct ModifyLiquidityParams {
+        int24 tick_lower;
+        int24 tick_upper;
+        int256 liquidity_delta;
+        bytes32 salt;
+    }
+
+    /// Payload of [`ConstantSumCurve::unlock_callback`], as passed to
+    /// [`ConstantSumCurve::unlock_pool_manager`]: the
+    /// [`pool_manager::UnlockAction`], the pool it runs on, and its
+    /// parameters.
+    #[allow(missing_docs)]
+    #[derive(Debug, AbiType)]
+    struct UnlockCallbackData {
+        uint8 action;
+        PoolKey key;
+        ModifyLiquidityParams params;
+        uint256 amount0;
+        uint256 amount1;
+    }
+
+    /// A price report signed by a reporter, as posted in batches to
+    /// [`oracle::IOracle::post_prices`].
+    #[allow(missing_docs)]
+    #[derive(Debug, AbiType)]
+    struct SignedPriceReport {
+        uint256 price;
+        uint64 timestamp;
+        uint256 nonce;
+        bytes signature;
+    }
+}
+
+sol! {
+    /// Indicates a custom error.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error CurveCustomError();
+
+    /// Indicates that a fee exceeds [`bounds::MAX_FEE_BPS`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidFee(uint16 fee_bps);
+
+    /// Indicates that `value` of the [`bounds::Parameter`] `parameter` is
+    /// out of its `bound`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error ParameterOutOfBounds(uint8 parameter, uint256 value, uint256 bound);
+
+    /// Indicates that the output reserve cannot cover the requested amount.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InsufficientLiquidity(uint256 available, uint256 requested);
+
+    /// Indicates that a price or reserve view was called while an operation
+    /// holds the lock.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error ReadOnlyReentrancy();
+
+    /// Indicates that an operation was called while another one holds the
+    /// lock.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error Reentrancy();
+
+    /// Indicates that the registered pool `pool_id` has no reserves to quote
+    /// against.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error PoolEmpty(bytes32 pool_id);
+
+    /// Indicates that the registered pool `pool_id` already holds reserves,
+    /// which only deposits and withdrawals may move.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error PoolNotEmpty(bytes32 pool_id);
+
+    /// Indicates that an amount does not fit into `uint256`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error AmountOverflow();
+
+    /// Indicates that a deposit would move the price of the pool to `price`,
+    /// beyond the depositor's tolerance around `expected_price`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error PriceOutOfTolerance(uint256 expected_price, uint256 price);
+
+    /// Indicates that `account` is not allowed to perform an operation.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error Unauthorized(address account);
+
+    /// Indicates that `policy` is not a valid [`CurrencyPolicy`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidCurrencyPolicy(uint8 policy);
+
+    /// Indicates that `curve` is not a valid [`ShadowCurve`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidShadowCurve(uint8 curve);
+
+    /// Indicates that `currency` cannot be swapped under the current
+    /// [`CurrencyPolicy`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error CurrencyNotAllowed(address currency);
+
+    /// Indicates that a swap amount is below the minimum swap amount.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error AmountTooSmall(uint256 amount, uint256 min_swap_amount);
+
+    /// Indicates that a swap amount is zero.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error ZeroAmount();
+
+    /// Indicates that the input and output currencies of a swap are the
+    /// same.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidTokenPair(address input, address output);
+
+    /// Indicates that an action is not allowed in the current bootstrap
+    /// phase.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidPhase(uint8 phase);
+
+    /// Indicates that the pool cannot move between two lifecycle states.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidStateTransition(uint8 from, uint8 to);
+
+    /// Indicates that a bootstrap auction ends in the past, does not last,
+    /// or has invalid weights.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidBootstrap(uint64 start, uint64 end, uint256 start_weight, uint256 end_weight);
+
+    /// Indicates that a swap sells `token0` during the bootstrap auction.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error BootstrapBuyOnly(address input);
+
+    /// Indicates that liquidity is added to or removed from the pool
+    /// manager instead of the hook.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error LiquidityViaHookOnly();
+
+    /// Indicates that a pool is already registered.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error PoolAlreadyRegistered(bytes32 pool_id);
+
+    /// Indicates that a pool's fee tier is not supported.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error UnsupportedFeeTier(uint24 fee);
+
+    /// Indicates that a pool is not registered.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error PoolNotRegistered(bytes32 pool_id);
+
+    /// Indicates that no pool of `currency0` and `currency1` with `fee` is
+    /// registered.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error PoolNotFound(address currency0, address currency1, uint24 fee);
+
+    /// Indicates that swaps in a pool are paused.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error PoolPaused(bytes32 pool_id);
+
+    /// Indicates that the address of the hook does not encode its
+    /// permissions.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error HookAddressNotValid(address hook);
+
+    /// Indicates that a hook entrypoint is called by an account other than
+    /// the pool manager.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error NotPoolManager(address caller);
+
+    /// Indicates that `currency` cannot be moved through the pool manager.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error SettlementFailed(address currency);
+
+    /// Indicates that the payload of `unlockCallback` cannot be decoded.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidUnlockData();
+
+    /// Indicates that `action` is not a valid
+    /// [`pool_manager::UnlockAction`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidUnlockAction(uint8 action);
+
+    /// Indicates that a liquidity delta does not match its unlock action.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidLiquidityDelta(int256 liquidity_delta);
+
+    /// Indicates that the pool manager rejected the call to `selector`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error PoolManagerCallFailed(bytes4 selector);
+
+    /// Indicates that a quote relied upon at `timestamp` expired at
+    /// `valid_until`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error QuoteExpired(uint64 valid_until, uint64 timestamp);
+
+    /// Indicates that the hook data of a swap cannot be decoded.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidHookData();
+
+    /// Indicates that a swap's `actual` amount crossed the swapper's
+    /// slippage `limit`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error SlippageExceeded(uint256 limit, uint256 actual);
+
+    /// Indicates that the oracle price is `age` seconds old, older than the
+    /// `max` age of a pool pegged to the oracle.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error PriceTooOld(uint256 age, uint256 max);
+
+    /// Indicates that `value` wei were sent for the pool `pool_id`, whose
+    /// currencies are both ERC-20 tokens.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error UnexpectedNativeValue(bytes32 pool_id, uint256 value);
+
+    /// Indicates that `value` wei were sent for a native currency amount of
+    /// `expected`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error NativeValueMismatch(uint256 expected, uint256 value);
+
+    /// Indicates that `currency` cannot be moved between an account and the
+    /// hook.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error TransferFailed(address currency);
+
+    /// Indicates that `currency0` does not sort before `currency1`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error UnsortedCurrencies(address currency0, address currency1);
+
+    /// Indicates that a deposit is too small to mint any LP share.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InsufficientLiquidityMinted();
+
+    /// Indicates that an account holding `balance` LP shares tried to burn
+    /// `shares`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InsufficientShares(uint256 balance, uint256 shares);
+
+    /// Indicates that burning LP shares pays nothing out.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InsufficientLiquidityBurned();
+
+    /// Indicates that a withdrawal pays out `amount`, less than its `min`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InsufficientOutputAmount(uint256 amount, uint256 min);
+
+    /// Indicates that a spender with an `allowance` of LP shares tried to
+    /// move `needed`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InsufficientAllowance(uint256 allowance, uint256 needed);
+
+    /// Indicates that LP shares cannot be sent to `receiver`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidReceiver(address receiver);
+
+    /// Indicates that the check `check` of the self-test failed.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error SelfTestFailed(uint8 check);
+
+    /// Indicates that the self-test already passed.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error SelfTestAlreadyPassed();
+
+    /// Indicates that `caller` is not a whitelisted router while the
+    /// router-only mode is enabled.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error RouterNotAllowed(address caller);
+
+    /// Indicates that the contract is decommissioned and cannot be mutated.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error ContractDecommissioned();
+
+    /// Indicates that the decommission is not scheduled, or its timelock
+    /// until `eta` has not passed yet.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error DecommissionNotReady(uint64 eta);
+
+    /// Indicates that a signature is malformed or cannot be recovered.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidSignature();
+
+    /// Indicates that a signature expired at `deadline`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error SignatureExpired(uint64 deadline);
+
+    /// Indicates that `action` is not a valid [`admin::AdminAction`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidAdminAction(uint8 action);
+
+    /// Indicates that a critical action must go through the multisig.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigRequired();
+
+    /// Indicates that the multisig is already configured.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error MultisigAlreadyConfigured();
+
+    /// Indicates that `threshold` is zero or exceeds the number of
+    /// `signers`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidThreshold(uint8 threshold, uint8 signers);
+
+    /// Indicates that `signer` cannot be added to or removed from the
+    /// multisig.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidSigner(address signer);
+
+    /// Indicates that `account` is not a signer of the multisig.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error NotSigner(address account);
+
+    /// Indicates that `action` is not a valid
+    /// [`multisig::CriticalAction`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidCriticalAction(uint8 action);
+
+    /// Indicates that no proposal with `id` exists.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error UnknownProposal(uint256 id);
+
+    /// Indicates that `signer` already approved the proposal with `id`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error ProposalAlreadyApproved(uint256 id, address signer);
+
+    /// Indicates that the proposal with `id` was already executed.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error ProposalAlreadyExecuted(uint256 id);
+
+    /// Indicates that the proposal with `id` has fewer `approvals` than the
+    /// `threshold`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error ThresholdNotMet(uint256 id, uint8 approvals, uint8 threshold);
+
+    /// Indicates that swaps are paused.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error ContractPaused();
+
+    /// Indicates that the guardian's powers expired at `sunset`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error GuardianExpired(uint64 sunset);
+
+    /// Indicates that a price posted at `timestamp` is older than
+    /// `max_price_age`, or that no price was posted.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error StalePrice(uint64 timestamp, uint64 max_price_age);
+
+    /// Indicates that a price report's `timestamp` is in the future, or not
+    /// newer than the reporter's `latest_timestamp`.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidPriceTimestamp(uint64 timestamp, uint64 latest_timestamp);
+
+    /// Indicates that `max_reporters` reporters are already registered.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error TooManyReporters(uint8 max_reporters);
+
+    /// Indicates that a price report of `reporter` does not use its
+    /// `expected` nonce.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidReportNonce(address reporter, uint256 expected);
+
+    /// Indicates that only one of `numerator` and `denominator` of an
+    /// exchange rate is zero.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidExchangeRate(uint256 numerator, uint256 denominator);
+
+    /// Indicates that an amplification coefficient is zero or above the
+    /// maximum.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidAmplification(uint256 amplification);
+
+    /// Indicates that the StableSwap invariant could not be solved.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvariantNotConverged();
+
+    /// Indicates that the weight of `currency` is not between the minimum
+    /// and maximum weight.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error InvalidWeight(address currency, uint256 weight);
+
+    /// Indicates that the reserves of the default pool back
+    /// `total_shares` LP shares, so the owner cannot set them.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error SharesOutstanding(uint256 total_shares);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Error {
+    /// Indicates a custom error.
+    CustomError(CurveCustomError),
+    /// Indicates that a fee exceeds [`bounds::MAX_FEE_BPS`].
+    InvalidFee(InvalidFee),
+    /// Indicates that a parameter is out of its bound.
+    ParameterOutOfBounds(ParameterOutOfBounds),
+    /// Indicates that the output reserve cannot cover the requested amount.
+    InsufficientLiquidity(InsufficientLiquidity),
+    /// Indicates that a view was called while the lock is held.
+    ReadOnlyReentrancy(ReadOnlyReentrancy),
+    /// Indicates that an operation was reentered while the lock is held.
+    Reentrancy(Reentrancy),
+    /// Indicates that a registered pool has no reserves to quote against.
+    PoolEmpty(PoolEmpty),
+    /// Indicates that a registered pool already holds reserves.
+    PoolNotEmpty(PoolNotEmpty),
+    /// Indicates that an amount does not fit into `uint256`.
+    AmountOverflow(AmountOverflow),
+    /// Indicates that a deposit would move the price out of tolerance.
+    PriceOutOfTolerance(PriceOutOfTolerance),
+    /// Indicates that `account` is not allowed to perform an operation.
+    Unauthorized(Unauthorized),
+    /// Indicates that a policy is not a valid [`CurrencyPolicy`].
+    InvalidCurrencyPolicy(InvalidCurrencyPolicy),
+    /// Indicates that a curve is not a valid [`ShadowCurve`].
+    InvalidShadowCurve(InvalidShadowCurve),
+    /// Indicates that a currency cannot be swapped under the current
+    /// [`CurrencyPolicy`].
+    CurrencyNotAllowed(CurrencyNotAllowed),
+    /// Indicates that a swap amount is below the minimum swap amount.
+    AmountTooSmall(AmountTooSmall),
+    /// Indicates that a swap amount is zero.
+    ZeroAmount(ZeroAmount),
+    /// Indicates that the input and output currencies of a swap are the
+    /// same.
+    InvalidTokenPair(InvalidTokenPair),
+    /// Indicates that an action is not allowed in the current bootstrap
+    /// phase.
+    InvalidPhase(InvalidPhase),
+    /// Indicates that the pool cannot move between two lifecycle states.
+    InvalidStateTransition(InvalidStateTransition),
+    /// Indicates that a bootstrap auction ends in the past, does not last,
+    /// or has invalid weights.
+    InvalidBootstrap(InvalidBootstrap),
+    /// Indicates that a swap sells `token0` during the bootstrap auction.
+    BootstrapBuyOnly(BootstrapBuyOnly),
+    /// Indicates that liquidity is added to or removed from the pool
+    /// manager instead of the hook.
+    LiquidityViaHookOnly(LiquidityViaHookOnly),
+    /// Indicates that a pool is already registered.
+    PoolAlreadyRegistered(PoolAlreadyRegistered),
+    /// Indicates that a pool's fee tier is not supported.
+    UnsupportedFeeTier(UnsupportedFeeTier),
+    /// Indicates that a pool is not registered.
+    PoolNotRegistered(PoolNotRegistered),
+    /// Indicates that no pool of a pair and fee is registered.
+    PoolNotFound(PoolNotFound),
+    /// Indicates that swaps in a pool are paused.
+    PoolPaused(PoolPaused),
+    /// Indicates that the address of the hook does not encode its
+    /// permissions.
+    HookAddressNotValid(HookAddressNotValid),
+    /// Indicates that a hook entrypoint is called by an account other than
+    /// the pool manager.
+    NotPoolManager(NotPoolManager),
+    /// Indicates that a currency cannot be moved through the pool manager.
+    SettlementFailed(SettlementFailed),
+    /// Indicates that the payload of `unlockCallback` cannot be decoded.
+    InvalidUnlockData(InvalidUnlockData),
+    /// Indicates that an unlock action is not valid.
+    InvalidUnlockAction(InvalidUnlockAction),
+    /// Indicates that a liquidity delta does not match its unlock action.
+    InvalidLiquidityDelta(InvalidLiquidityDelta),
+    /// Indicates that the pool manager rejected a call.
+    PoolManagerCallFailed(PoolManagerCallFailed),
+    /// Indicates that a quote expired.
+    QuoteExpired(QuoteExpired),
+    /// Indicates that the hook data of a swap cannot be decoded.
+    InvalidHookData(InvalidHookData),
+    /// Indicates that a swap crossed the swapper's slippage limit.
+    SlippageExceeded(SlippageExceeded),
+    /// Indicates that the oracle price is too old for a pool.
+    PriceTooOld(PriceTooOld),
+    /// Indicates that native currency was sent for a pool of ERC-20 tokens.
+    UnexpectedNativeValue(UnexpectedNativeValue),
+    /// Indicates that the native currency sent does not match the amount.
+    NativeValueMismatch(NativeValueMismatch),
+    /// Indicates that a currency cannot be moved between an account and the
+    /// hook.
+    TransferFailed(TransferFailed),
+    /// Indicates that currencies are not sorted.
+    UnsortedCurrencies(UnsortedCurrencies),
+    /// Indicates that a deposit is too small to mint any LP share.
+    InsufficientLiquidityMinted(InsufficientLiquidityMinted),
+    /// Indicates that an account does not hold enough LP shares.
+    InsufficientShares(InsufficientShares),
+    /// Indicates that burning LP shares pays nothing out.
+    InsufficientLiquidityBurned(InsufficientLiquidityBurned),
+    /// Indicates that a withdrawal pays out less than its minimum.
+    InsufficientOutputAmount(InsufficientOutputAmount),
+    /// Indicates that a spender's allowance of LP shares is too small.
+    InsufficientAllowance(InsufficientAllowance),
+    /// Indicates that LP shares cannot be sent to an account.
+    InvalidReceiver(InvalidReceiver),
+    /// Indicates that a check of the self-test failed.
+    SelfTestFailed(SelfTestFailed),
+    /// Indicates that the self-test already passed.
+    SelfTestAlreadyPassed(SelfTestAlreadyPassed),
+    /// Indicates that the caller is not a whitelisted router while the
+    /// router-only mode is enabled.
+    RouterNotAllowed(RouterNotAllowed),
+    /// Indicates that the contract is decommissioned and cannot be mutated.
+    ContractDecommissioned(ContractDecommissioned),
+    /// Indicates that the decommission is not scheduled, or its timelock has
+    /// not passed yet.
+    DecommissionNotReady(DecommissionNotReady),
+    /// Indicates that a signature is malformed or cannot be recovered.
+    InvalidSignature(InvalidSignature),
+    /// Indicates that a signature expired.
+    SignatureExpired(SignatureExpired),
+    /// Indicates that an action is not a valid [`admin::AdminAction`].
+    InvalidAdminAction(InvalidAdminAction),
+    /// Indicates that a critical action must go through the multisig.
+    MultisigRequired(MultisigRequired),
+    /// Indicates that the multisig is already configured.
+    MultisigAlreadyConfigured(MultisigAlreadyConfigured),
+    /// Indicates that a threshold is zero or exceeds the number of signers.
+    InvalidThreshold(InvalidThreshold),
+    /// Indicates that a signer cannot be added to or removed from the
+    /// multisig.
+    InvalidSigner(InvalidSigner),
+    /// Indicates that an account is not a signer of the multisig.
+    NotSigner(NotSigner),
+    /// Indicates that an action is not a valid [`multisig::CriticalAction`].
+    InvalidCriticalAction(InvalidCriticalAction),
+    /// Indicates that a proposal does not exist.
+    UnknownProposal(UnknownProposal),
+    /// Indicates that a signer already approved a proposal.
+    ProposalAlreadyApproved(ProposalAlreadyApproved),
+    /// Indicates that a proposal was already executed.
+    ProposalAlreadyExecuted(ProposalAlreadyExecuted),
+    /// Indicates that a proposal has fewer approvals than the threshold.
+    ThresholdNotMet(ThresholdNotMet),
+    /// Indicates that swaps are paused.
+    ContractPaused(ContractPaused),
+    /// Indicates that the guardian's powers expired.
+    GuardianExpired(GuardianExpired),
+    /// Indicates that a price is stale or was never posted.
+    StalePrice(StalePrice),
+    /// Indicates that a price report's timestamp is in the future or not
+    /// newer than the latest price.
+    InvalidPriceTimestamp(InvalidPriceTimestamp),
+    /// Indicates that the maximum number of reporters is registered.
+    TooManyReporters(TooManyReporters),
+    /// Indicates that a price report does not use the reporter's next nonce.
+    InvalidReportNonce(InvalidReportNonce),
+    /// Indicates that only one of the numerator and denominator of an
+    /// exchange rate is zero.
+    InvalidExchangeRate(InvalidExchangeRate),
+    /// Indicates that an amplification coefficient is zero or above the
+    /// maximum.
+    InvalidAmplification(InvalidAmplification),
+    /// Indicates that the StableSwap invariant could not be solved.
+    InvariantNotConverged(InvariantNotConverged),
+    /// Indicates that the weight of a currency is not between the minimum
+    /// and maximum weight.
+    InvalidWeight(InvalidWeight),
+    /// Indicates that LP shares are backed by the default pool's reserves.
+    SharesOutstanding(SharesOutstanding),
+    /// Wraps the revert data of a failed call with its trace.
+    #[cfg(feature = "trace")]
+    Traced(trace::Traced),
+}
+
+/// Denominator of fees expressed in basis points.
+pub const FEE_DENOMINATOR: u16 = 10_000;
+
+/// Square root of the 1:1 price as a Q64.96 number.
+pub const SQRT_PRICE_1_1: U160 = uint!(79228162514264337593543950336_U160);
+
+/// Delay between scheduling and executing the decommission, in seconds.
+pub const DECOMMISSION_DELAY: u64 = 2 * 24 * 60 * 60;
+
+/// Policy deciding which currencies can be swapped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CurrencyPolicy {
+    /// Every currency can be swapped.
+    Open = 0,
+    /// Only listed currencies can be swapped.
+    Allowlist = 1,
+    /// Every currency except the listed ones can be swapped.
+    Denylist = 2,
+}
+
+impl TryFrom<u8> for CurrencyPolicy {
+    type Error = Error;
+
+    fn try_from(policy: u8) -> Result<Self, Self::Error> {
+        match policy {
+            0 => Ok(Self::Open),
+            1 => Ok(Self::Allowlist),
+            2 => Ok(Self::Denylist),
+            _ => Err(Error::InvalidCurrencyPolicy(InvalidCurrencyPolicy {
+                policy,
+            })),
+        }
+    }
+}
+
+/// Candidate curve quoting swaps alongside the active one in shadow mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ShadowCurve {
+    /// Tokens trade at the exchange rate of the pair.
+    ConstantSum = 0,
+    /// Tokens trade along the `x * y = k` invariant of the pool's reserves.
+    ConstantProduct = 1,
+}
+
+impl TryFrom<u8> for ShadowCurve {
+    type Error = Error;
+
+    fn try_from(curve: u8) -> Result<Self, Self::Error> {
+        match curve {
+            0 => Ok(Self::ConstantSum),
+            1 => Ok(Self::ConstantProduct),
+            _ => Err(Error::InvalidShadowCurve(InvalidShadowCurve { curve })),
+        }
+    }
+}
+
+#[storage]
+#[cfg_attr(
+    not(any(curve = "constant-product", curve = "stable-swap", curve = "weighted")),
+    entrypoint
+)]
+struct ConstantSumCurve {
+    version: StorageString,
+    owner: StorageAddress,
+    currency_policy: StorageU8,
+    listed_currencies: StorageMap<Currency, StorageBool>,
+    min_swap_amount: StorageU256,
+    router_only: StorageBool,
+    routers: StorageMap<Address, StorageBool>,
+    changelog: StorageMap<U64, ChangeRecord>,
+    change_count: StorageU64,
+    decommission_eta: StorageU64,
+    decommissioned: StorageBool,
+    admin_nonce: StorageU256,
+    signers: StorageMap<Address, StorageBool>,
+    signer_count: StorageU8,
+    threshold: StorageU8,
+    proposals: StorageMap<U256, Proposal>,
+    proposal_count: StorageU256,
+    guardian: StorageAddress,
+    guardian_sunset: StorageU64,
+    paused: StorageBool,
+    shadow_mode: StorageBool,
+    shadow_fee_bps: StorageU16,
+    reporters: StorageMap<Address, StorageBool>,
+    reporter_list: StorageVec<StorageAddress>,
+    reports: StorageMap<Address, Report>,
+    max_deviation_bps: StorageU16,
+    max_price_age: StorageU64,
+    price: StorageU256,
+    price_timestamp: StorageU64,
+    default_rate: ExchangeRate,
+    exchange_rates: StorageMap<Currency, StorageMap<Currency, ExchangeRate>>,
+    fee_bps: StorageU16,
+    reserve0: StorageU256,
+    reserve1: StorageU256,
+    bootstrap: Bootstrap,
+    after_swap_fee_bps: StorageU16,
+    pools: StorageMap<B256, RegisteredPool>,
+    pool_manager: StorageAddress,
+    claims_mode: StorageBool,
+    claim_balances: StorageMap<Currency, StorageU256>,
+    fee_manager: StorageAddress,
+    currency0: StorageAddress,
+    currency1: StorageAddress,
+    total_shares: StorageU256,
+    shares: StorageMap<Address, StorageU256>,
+    self_test_passed: StorageBool,
+    allowances: StorageMap<Address, StorageMap<Address, StorageU256>>,
+    shadow_curve: StorageU8,
+    pool_index: StorageMap<B256, StorageB256>,
+}
+
+// SAFETY: Unless it is the entrypoint, the contract is only instantiated as
+// top-level storage in tests.
+#[cfg(any(curve = "constant-product", curve = "stable-swap", curve = "weighted"))]
+unsafe impl TopLevelStorage for ConstantSumCurve {}
+
+/// Interface of an [`UniswapCurve`] contract.
+///
+/// NOTE: The contract's interface can be modified in any way.
+pub trait IUniswapV4Curve {
+    /// The error type associated to the trait implementation.
+    type Error: Into<alloc::vec::Vec<u8>>;
+
+    /// Returns the version of the curve.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    fn version(&self) -> String;
+
+    /// Returns the amount of input tokens for an exact-output swap.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `amount_out` the amount of output tokens the user expects to receive.
+    /// * `input` - The input token.
+    /// * `output` - The output token.
+    /// * `zero_for_one` - True if the input token is token0.
+    ///
+    /// # Errors
+    ///
+    /// May return an [`Error`].
+    ///
+    /// # Events
+    ///
+    /// May emit any event.
+    fn get_amount_in_for_exact_output(
+        &mut self,
+        amount_out: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Self::Error>;
+
+    /// Returns the amount of output tokens for an exact-input swap.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `amount_in` - The amount of input tokens.
+    /// * `input` - The input token.
+    /// * `output` - The output token.
+    /// * `zero_for_one` - True if the input token is `token_0`.
+    ///
+    /// # Errors
+    ///
+    /// May return an [`Error`].
+    ///
+    /// # Events
+    ///
+    /// May emit any event.
+    fn get_amount_out_from_exact_input(
+        &mut self,
+        amount_in: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Self::Error>;
+}
+
+#[public]
+#[implements(
+    IUniswapV4Curve<Error = Error>,
+    IPool<Error = Error>,
+    ILpToken<Error = Error>,
+    ILiquidity<Error = Error>,
+    IOracle<Error = Error>,
+    IMultisig<Error = Error>,
+    IQuoter<Error = Error>,
+    IAdmin<Error = Error>,
+)]
+impl ConstantSumCurve {
+    /// Initializes the contract.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `version` - The version of the curve.
+    /// * `owner` - The owner of the contract.
+    /// * `pool_manager` - The Uniswap V4 pool manager calling the hooks.
+    /// * `rate_numerator` - The numerator of the default exchange rate.
+    /// * `rate_denominator` - The denominator of the default exchange rate,
+    ///   which is 1:1 if either is zero.
+    /// * `fee_bps` - The swap fee in basis points.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidFee`] - If `fee_bps` exceeds [`bounds::MAX_FEE_BPS`].
+    /// * [`Error::ParameterOutOfBounds`] - If the default exchange rate is
+    ///   skewed by more than [`bounds::MAX_SKEW`].
+    /// * [`Error::HookAddressNotValid`] - If the contract's address does not
+    ///   encode [`hooks::hook_permissions`].
+    ///
+    /// # Events
+    ///
+    /// * [`Deployed`].
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        version: String,
+        owner: Address,
+        pool_manager: Address,
+        rate_numerator: U256,
+        rate_denominator: U256,
+        fee_bps: u16,
+    ) -> Result<(), Error> {
+        bounds::check_fee(fee_bps)?;
+        bounds::check_skew(rate_numerator, rate_denominator)?;
+        hooks::check_hook_address(self.vm().contract_address())?;
+        self.version.set_str(version);
+        self.owner.set(owner);
+        self.pool_manager.set(pool_manager);
+        self.default_rate.set_rate(rate_numerator, rate_denominator);
+        self.fee_bps.set(U16::from(fee_bps));
+
+        #[allow(deprecated)]
+        evm::log(Deployed {
+            version: self.version.get_string(),
+            owner,
+            chain_id: self.vm().chain_id(),
+            feature_bitmap: features::FEATURE_BITMAP,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the swap fee in basis points.
+    pub fn fee(&self) -> u16 {
+        self.fee_bps.get().to::<u16>()
+    }
+
+    /// Returns the Uniswap V4 pool manager allowed to call the hooks.
+    pub fn pool_manager(&self) -> Address {
+        self.pool_manager.get()
+    }
+
+    /// Returns true once the post-deploy self-test passed.
+    pub fn self_test_passed(&self) -> bool {
+        self.self_test_passed.get()
+    }
+
+    /// Runs the post-deploy self-test once: quotes a canary pair at a dummy
+    /// exchange rate in both directions, checks the results, and records
+    /// that the test passed.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::SelfTestAlreadyPassed`] - If the self-test already passed.
+    /// * [`Error::SelfTestFailed`] - If a check fails.
+    ///
+    /// # Events
+    ///
+    /// * [`AmountOutCalculated`].
+    /// * [`AmountInCalculated`].
+    /// * [`SelfTestPassed`].
+    pub fn self_test(&mut self) -> Result<(), Error> {
+        meter!("selfTest");
+        self.check_active()?;
+        self.only_owner()?;
+        if self.self_test_passed.get() {
+            return Err(Error::SelfTestAlreadyPassed(SelfTestAlreadyPassed {}));
+        }
+        self.run_self_test()?;
+        self.self_test_passed.set(true);
+        let op_seq = self.record_change(ChangeKind::SelfTest, B256::ZERO, U256::from(1));
+
+        #[allow(deprecated)]
+        evm::log(SelfTestPassed { op_seq });
+
+        Ok(())
+    }
+
+    /// Returns the [`features::CAPABILITIES`] of the deployment: a bit per
+    /// optional feature and subsystem compiled in.
+    pub fn capabilities(&self) -> u32 {
+        features::CAPABILITIES
+    }
+
+    /// Returns the bounds checked by the setters: the maximum fee and
+    /// report spread in basis points, the minimum duration of a bootstrap
+    /// auction in seconds, and the maximum skew of an exchange rate.
+    pub fn bounds(&self) -> BoundsView {
+        bounds::bounds()
+    }
+
+    /// Returns the current [`CurrencyPolicy`] as its `u8` discriminant.
+    pub fn currency_policy(&self) -> u8 {
+        self.currency_policy.get().to::<u8>()
+    }
+
+    /// Sets the [`CurrencyPolicy`] applied to swapped currencies.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `policy` - The `u8` discriminant of the new [`CurrencyPolicy`].
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::InvalidCurrencyPolicy`] - If `policy` is not a valid
+    ///   [`CurrencyPolicy`].
+    ///
+    /// # Events
+    ///
+    /// * [`CurrencyPolicySet`].
+    pub fn set_currency_policy(&mut self, policy: u8) -> Result<(), Error> {
+        meter!("setCurrencyPolicy", u8);
+        self.check_active()?;
+        self.only_owner()?;
+        self.update_currency_policy(policy)
+    }
+
+    /// Returns true if `currency` is on the currency list.
+    pub fn is_currency_listed(&self, currency: Currency) -> bool {
+        self.listed_currencies.get(currency)
+    }
+
+    /// Adds `currency` to or removes it from the currency list, which is
+    /// an allowlist or a denylist depending on the [`CurrencyPolicy`].
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `currency` - The currency to update.
+    /// * `listed` - True to add `currency` to the list.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`CurrencyListed`].
+    pub fn set_currency_listed(&mut self, currency: Currency, listed: bool) -> Result<(), Error> {
+        meter!("setCurrencyListed", Address, bool);
+        self.check_active()?;
+        self.only_owner()?;
+        self.update_currency_listed(currency, listed);
+        Ok(())
+    }
+
+    /// Returns the minimum amount of a swap.
+    pub fn min_swap_amount(&self) -> U256 {
+        self.min_swap_amount.get()
+    }
+
+    /// Sets the minimum amount of a swap, rejecting dust swaps that would
+    /// round to zero after fees.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `min_swap_amount` - The new minimum swap amount.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`MinSwapAmountSet`].
+    pub fn set_min_swap_amount(&mut self, min_swap_amount: U256) -> Result<(), Error> {
+        meter!("setMinSwapAmount", U256);
+        self.check_active()?;
+        self.only_owner()?;
+        self.update_min_swap_amount(min_swap_amount);
+        Ok(())
+    }
+
+    /// Returns true if swaps can only be executed by whitelisted routers.
+    pub fn router_only(&self) -> bool {
+        self.router_only.get()
+    }
+
+    /// Enables or disables the router-only mode, in which swaps can only be
+    /// executed by whitelisted routers. Views stay callable by anyone.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `enabled` - True to enable the router-only mode.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`RouterOnlySet`].
+    pub fn set_router_only(&mut self, enabled: bool) -> Result<(), Error> {
+        meter!("setRouterOnly", bool);
+        self.check_active()?;
+        self.only_owner()?;
+        self.update_router_only(enabled);
+        Ok(())
+    }
+
+    /// Returns true if `router` is on the router whitelist.
+    pub fn is_router(&self, router: Address) -> bool {
+        self.routers.get(router)
+    }
+
+    /// Adds `router` to or removes it from the router whitelist.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `router` - The router or `PoolManager` address.
+    /// * `allowed` - True to whitelist `router`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`RouterSet`].
+    pub fn set_router(&mut self, router: Address, allowed: bool) -> Result<(), Error> {
+        meter!("setRouter", Address, bool);
+        self.check_active()?;
+        self.only_owner()?;
+        self.update_router(router, allowed);
+        Ok(())
+    }
+
+    /// Returns the guardian, which can only pause swaps until its sunset.
+    pub fn guardian(&self) -> Address {
+        self.guardian.get()
+    }
+
+    /// Returns the time from which the guardian can no longer pause swaps.
+    pub fn guardian_sunset(&self) -> u64 {
+        self.guardian_sunset.get().to::<u64>()
+    }
+
+    /// Sets the guardian, which can pause swaps (but never unpause them or
+    /// change parameters) until `sunset`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `guardian` - The new guardian, or zero to remove it.
+    /// * `sunset` - The time from which the guardian's powers expire.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`GuardianSet`].
+    pub fn set_guardian(&mut self, guardian: Address, sunset: u64) -> Result<(), Error> {
+        meter!("setGuardian", Address, u64);
+        self.check_active()?;
+        self.only_owner()?;
+        self.guardian.set(guardian);
+        self.guardian_sunset.set(U64::from(sunset));
+        let op_seq = self.record_change(
+            ChangeKind::Guardian,
+            guardian.into_word(),
+            U256::from(sunset),
+        );
+
+        #[allow(deprecated)]
+        evm::log(GuardianSet {
+            op_seq,
+            guardian,
+            sunset,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the fee manager, which can tune the oracle parameters of the
+    /// pools alongside the owner.
+    pub fn fee_manager(&self) -> Address {
+        self.fee_manager.get()
+    }
+
+    /// Sets the fee manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `fee_manager` - The new fee manager, or zero to remove it.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`FeeManagerSet`].
+    pub fn set_fee_manager(&mut self, fee_manager: Address) -> Result<(), Error> {
+        meter!("setFeeManager", Address);
+        self.check_active()?;
+        self.only_owner()?;
+        self.fee_manager.set(fee_manager);
+        let op_seq =
+            self.record_change(ChangeKind::FeeManager, fee_manager.into_word(), U256::ZERO);
+
+        #[allow(deprecated)]
+        evm::log(FeeManagerSet {
+            op_seq,
+            fee_manager,
+        });
+
+        Ok(())
+    }
+
+    /// Returns true if swaps are paused.
+    pub fn paused(&self) -> bool {
+        self.paused.get()
+    }
+
+    /// Pauses swaps. Callable by the owner, or by the guardian before its
+    /// sunset.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::GuardianExpired`] - If the caller is the guardian and its
+    ///   sunset has passed.
+    /// * [`Error::Unauthorized`] - If the caller is neither the owner nor the
+    ///   guardian.
+    /// * [`Error::InvalidStateTransition`] - If swaps are already paused.
+    ///
+    /// # Events
+    ///
+    /// * [`Paused`].
+    /// * [`PoolStateChanged`].
+    pub fn pause(&mut self) -> Result<(), Error> {
+        meter!("pause");
+        self.check_active()?;
+        let account = self.vm().msg_sender();
+        if account != self.owner.get() {
+            self.only_guardian()?;
+        }
+        let from = self.check_transition(PoolState::Paused)?;
+        self.update_paused(account, true);
+        self.emit_state_change(from);
+        Ok(())
+    }
+
+    /// Unpauses swaps.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::InvalidStateTransition`] - If swaps are not paused.
+    ///
+    /// # Events
+    ///
+    /// * [`Unpaused`].
+    /// * [`PoolStateChanged`].
+    pub fn unpause(&mut self) -> Result<(), Error> {
+        meter!("unpause");
+        self.check_active()?;
+        self.only_owner()?;
+        let from = self.check_transition(self.unpaused_state())?;
+        let account = self.vm().msg_sender();
+        self.update_paused(account, false);
+        self.emit_state_change(from);
+        Ok(())
+    }
+
+    /// Returns true if the shadow mode is enabled.
+    pub fn shadow_mode(&self) -> bool {
+        self.shadow_mode.get()
+    }
+
+    /// Returns the candidate [`ShadowCurve`] evaluated in shadow mode as its
+    /// `u8` discriminant.
+    pub fn shadow_curve(&self) -> u8 {
+        self.shadow_curve.get().to::<u8>()
+    }
+
+    /// Returns the fee of the candidate curve evaluated in shadow mode.
+    pub fn shadow_fee_bps(&self) -> u16 {
+        self.shadow_fee_bps.get().to::<u16>()
+    }
+
+    /// Enables or disables the shadow mode, in which every swap of the pool
+    /// manager is also quoted by the candidate `curve` charging `fee_bps`,
+    /// emitting [`ShadowQuoteDiff`] without affecting the settled amounts.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `enabled` - True to enable the shadow mode.
+    /// * `curve` - The `u8` discriminant of the candidate [`ShadowCurve`].
+    /// * `fee_bps` - The fee of the candidate curve in basis points.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::InvalidShadowCurve`] - If `curve` is not a valid
+    ///   [`ShadowCurve`].
+    /// * [`Error::InvalidFee`] - If `fee_bps` exceeds [`bounds::MAX_FEE_BPS`].
+    ///
+    /// # Events
+    ///
+    /// * [`ShadowModeSet`].
+    pub fn set_shadow_mode(&mut self, enabled: bool, curve: u8, fee_bps: u16) -> Result<(), Error> {
+        meter!("setShadowMode", bool, u8, u16);
+        self.check_active()?;
+        self.only_owner()?;
+        ShadowCurve::try_from(curve)?;
+        bounds::check_fee(fee_bps)?;
+        self.shadow_mode.set(enabled);
+        self.shadow_curve.set(U8::from(curve));
+        self.shadow_fee_bps.set(U16::from(fee_bps));
+        let op_seq = self.record_change(
+            ChangeKind::ShadowMode,
+            B256::ZERO,
+            U256::from(u8::from(enabled)),
+        );
+
+        #[allow(deprecated)]
+        evm::log(ShadowModeSet {
+            op_seq,
+            enabled,
+            curve,
+            fee_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the exchange rate of the pair as `(numerator, denominator)`,
+    /// i.e. the amount of `currency1` one unit of `currency0` trades for.
+    ///
+    /// Pairs without a rate use the default rate, and 1:1 if it is unset.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `currency0` - The lower currency of the pair.
+    /// * `currency1` - The higher currency of the pair.
+    pub fn exchange_rate(&self, currency0: Currency, currency1: Currency) -> (U256, U256) {
+        self.exchange_rates
+            .getter(currency0)
+            .getter(currency1)
+            .rate()
+            .or_else(|| self.default_rate.rate())
+            .unwrap_or((U256::from(1), U256::from(1)))
+    }
+
+    /// Sets the exchange rate of a pair, or unsets it if `numerator` and
+    /// `denominator` are zero. The currencies can be passed in any order,
+    /// with the rate expressed as the amount of `currency_b` one unit of
+    /// `currency_a` trades for.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `currency_a` - One currency of the pair.
+    /// * `currency_b` - The other currency of the pair.
+    /// * `numerator` - The numerator of the rate.
+    /// * `denominator` - The denominator of the rate.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::InvalidExchangeRate`] - If only one of `numerator` and
+    ///   `denominator` is zero.
+    /// * [`Error::ParameterOutOfBounds`] - If the rate is skewed by more than
+    ///   [`bounds::MAX_SKEW`].
+    ///
+    /// # Events
+    ///
+    /// * [`ExchangeRateSet`].
+    pub fn set_exchange_rate(
+        &mut self,
+        currency_a: Currency,
+        currency_b: Currency,
+        numerator: U256,
+        denominator: U256,
+    ) -> Result<(), Error> {
+        meter!("setExchangeRate", Address, Address, U256, U256);
+        self.check_active()?;
+        self.only_owner()?;
+        if numerator.is_zero() != denominator.is_zero() {
+            return Err(Error::InvalidExchangeRate(InvalidExchangeRate {
+                numerator,
+                denominator,
+            }));
+        }
+        bounds::check_skew(numerator, denominator)?;
+
+        let (currency0, currency1, numerator, denominator) = if currency_a < currency_b {
+            (currency_a, currency_b, numerator, denominator)
+        } else {
+            (currency_b, currency_a, denominator, numerator)
+        };
+        self.exchange_rates
+            .setter(currency0)
+            .setter(currency1)
+            .set_rate(numerator, denominator);
+        let op_seq = self.record_change(ChangeKind::ExchangeRate, currency0.into_word(), numerator);
+
+        #[allow(deprecated)]
+        evm::log(ExchangeRateSet {
+            op_seq,
+            currency0,
+            currency1,
+            numerator,
+            denominator,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the reserves of `token0` and `token1`, set by the owner or
+    /// moved by liquidity providers.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    pub fn get_reserves(&self) -> Result<(U256, U256), Error> {
+        self.check_view_unlocked()?;
+        Ok(self.reserves_of(pool::DEFAULT_POOL_ID))
+    }
+
+    /// Sets the reserves of `token0` and `token1`, until liquidity providers
+    /// hold shares of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `reserve0` - The new reserve of `token0`.
+    /// * `reserve1` - The new reserve of `token1`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::SharesOutstanding`] - If LP shares are minted.
+    ///
+    /// # Events
+    ///
+    /// * [`ReservesSet`].
+    pub fn set_reserves(&mut self, reserve0: U256, reserve1: U256) -> Result<(), Error> {
+        meter!("setReserves", U256, U256);
+        self.check_active()?;
+        self.only_owner()?;
+        self.check_no_shares()?;
+        self.set_reserves_of(pool::DEFAULT_POOL_ID, reserve0, reserve1);
+        let op_seq = self.record_change(ChangeKind::Reserves, B256::ZERO, reserve0);
+
+        #[allow(deprecated)]
+        evm::log(ReservesSet {
+            op_seq,
+            reserve0,
+            reserve1,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the current bootstrap [`Phase`] as its `u8` discriminant.
+    pub fn phase(&self) -> u8 {
+        self.current_phase() as u8
+    }
+
+    /// Returns the bootstrap auction: its start, end, and start and end
+    /// weights of `token0`.
+    pub fn bootstrap(&self) -> BootstrapView {
+        self.bootstrap_view()
+    }
+
+    /// Schedules a bootstrap auction selling `token0` for `token1` from
+    /// `start` to `end`, with the weight of `token0` declining from
+    /// `start_weight` to `end_weight`. The curve trades at its exchange rate
+    /// once the auction ends.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `start` - The timestamp at which the auction starts.
+    /// * `end` - The timestamp at which the auction ends.
+    /// * `start_weight` - The weight of `token0` at `start`.
+    /// * `end_weight` - The weight of `token0` at `end`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::InvalidPhase`] - If a bootstrap auction is running or has
+    ///   ended.
+    /// * [`Error::InvalidBootstrap`] - If the auction ends in the past, does
+    ///   not last, or has invalid weights.
+    /// * [`Error::ParameterOutOfBounds`] - If the auction runs for less than
+    ///   [`bounds::MIN_LOCK`] from now.
+    ///
+    /// # Events
+    ///
+    /// * [`BootstrapScheduled`].
+    pub fn schedule_bootstrap(
+        &mut self,
+        start: u64,
+        end: u64,
+        start_weight: U256,
+        end_weight: U256,
+    ) -> Result<(), Error> {
+        meter!("scheduleBootstrap", u64, u64, U256, U256);
+        self.check_active()?;
+        self.only_owner()?;
+        self.check_bootstrap_pending()?;
+        self.check_bootstrap(start, end, start_weight, end_weight)?;
+        let from = self.current_state();
+        self.update_bootstrap(start, end, start_weight, end_weight);
+        let op_seq = self.record_change(ChangeKind::Bootstrap, B256::ZERO, U256::from(end));
+
+        #[allow(deprecated)]
+        evm::log(BootstrapScheduled {
+            op_seq,
+            start,
+            end,
+            start_weight,
+            end_weight,
+        });
+        self.emit_state_change(from);
+
+        Ok(())
+    }
+
+    /// Cancels the scheduled bootstrap auction before it starts.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::InvalidPhase`] - If no bootstrap auction is scheduled.
+    ///
+    /// # Events
+    ///
+    /// * [`BootstrapCancelled`].
+    pub fn cancel_bootstrap(&mut self) -> Result<(), Error> {
+        meter!("cancelBootstrap");
+        self.check_active()?;
+        self.only_owner()?;
+        let phase = self.current_phase();
+        if phase != Phase::Scheduled {
+            return Err(Error::InvalidPhase(InvalidPhase { phase: phase as u8 }));
+        }
+        let from = self.current_state();
+        self.update_bootstrap(0, 0, U256::ZERO, U256::ZERO);
+        let op_seq = self.record_change(ChangeKind::Bootstrap, B256::ZERO, U256::ZERO);
+
+        #[allow(deprecated)]
+        evm::log(BootstrapCancelled { op_seq });
+        self.emit_state_change(from);
+
+        Ok(())
+    }
+
+    /// Prices a swap of the pool `key` on the curve, as Uniswap V4's
+    /// `beforeSwap` hook, taking the whole specified amount so the pool's own
+    /// liquidity is never used. The hook takes the input currency from the
+    /// pool manager and pays it the output currency.
+    ///
+    /// Returns the `beforeSwap` selector, the `BeforeSwapDelta` owed between
+    /// the hook and the pool manager, and no LP fee override.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `_sender` - The account initiating the swap on the pool manager.
+    /// * `key` - The key of the swapped pool.
+    /// * `params` - The swap parameters.
+    /// * `hook_data` - The ABI-encoded `uint64` timestamp until which the
+    ///   swapper's quote is valid, optionally followed by a `uint256`
+    ///   slippage limit, or empty to skip both checks.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotPoolManager`] - If the caller is not the pool manager.
+    /// * [`Error::InvalidHookData`] - If `hook_data` cannot be decoded.
+    /// * [`Error::QuoteExpired`] - If the swapper's quote expired.
+    /// * [`Error::SlippageExceeded`] - If an exact input pays out less than
+    ///   the slippage limit, or an exact output costs more.
+    /// * [`Error::PoolNotRegistered`] - If the pool `key` is not registered.
+    /// * [`Error::PoolEmpty`] - If both reserves of the pool are zero.
+    /// * [`Error::PriceTooOld`] - If the pool is pegged to the oracle and its
+    ///   price is too old.
+    /// * [`Error::PoolPaused`] - If swaps in the pool are paused.
+    /// * [`Error::AmountOverflow`] - If an amount does not fit into
+    ///   `int128`.
+    /// * [`Error::SettlementFailed`] - If the swapped currencies cannot be
+    ///   moved through the pool manager.
+    /// * Any error of [`IUniswapV4Curve::get_amount_out_from_exact_input`] for
+    ///   exact inputs, or [`IUniswapV4Curve::get_amount_in_for_exact_output`]
+    ///   for exact outputs.
+    /// * [`Error::Reentrancy`] - If another operation holds the lock.
+    ///
+    /// # Events
+    ///
+    /// * Any event of the swap's [`IUniswapV4Curve`] method.
+    /// * [`ShadowQuoteDiff`] - If the shadow mode is enabled.
+    pub fn before_swap(
+        &mut self,
+        _sender: Address,
+        key: PoolKey,
+        params: SwapParams,
+        hook_data: Bytes,
+    ) -> Result<(FixedBytes<4>, I256, U24), Error> {
+        meter!("beforeSwap", Address, PoolKey, SwapParams, Bytes);
+        self.only_pool_manager()?;
+        let limit = self.check_hook_data(&hook_data)?;
+        let pool_id = pool::pool_id(&key);
+        self.check_registered(pool_id)?;
+        self.check_not_empty(pool_id)?;
+        self.check_pegged_price(pool_id)?;
+        let zero_for_one = params.zero_for_one;
+        let (input, output) = if zero_for_one {
+            (key.currency0, key.currency1)
+        } else {
+            (key.currency1, key.currency0)
+        };
+        let amount = params.amount_specified.unsigned_abs();
+        let specified = hooks::to_int128(amount)?;
+
+        let exact_input = params.amount_specified.is_negative();
+        let delta = self.with_lock(|curve| {
+            let (amount_in, amount_out) = if exact_input {
+                let amount_out =
+                    curve.swap_exact_input(pool_id, amount, input, output, zero_for_one)?;
+                (amount, amount_out)
+            } else {
+                let amount_in =
+                    curve.swap_exact_output(pool_id, amount, input, output, zero_for_one)?;
+                (amount_in, amount)
+            };
+            if let Some(limit) = limit {
+                Self::check_slippage(exact_input, limit, amount_in, amount_out)?;
+            }
+
+            // The hook takes the input from the pool manager and pays it the
+            // output.
+            let delta = if exact_input {
+                hooks::to_before_swap_delta(specified, -hooks::to_int128(amount_out)?)
+            } else {
+                hooks::to_before_swap_delta(-specified, hooks::to_int128(amount_in)?)
+            };
+            curve.settle_swap(input, output, amount_in, amount_out)?;
+            Ok(delta)
+        })?;
+
+        Ok((hooks::BEFORE_SWAP_SELECTOR, delta, U24::ZERO))
+    }
+
+    /// Returns the fee taken after each swap of the pool manager, in basis
+    /// points of the unspecified amount.
+    pub fn after_swap_fee_bps(&self) -> u16 {
+        self.after_swap_fee_bps.get().to::<u16>()
+    }
+
+    /// Sets the fee taken after each swap of the pool manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `fee_bps` - The fee in basis points of the unspecified amount.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::InvalidFee`] - If `fee_bps` exceeds [`bounds::MAX_FEE_BPS`].
+    ///
+    /// # Events
+    ///
+    /// * [`AfterSwapFeeSet`].
+    pub fn set_after_swap_fee_bps(&mut self, fee_bps: u16) -> Result<(), Error> {
+        meter!("setAfterSwapFeeBps", u16);
+        self.check_active()?;
+        self.only_owner()?;
+        bounds::check_fee(fee_bps)?;
+        self.after_swap_fee_bps.set(U16::from(fee_bps));
+        let op_seq = self.record_change(ChangeKind::AfterSwapFee, B256::ZERO, U256::from(fee_bps));
+
+        #[allow(deprecated)]
+        evm::log(AfterSwapFeeSet { op_seq, fee_bps });
+
+        Ok(())
+    }
+
+    /// Accounts a swap of the pool `key`, as Uniswap V4's `afterSwap` hook,
+    /// and takes the after-swap fee from its unspecified amount. The hook
+    /// takes the fee from the pool manager into the pool's reserve of the
+    /// unspecified currency.
+    ///
+    /// Returns the `afterSwap` selector and the fee owed to the hook.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `_sender` - The account initiating the swap on the pool manager.
+    /// * `key` - The key of the swapped pool.
+    /// * `params` - The swap parameters.
+    /// * `delta` - The `BalanceDelta` of the swap, owed to the swapper.
+    /// * `_hook_data` - Arbitrary data for the hook, ignored.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotPoolManager`] - If the caller is not the pool manager.
+    /// * [`Error::PoolNotRegistered`] - If the pool `key` is not registered.
+    /// * [`Error::AmountOverflow`] - If the fee does not fit into `int128`,
+    ///   or the reserve it is credited to overflows.
+    /// * [`Error::SettlementFailed`] - If the fee cannot be taken from the
+    ///   pool manager.
+    /// * [`Error::Reentrancy`] - If another operation holds the lock.
+    ///
+    /// # Events
+    ///
+    /// * [`SwapAccounted`].
+    pub fn after_swap(
+        &mut self,
+        _sender: Address,
+        key: PoolKey,
+        params: SwapParams,
+        delta: I256,
+        _hook_data: Bytes,
+    ) -> Result<(FixedBytes<4>, i128), Error> {
+        meter!("afterSwap", Address, PoolKey, SwapParams, I256, Bytes);
+        self.only_pool_manager()?;
+        let pool_id = pool::pool_id(&key);
+        self.check_registered(pool_id)?;
+        let (amount0, amount1) = hooks::balance_delta_amounts(delta);
+        let (volume0, volume1) = (
+            U256::from(amount0.unsigned_abs()),
+            U256::from(amount1.unsigned_abs()),
+        );
+        self.account_swap(pool_id, volume0, volume1);
+
+        // The unspecified currency is `currency1` for exact inputs of
+        // `currency0` and exact outputs of `currency1`.
+        let exact_input = params.amount_specified.is_negative();
+        let fee_in_currency1 = exact_input == params.zero_for_one;
+        let (unspecified_currency, unspecified) = if fee_in_currency1 {
+            (key.currency1, volume1)
+        } else {
+            (key.currency0, volume0)
+        };
+        let fee = unspecified - Self::deduct_fee(unspecified, self.after_swap_fee_bps())?;
+        let owed = hooks::to_int128(fee)?;
+
+        if !fee.is_zero() {
+            self.with_lock(|curve| {
+                let (mut reserve0, mut reserve1) = curve.reserves_of(pool_id);
+                let reserve = if fee_in_currency1 {
+                    &mut reserve1
+                } else {
+                    &mut reserve0
+                };
+                *reserve = reserve
+                    .checked_add(fee)
+                    .ok_or(Error::AmountOverflow(AmountOverflow {}))?;
+                curve.set_reserves_of(pool_id, reserve0, reserve1);
+                curve.take_currency(unspecified_currency, fee)
+            })?;
+        }
+
+        #[allow(deprecated)]
+        evm::log(SwapAccounted {
+            pool_id,
+            amount0,
+            amount1,
+            fee,
+        });
+
+        Ok((hooks::AFTER_SWAP_SELECTOR, owed))
+    }
+
+    /// Returns the hooks implemented by the curve, which its address must
+    /// encode.
+    pub fn get_hook_permissions(&self) -> HookPermissions {
+        hooks::hook_permissions()
+    }
+
+    /// Receives native ETH taken from the pool manager.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotPoolManager`] - If the sender is not the pool manager.
+    #[receive]
+    #[payable]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        self.only_pool_manager().map_err(Into::into)
+    }
+
+    /// Returns the currencies of the default pool, or zeroes until the owner
+    /// sets them.
+    pub fn currencies(&self) -> (Currency, Currency) {
+        (self.currency0.get(), self.currency1.get())
+    }
+
+    /// Sets the currencies of the default pool, once, so liquidity providers
+    /// can deposit them.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `currency0` - The lower currency, or [`currency::NATIVE`].
+    /// * `currency1` - The higher currency.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::PoolAlreadyRegistered`] - If the currencies are already
+    ///   set.
+    /// * [`Error::UnsortedCurrencies`] - If `currency0` does not sort before
+    ///   `currency1`.
+    ///
+    /// # Events
+    ///
+    /// * [`CurrenciesSet`].
+    pub fn set_currencies(
+        &mut self,
+        currency0: Currency,
+        currency1: Currency,
+    ) -> Result<(), Error> {
+        meter!("setCurrencies", Address, Address);
+        self.check_active()?;
+        self.only_owner()?;
+        if self.default_currencies().is_ok() {
+            return Err(Error::PoolAlreadyRegistered(PoolAlreadyRegistered {
+                pool_id: pool::DEFAULT_POOL_ID,
+            }));
+        }
+        if currency0 >= currency1 {
+            return Err(Error::UnsortedCurrencies(UnsortedCurrencies {
+                currency0,
+                currency1,
+            }));
+        }
+        self.currency0.set(currency0);
+        self.currency1.set(currency1);
+        let op_seq = self.record_change(ChangeKind::Currencies, currency1.into_word(), U256::ZERO);
+
+        #[allow(deprecated)]
+        evm::log(CurrenciesSet {
+            op_seq,
+            currency0,
+            currency1,
+        });
+
+        Ok(())
+    }
+
+    /// Registers the pool `key`, as Uniswap V4's `beforeInitialize` hook.
+    ///
+    /// Returns the `beforeInitialize` selector.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `_sender` - The account initializing the pool.
+    /// * `key` - The key of the pool.
+    /// * `_sqrt_price_x96` - The initial square root price of the pool.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotPoolManager`] - If the caller is not the pool manager.
+    /// * [`Error::PoolAlreadyRegistered`] - If the pool is already
+    ///   registered.
+    /// * [`Error::UnsupportedFeeTier`] - If the fee of `key` is not one of
+    ///   [`pool::SUPPORTED_FEE_TIERS`].
+    ///
+    /// # Events
+    ///
+    /// * [`PoolRegistered`].
+    pub fn before_initialize(
+        &mut self,
+        _sender: Address,
+        key: PoolKey,
+        _sqrt_price_x96: U160,
+    ) -> Result<FixedBytes<4>, Error> {
+        meter!("beforeInitialize", Address, PoolKey, U160);
+        self.only_pool_manager()?;
+        self.register_pool(&key)?;
+        Ok(hooks::BEFORE_INITIALIZE_SELECTOR)
+    }
+
+    /// Acknowledges the initialization of the pool `key`, as Uniswap V4's
+    /// `afterInitialize` hook.
+    ///
+    /// Returns the `afterInitialize` selector.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `_sender` - The account initializing the pool.
+    /// * `_key` - The key of the pool.
+    /// * `_sqrt_price_x96` - The initial square root price of the pool.
+    /// * `_tick` - The initial tick of the pool.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotPoolManager`] - If the caller is not the pool manager.
+    pub fn after_initialize(
+        &mut self,
+        _sender: Address,
+        _key: PoolKey,
+        _sqrt_price_x96: U160,
+        _tick: I24,
+    ) -> Result<FixedBytes<4>, Error> {
+        meter!("afterInitialize", Address, PoolKey, U160, I24);
+        self.only_pool_manager()?;
+        Ok(hooks::AFTER_INITIALIZE_SELECTOR)
+    }
+
+    /// Rejects liquidity added through the pool manager, as Uniswap V4's
+    /// `beforeAddLiquidity` hook: the curve prices swaps on its own
+    /// reserves, so liquidity must come through the hook itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `_sender` - The account adding liquidity on the pool manager.
+    /// * `_key` - The key of the pool.
+    /// * `_params` - The liquidity change parameters.
+    /// * `_hook_data` - Arbitrary data for the hook, ignored.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotPoolManager`] - If the caller is not the pool manager.
+    /// * [`Error::LiquidityViaHookOnly`] - If the caller is the pool
+    ///   manager.
+    pub fn before_add_liquidity(
+        &mut self,
+        _sender: Address,
+        _key: PoolKey,
+        _params: ModifyLiquidityParams,
+        _hook_data: Bytes,
+    ) -> Result<FixedBytes<4>, Error> {
+        meter!(
+            "beforeAddLiquidity",
+            Address,
+            PoolKey,
+            ModifyLiquidityParams,
+            Bytes
+        );
+        self.only_pool_manager()?;
+        Err(Error::LiquidityViaHookOnly(LiquidityViaHookOnly {}))
+    }
+
+    /// Rejects liquidity removed through the pool manager, as Uniswap V4's
+    /// `beforeRemoveLiquidity` hook.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `_sender` - The account removing liquidity on the pool manager.
+    /// * `_key` - The key of the pool.
+    /// * `_params` - The liquidity change parameters.
+    /// * `_hook_data` - Arbitrary data for the hook, ignored.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotPoolManager`] - If the caller is not the pool manager.
+    /// * [`Error::LiquidityViaHookOnly`] - If the caller is the pool
+    ///   manager.
+    pub fn before_remove_liquidity(
+        &mut self,
+        _sender: Address,
+        _key: PoolKey,
+        _params: ModifyLiquidityParams,
+        _hook_data: Bytes,
+    ) -> Result<FixedBytes<4>, Error> {
+        meter!(
+            "beforeRemoveLiquidity",
+            Address,
+            PoolKey,
+            ModifyLiquidityParams,
+            Bytes
+        );
+        self.only_pool_manager()?;
+        Err(Error::LiquidityViaHookOnly(LiquidityViaHookOnly {}))
+    }
+
+    /// Returns true if the hook receives currencies as ERC-6909 claims of
+    /// the pool manager instead of ERC-20 tokens.
+    pub fn claims_mode(&self) -> bool {
+        self.claims_mode.get()
+    }
+
+    /// Returns the ERC-6909 claims of `currency` the hook holds on the pool
+    /// manager.
+    pub fn claim_balance(&self, currency: Currency) -> U256 {
+        self.claim_balances.get(currency)
+    }
+
+    /// Makes the hook receive currencies as ERC-6909 claims of the pool
+    /// manager, or as ERC-20 tokens.
+    ///
+    /// Claims are spent before tokens whatever the mode, so switching it
+    /// never strands reserves.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `enabled` - True to receive claims.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`ClaimsModeSet`].
+    pub fn set_claims_mode(&mut self, enabled: bool) -> Result<(), Error> {
+        meter!("setClaimsMode", bool);
+        self.check_active()?;
+        self.only_owner()?;
+        self.claims_mode.set(enabled);
+        let op_seq = self.record_change(
+            ChangeKind::ClaimsMode,
+            B256::ZERO,
+            U256::from(u8::from(enabled)),
+        );
+
+        #[allow(deprecated)]
+        evm::log(ClaimsModeSet { op_seq, enabled });
+
+        Ok(())
+    }
+
+    /// Runs the pool operation `data` of the hook: unlocks the pool manager,
+    /// which calls back [`Self::unlock_callback`] with `data`, so the
+    /// operation's delta is cleared before the pool manager locks again.
+    ///
+    /// Returns the `BalanceDelta` of the operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `data` - The [`pool_manager::UnlockAction`] to run, the pool it
+    ///   runs on, and its parameters.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::InvalidUnlockAction`] - If the action is not a valid
+    ///   [`UnlockAction`].
+    /// * [`Error::PoolNotRegistered`] - If the pool is not registered.
+    /// * [`Error::InvalidLiquidityDelta`] - If the liquidity delta does not
+    ///   match the action.
+    /// * [`Error::PoolManagerCallFailed`] - If the pool manager or the
+    ///   callback reverts.
+    ///
+    /// # Events
+    ///
+    /// * [`UnlockActionExecuted`].
+    pub fn unlock_pool_manager(&mut self, data: UnlockCallbackData) -> Result<I256, Error> {
+        meter!("unlockPoolManager", UnlockCallbackData);
+        self.check_active()?;
+        self.only_owner()?;
+        let action = UnlockAction::try_from(data.action)?;
+        self.check_registered(pool::pool_id(&data.key))?;
+        action.check(&data)?;
+        self.unlock(data)
+    }
+
+    /// Runs a pool operation originated by the hook, as Uniswap V4's
+    /// `IUnlockCallback.unlockCallback`: the hook unlocks the pool manager,
+    /// which calls back with the payload it was given.
+    ///
+    /// `data` is an ABI-encoded [`UnlockCallbackData`]. Liquidity changes
+    /// and donations by the hook itself skip its hooks on the pool manager,
+    /// and their delta is cleared against the reserves of the pool.
+    ///
+    /// Returns the ABI-encoded `BalanceDelta` of the operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `data` - The payload passed to the pool manager's `unlock` by
+    ///   [`Self::unlock_pool_manager`].
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::NotPoolManager`] - If the caller is not the pool manager.
+    /// * [`Error::InvalidUnlockData`] - If `data` cannot be decoded.
+    /// * [`Error::InvalidUnlockAction`] - If the action is not a valid
+    ///   [`UnlockAction`].
+    /// * [`Error::PoolNotRegistered`] - If the pool is not registered.
+    /// * [`Error::InvalidLiquidityDelta`] - If the liquidity delta does not
+    ///   match the action.
+    /// * [`Error::PoolManagerCallFailed`] - If the pool manager rejects the
+    ///   operation.
+    /// * [`Error::InsufficientLiquidity`] - If the reserves of the pool cannot
+    ///   pay the operation.
+    /// * [`Error::SettlementFailed`] - If the delta cannot be cleared.
+    /// * [`Error::Reentrancy`] - If another operation holds the lock.
+    ///
+    /// # Events
+    ///
+    /// * [`UnlockActionExecuted`].
+    pub fn unlock_callback(&mut self, data: Bytes) -> Result<Bytes, Error> {
+        meter!("unlockCallback", Bytes);
+        self.check_active()?;
+        self.only_pool_manager()?;
+        let data = UnlockCallbackData::abi_decode(&data, true)
+            .map_err(|_| Error::InvalidUnlockData(InvalidUnlockData {}))?;
+        let action = UnlockAction::try_from(data.action)?;
+        self.check_registered(pool::pool_id(&data.key))?;
+        action.check(&data)?;
+
+        let delta = self.with_lock(|curve| curve.execute_unlock(action, data))?;
+
+        Ok(Bytes::from(delta.abi_encode()))
+    }
+
+    /// Returns the amount of output tokens for an exact `amount_in`, like
+    /// [`IUniswapV4Curve::get_amount_out_from_exact_input`] without emitting
+    /// its event, so callers can quote with `eth_call`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `amount_in` - The amount of input tokens.
+    /// * `input` - The input token.
+    /// * `output` - The output token.
+    /// * `zero_for_one` - True if the input token is `token0`.
+    ///
+    /// # Errors
+    ///
+    /// * Any error of [`IUniswapV4Curve::get_amount_out_from_exact_input`],
+    ///   except [`Error::RouterNotAllowed`].
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    pub fn quote_amount_out(
+        &self,
+        amount_in: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Error> {
+        self.check_quote(pool::DEFAULT_POOL_ID, input, output, amount_in)?;
+        let (amount_out, _) = self.calculate_amount_out(
+            pool::DEFAULT_POOL_ID,
+            amount_in,
+            input,
+            output,
+            zero_for_one,
+        )?;
+        Ok(amount_out)
+    }
+
+    /// Returns the amount of input tokens for an exact `amount_out`, fee
+    /// included, like [`IUniswapV4Curve::get_amount_in_for_exact_output`]
+    /// without emitting its event.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `amount_out` - The amount of output tokens the user expects to
+    ///   receive.
+    /// * `input` - The input token.
+    /// * `output` - The output token.
+    /// * `zero_for_one` - True if the input token is `token0`.
+    ///
+    /// # Errors
+    ///
+    /// * Any error of [`IUniswapV4Curve::get_amount_in_for_exact_output`],
+    ///   except [`Error::RouterNotAllowed`].
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    pub fn quote_amount_in(
+        &self,
+        amount_out: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Error> {
+        self.check_quote(pool::DEFAULT_POOL_ID, input, output, amount_out)?;
+        let (_, reserve_out) = self.directed_reserves(pool::DEFAULT_POOL_ID, input, output);
+        Self::check_liquidity(reserve_out, amount_out)?;
+        let (amount_in, _) = self.calculate_amount_in(
+            pool::DEFAULT_POOL_ID,
+            amount_out,
+            input,
+            output,
+            zero_for_one,
+        )?;
+        Ok(amount_in)
+    }
+
+    /// Returns the number of state changes recorded so far, i.e. the
+    /// sequence number of the next change.
+    pub fn change_count(&self) -> u64 {
+        self.change_count.get().to::<u64>()
+    }
+
+    /// Returns the state changes with a sequence number of at least
+    /// `from_seq` that are still in the changelog, oldest first, as
+    /// `(op_seq, kind, subject, value)` tuples.
+    ///
+    /// Only the last [`changelog::CHANGELOG_CAPACITY`] changes are kept;
+    /// indexers further behind must fall back to scanning logs.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `from_seq` - The sequence number of the first change to return.
+    pub fn recent_changes(&self, from_seq: u64) -> Vec<Change> {
+        self.changes_since(from_seq)
+    }
+
+    /// Returns the time after which the contract can be decommissioned, or
+    /// zero if the decommission is not scheduled.
+    pub fn decommission_eta(&self) -> u64 {
+        self.decommission_eta.get().to::<u64>()
+    }
+
+    /// Returns the current [`PoolState`] as its `u8` discriminant.
+    pub fn pool_state(&self) -> u8 {
+        self.current_state() as u8
+    }
+
+    /// Returns true if the contract is decommissioned.
+    pub fn decommissioned(&self) -> bool {
+        self.decommissioned.get()
+    }
+
+    /// Schedules the decommission of the contract after
+    /// [`DECOMMISSION_DELAY`].
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is already
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`DecommissionScheduled`].
+    pub fn schedule_decommission(&mut self) -> Result<(), Error> {
+        meter!("scheduleDecommission");
+        self.check_active()?;
+        self.only_owner()?;
+        let eta = self.vm().block_timestamp() + DECOMMISSION_DELAY;
+        self.set_decommission_eta(eta);
+        Ok(())
+    }
+
+    /// Cancels the scheduled decommission.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is already
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`DecommissionScheduled`] with a zero `eta`.
+    pub fn cancel_decommission(&mut self) -> Result<(), Error> {
+        meter!("cancelDecommission");
+        self.check_active()?;
+        self.only_owner()?;
+        self.set_decommission_eta(0);
+        Ok(())
+    }
+
+    /// Permanently disables every state-changing function, leaving views
+    /// working. This is the terminal state of the contract, replacing
+    /// `selfdestruct`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is already
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::DecommissionNotReady`] - If the decommission is not
+    ///   scheduled or its timelock has not passed.
+    ///
+    /// # Events
+    ///
+    /// * [`Decommissioned`].
+    pub fn decommission(&mut self) -> Result<(), Error> {
+        meter!("decommission");
+        self.check_active()?;
+        self.only_owner()?;
+        let eta = self.decommission_eta();
+        if eta == 0 || self.vm().block_timestamp() < eta {
+            return Err(Error::DecommissionNotReady(DecommissionNotReady { eta }));
+        }
+
+        let from = self.check_transition(PoolState::Retired)?;
+        self.decommissioned.set(true);
+        let op_seq = self.record_change(ChangeKind::Decommissioned, B256::ZERO, U256::ZERO);
+
+        #[allow(deprecated)]
+        evm::log(Decommissioned {
+            op_seq,
+            version: self.version.get_string(),
+            owner: self.owner.get(),
+            currency_policy: self.currency_policy(),
+            min_swap_amount: self.min_swap_amount.get(),
+            router_only: self.router_only.get(),
+        });
+        self.emit_state_change(from);
+
+        Ok(())
+    }
+
+    /// Returns the amount of output tokens for an exact-input swap, using
+    /// only the passed state instead of the contract's storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `reserve0` - The reserve of `token0`.
+    /// * `reserve1` - The reserve of `token1`.
+    /// * `fee_bps` - The swap fee in basis points.
+    /// * `rate_numerator` - The numerator of the exchange rate of the pair.
+    /// * `rate_denominator` - The denominator of the exchange rate of the
+    ///   pair. A zero numerator or denominator trades at 1:1.
+    /// * `amount_in` - The amount of input tokens.
+    /// * `zero_for_one` - True if the input token is `token0`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidFee`] - If `fee_bps` exceeds [`bounds::MAX_FEE_BPS`].
+    /// * [`Error::AmountOverflow`] - If the output amount does not fit into
+    ///   `uint256`.
+    /// * [`Error::InsufficientLiquidity`] - If the output reserve is smaller
+    ///   than the resulting amount.
+    pub fn quote_with_state(
+        reserve0: U256,
+        reserve1: U256,
+        fee_bps: u16,
+        rate_numerator: U256,
+        rate_denominator: U256,
+        amount_in: U256,
+        zero_for_one: bool,
+    ) -> Result<U256, Error> {
+        let amount_in_after_fee = Self::deduct_fee(amount_in, fee_bps)?;
+        let (numerator, denominator) =
+            Self::directed_state_rate(rate_numerator, rate_denominator, zero_for_one);
+        let amount_out = Self::convert_out(amount_in_after_fee, numerator, denominator)?;
+        let reserve_out = if zero_for_one { reserve1 } else { reserve0 };
+        Self::check_liquidity(reserve_out, amount_out)?;
+        Ok(amount_out)
+    }
+
+    /// Returns the amount of input tokens for an exact-output swap, using
+    /// only the passed state instead of the contract's storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `reserve0` - The reserve of `token0`.
+    /// * `reserve1` - The reserve of `token1`.
+    /// * `fee_bps` - The swap fee in basis points.
+    /// * `rate_numerator` - The numerator of the exchange rate of the pair.
+    /// * `rate_denominator` - The denominator of the exchange rate of the
+    ///   pair. A zero numerator or denominator trades at 1:1.
+    /// * `amount_out` - The amount of output tokens the user expects to receive.
+    /// * `zero_for_one` - True if the input token is `token0`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidFee`] - If `fee_bps` exceeds [`bounds::MAX_FEE_BPS`].
+    /// * [`Error::InsufficientLiquidity`] - If the output reserve is smaller
+    ///   than `amount_out`.
+    /// * [`Error::AmountOverflow`] - If the input amount does not fit into
+    ///   `uint256`.
+    pub fn quote_in_with_state(
+        reserve0: U256,
+        reserve1: U256,
+        fee_bps: u16,
+        rate_numerator: U256,
+        rate_denominator: U256,
+        amount_out: U256,
+        zero_for_one: bool,
+    ) -> Result<U256, Error> {
+        let reserve_out = if zero_for_one { reserve1 } else { reserve0 };
+        Self::check_liquidity(reserve_out, amount_out)?;
+        let (numerator, denominator) =
+            Self::directed_state_rate(rate_numerator, rate_denominator, zero_for_one);
+        let amount_in = Self::convert_in(amount_out, numerator, denominator)?;
+        Self::add_fee(amount_in, fee_bps)
+    }
+}
+
+#[public]
+impl IUniswapV4Curve for ConstantSumCurve {
+    type Error = Error;
+
+    fn version(&self) -> String {
+        self.version.get_string()
+    }
+
+    fn get_amount_in_for_exact_output(
+        &mut self,
+        amount_out: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Self::Error> {
+        meter!("getAmountInForExactOutput", U256, Address, Address, bool);
+        // The default pool moves no currencies, so its swaps only quote.
+        self.check_router()?;
+        let amount_in = self.quote_amount_in(amount_out, input, output, zero_for_one)?;
+
+        #[allow(deprecated)]
+        evm::log(AmountInCalculated {
+            amount_out,
+            input,
+            output,
+            zero_for_one,
+        });
+
+        Ok(amount_in)
+    }
+
+    fn get_amount_out_from_exact_input(
+        &mut self,
+        amount_in: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Self::Error> {
+        meter!("getAmountOutFromExactInput", U256, Address, Address, bool);
+        // The default pool moves no currencies, so its swaps only quote.
+        self.check_router()?;
+        let amount_out = self.quote_amount_out(amount_in, input, output, zero_for_one)?;
+
+        #[allow(deprecated)]
+        evm::log(AmountOutCalculated {
+            amount_in,
+            input,
+            output,
+            zero_for_one,
+        });
+
+        Ok(amount_out)
+    }
+}
+
+impl ConstantSumCurve {
+    /// Checks that the caller is the owner.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    fn only_owner(&self) -> Result<(), Error> {
+        let account = self.vm().msg_sender();
+        if account != self.owner.get() {
+            return Err(Error::Unauthorized(Unauthorized { account }));
+        }
+        Ok(())
+    }
+
+    /// Checks that the caller is the pool manager.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotPoolManager`] - If the caller is not the pool manager.
+    fn only_pool_manager(&self) -> Result<(), Error> {
+        let caller = self.vm().msg_sender();
+        if caller != self.pool_manager.get() {
+            return Err(Error::NotPoolManager(NotPoolManager { caller }));
+        }
+        Ok(())
+    }
+
+    /// Transfers the ownership to `new_owner`, without access control.
+    ///
+    /// # Events
+    ///
+    /// * [`OwnershipTransferred`].
+    fn update_owner(&mut self, new_owner: Address) {
+        let previous_owner = self.owner.get();
+        self.owner.set(new_owner);
+        let op_seq = self.record_change(ChangeKind::Owner, new_owner.into_word(), U256::ZERO);
+
+        #[allow(deprecated)]
+        evm::log(OwnershipTransferred {
+            op_seq,
+            previous_owner,
+            new_owner,
+        });
+    }
+
+    /// Sets the [`CurrencyPolicy`], without access control.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidCurrencyPolicy`] - If `policy` is not a valid
+    ///   [`CurrencyPolicy`].
+    ///
+    /// # Events
+    ///
+    /// * [`CurrencyPolicySet`].
+    fn update_currency_policy(&mut self, policy: u8) -> Result<(), Error> {
+        let policy = CurrencyPolicy::try_from(policy)? as u8;
+        self.currency_policy.set(U8::from(policy));
+        let op_seq = self.record_change(ChangeKind::CurrencyPolicy, B256::ZERO, U256::from(policy));
+
+        #[allow(deprecated)]
+        evm::log(CurrencyPolicySet { op_seq, policy });
+
+        Ok(())
+    }
+
+    /// Adds `currency` to or removes it from the currency list, without
+    /// access control.
+    ///
+    /// # Events
+    ///
+    /// * [`CurrencyListed`].
+    fn update_currency_listed(&mut self, currency: Currency, listed: bool) {
+        self.listed_currencies.setter(currency).set(listed);
+        let op_seq = self.record_change(
+            ChangeKind::CurrencyListed,
+            currency.into_word(),
+            U256::from(u8::from(listed)),
+        );
+
+        #[allow(deprecated)]
+        evm::log(CurrencyListed {
+            op_seq,
+            currency,
+            listed,
+        });
+    }
+
+    /// Sets the minimum amount of a swap, without access control.
+    ///
+    /// # Events
+    ///
+    /// * [`MinSwapAmountSet`].
+    fn update_min_swap_amount(&mut self, min_swap_amount: U256) {
+        self.min_swap_amount.set(min_swap_amount);
+        let op_seq = self.record_change(ChangeKind::MinSwapAmount, B256::ZERO, min_swap_amount);
+
+        #[allow(deprecated)]
+        evm::log(MinSwapAmountSet {
+            op_seq,
+            min_swap_amount,
+        });
+    }
+
+    /// Sets the reserves of the pool `pool_id`, without access control.
+    ///
+    /// # Events
+    ///
+    /// * [`PoolReservesSet`].
+    fn update_pool_reserves(&mut self, pool_id: B256, reserve0: U256, reserve1: U256) {
+        self.set_reserves_of(pool_id, reserve0, reserve1);
+        let op_seq = self.record_change(ChangeKind::PoolReserves, pool_id, reserve0);
+
+        #[allow(deprecated)]
+        evm::log(PoolReservesSet {
+            op_seq,
+            pool_id,
+            reserve0,
+            reserve1,
+        });
+    }
+
+    /// Enables or disables the router-only mode, without access control.
+    ///
+    /// # Events
+    ///
+    /// * [`RouterOnlySet`].
+    fn update_router_only(&mut self, enabled: bool) {
+        self.router_only.set(enabled);
+        let op_seq = self.record_change(
+            ChangeKind::RouterOnly,
+            B256::ZERO,
+            U256::from(u8::from(enabled)),
+        );
+
+        #[allow(deprecated)]
+        evm::log(RouterOnlySet { op_seq, enabled });
+    }
+
+    /// Adds `router` to or removes it from the router whitelist, without
+    /// access control.
+    ///
+    /// # Events
+    ///
+    /// * [`RouterSet`].
+    fn update_router(&mut self, router: Address, allowed: bool) {
+        self.routers.setter(router).set(allowed);
+        let op_seq = self.record_change(
+            ChangeKind::Router,
+            router.into_word(),
+            U256::from(u8::from(allowed)),
+        );
+
+        #[allow(deprecated)]
+        evm::log(RouterSet {
+            op_seq,
+            router,
+            allowed,
+        });
+    }
+
+    /// Checks that the contract is not decommissioned.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    fn check_active(&self) -> Result<(), Error> {
+        if self.decommissioned.get() {
+            return Err(Error::ContractDecommissioned(ContractDecommissioned {}));
+        }
+        Ok(())
+    }
+
+    /// Sets the decommission `eta`, where zero means not scheduled.
+    ///
+    /// # Events
+    ///
+    /// * [`DecommissionScheduled`].
+    fn set_decommission_eta(&mut self, eta: u64) {
+        self.decommission_eta.set(U64::from(eta));
+        let op_seq = self.record_change(
+            ChangeKind::DecommissionScheduled,
+            B256::ZERO,
+            U256::from(eta),
+        );
+
+        #[allow(deprecated)]
+        evm::log(DecommissionScheduled { op_seq, eta });
+    }
+
+    /// Checks that the caller is the fee manager or the owner.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is neither the fee manager
+    ///   nor the owner.
+    fn only_fee_manager(&self) -> Result<(), Error> {
+        let account = self.vm().msg_sender();
+        if account == self.owner.get() {
+            return Ok(());
+        }
+        if account.is_zero() || account != self.fee_manager.get() {
+            return Err(Error::Unauthorized(Unauthorized { account }));
+        }
+        Ok(())
+    }
+
+    /// Checks that the caller is the guardian and its sunset has not passed.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the guardian.
+    /// * [`Error::GuardianExpired`] - If the guardian's sunset has passed.
+    fn only_guardian(&self) -> Result<(), Error> {
+        let account = self.vm().msg_sender();
+        if account.is_zero() || account != self.guardian.get() {
+            return Err(Error::Unauthorized(Unauthorized { account }));
+        }
+        let sunset = self.guardian_sunset();
+        if self.vm().block_timestamp() >= sunset {
+            return Err(Error::GuardianExpired(GuardianExpired { sunset }));
+        }
+        Ok(())
+    }
+
+    /// Pauses or unpauses swaps on behalf of `account`.
+    ///
+    /// # Events
+    ///
+    /// * [`Paused`] or [`Unpaused`].
+    fn update_paused(&mut self, account: Address, paused: bool) {
+        self.paused.set(paused);
+        let op_seq = self.record_change(
+            ChangeKind::Paused,
+            account.into_word(),
+            U256::from(u8::from(paused)),
+        );
+
+        if paused {
+            #[allow(deprecated)]
+            evm::log(Paused { op_seq, account });
+        } else {
+            #[allow(deprecated)]
+            evm::log(Unpaused { op_seq, account });
+        }
+    }
+
+    /// Checks that the caller is a whitelisted router, if the router-only
+    /// mode is enabled.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::RouterNotAllowed`] - If the router-only mode is enabled and
+    ///   the caller is not a whitelisted router.
+    fn check_router(&self) -> Result<(), Error> {
+        let caller = self.vm().msg_sender();
+        if self.router_only.get() && !self.routers.get(caller) {
+            return Err(Error::RouterNotAllowed(RouterNotAllowed { caller }));
+        }
+        Ok(())
+    }
+
+    /// Checks that `currency` can be swapped under the current
+    /// [`CurrencyPolicy`].
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::CurrencyNotAllowed`] - If `currency` is not allowlisted or
+    ///   is denylisted.
+    fn check_currency(&self, currency: Currency) -> Result<(), Error> {
+        let listed = self.listed_currencies.get(currency);
+        let allowed = match CurrencyPolicy::try_from(self.currency_policy())? {
+            CurrencyPolicy::Open => true,
+            CurrencyPolicy::Allowlist => listed,
+            CurrencyPolicy::Denylist => !listed,
+        };
+        if !allowed {
+            return Err(Error::CurrencyNotAllowed(CurrencyNotAllowed { currency }));
+        }
+        Ok(())
+    }
+
+    /// Checks that a swap of `amount` from `input` to `output` is well-formed.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidTokenPair`] - If `input` and `output` are the same.
+    /// * [`Error::ZeroAmount`] - If `amount` is zero.
+    fn check_swap_params(input: Currency, output: Currency, amount: U256) -> Result<(), Error> {
+        if input == output {
+            return Err(Error::InvalidTokenPair(InvalidTokenPair { input, output }));
+        }
+        if amount.is_zero() {
+            return Err(Error::ZeroAmount(ZeroAmount {}));
+        }
+        Ok(())
+    }
+
+    /// Checks that the pool `pool_id` can quote a swap of `amount` from
+    /// `input` to `output`, as its swaps check before pricing, except for
+    /// the router.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::PoolEmpty`] - If both reserves of a registered pool are
+    ///   zero.
+    /// * [`Error::PriceTooOld`] - If a registered pool is pegged to the
+    ///   oracle and its price is too old.
+    /// * [`Error::ContractPaused`] - If swaps are paused.
+    /// * [`Error::PoolPaused`] - If swaps in the pool are paused.
+    /// * [`Error::InvalidTokenPair`] - If `input` and `output` are the same.
+    /// * [`Error::ZeroAmount`] - If `amount` is zero.
+    /// * [`Error::CurrencyNotAllowed`] - If either token cannot be swapped.
+    /// * [`Error::AmountTooSmall`] - If `amount` is below the minimum swap
+    ///   amount.
+    fn check_quote(
+        &self,
+        pool_id: B256,
+        input: Currency,
+        output: Currency,
+        amount: U256,
+    ) -> Result<(), Error> {
+        self.check_view_unlocked()?;
+        self.check_active()?;
+        if pool_id != pool::DEFAULT_POOL_ID {
+            self.check_not_empty(pool_id)?;
+            self.check_pegged_price(pool_id)?;
+        }
+        self.check_pool_not_paused(pool_id)?;
+        Self::check_swap_params(input, output, amount)?;
+        self.check_currency(input)?;
+        self.check_currency(output)?;
+        self.check_swap_amount(amount)
+    }
+
+    /// Checks that a swap of `amount` is not below the minimum swap amount.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountTooSmall`] - If `amount` is below the minimum swap
+    ///   amount.
+    fn check_swap_amount(&self, amount: U256) -> Result<(), Error> {
+        let min_swap_amount = self.min_swap_amount.get();
+        if amount < min_swap_amount {
+            return Err(Error::AmountTooSmall(AmountTooSmall {
+                amount,
+                min_swap_amount,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Quotes the swap of `amount` from `input` to `output` in the pool
+    /// `pool_id` with the candidate curve if the shadow mode is enabled, and
+    /// logs the difference with the `active` quote. The candidate starts from
+    /// the same pre-fee amount and the reserves before the swap. Failures of
+    /// the candidate curve are ignored.
+    ///
+    /// # Events
+    ///
+    /// * [`ShadowQuoteDiff`] - If the shadow mode is enabled.
+    fn shadow_quote(
+        &self,
+        pool_id: B256,
+        input: Currency,
+        output: Currency,
+        exact_input: bool,
+        amount: U256,
+        active: U256,
+    ) {
+        if !self.shadow_mode.get() {
+            return;
+        }
+        let Ok(shadow) = self.calculate_shadow(pool_id, input, output, exact_input, amount) else {
+            return;
+        };
+
+        #[allow(deprecated)]
+        evm::log(ShadowQuoteDiff {
+            pool_id,
+            exact_input,
+            amount,
+            active,
+            shadow,
+        });
+    }
+
+    /// Returns the quote of the candidate curve for the swap of `amount` from
+    /// `input` to `output` in the pool `pool_id`: the output of an exact
+    /// input, or the input of an exact output, fee included.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidShadowCurve`] - If the stored curve is not a valid
+    ///   [`ShadowCurve`].
+    /// * [`Error::AmountOverflow`] - If an amount does not fit into
+    ///   `uint256`.
+    /// * [`Error::InsufficientLiquidity`] - If a constant-product exact
+    ///   output drains the output reserve.
+    fn calculate_shadow(
+        &self,
+        pool_id: B256,
+        input: Currency,
+        output: Currency,
+        exact_input: bool,
+        amount: U256,
+    ) -> Result<U256, Error> {
+        let curve = ShadowCurve::try_from(self.shadow_curve())?;
+        let fee_bps = self.shadow_fee_bps();
+        let (reserve_in, reserve_out) = self.directed_reserves(pool_id, input, output);
+        let (numerator, denominator) = self.directed_rate(input, output);
+        if exact_input {
+            let amount_in = Self::deduct_fee(amount, fee_bps)?;
+            match curve {
+                ShadowCurve::ConstantSum => Self::convert_out(amount_in, numerator, denominator),
+                ShadowCurve::ConstantProduct => {
+                    ConstantProductCurve::calculate_amount_out(reserve_in, reserve_out, amount_in)
+                }
+            }
+        } else {
+            let amount_in = match curve {
+                ShadowCurve::ConstantSum => Self::convert_in(amount, numerator, denominator)?,
+                ShadowCurve::ConstantProduct => {
+                    ConstantProductCurve::calculate_amount_in(reserve_in, reserve_out, amount)?
+                }
+            };
+            Self::add_fee(amount_in, fee_bps)
+        }
+    }
+
+    /// Swaps `input` for an exact `amount_out` of `output` in the registered
+    /// pool `pool_id`, and returns the amount of input tokens, fee included.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolPaused`] - If swaps in the pool are paused.
+    /// * Any error of [`IUniswapV4Curve::get_amount_in_for_exact_output`].
+    ///
+    /// # Events
+    ///
+    /// * [`FeeCharged`].
+    /// * [`AmountInCalculated`].
+    /// * [`ShadowQuoteDiff`] - If the shadow mode is enabled.
+    fn swap_exact_output(
+        &mut self,
+        pool_id: B256,
+        amount_out: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Error> {
+        trace::capture(|| {
+            self.check_active()?;
+            self.check_pool_not_paused(pool_id)?;
+            self.check_router()?;
+            Self::check_swap_params(input, output, amount_out)?;
+            trace!("swap params valid");
+            self.check_currency(input)?;
+            self.check_currency(output)?;
+            trace!("currencies allowed");
+            self.check_swap_amount(amount_out)?;
+            trace!("amount_out {amount_out} above minimum");
+            let (_, reserve_out) = self.directed_reserves(pool_id, input, output);
+            Self::check_liquidity(reserve_out, amount_out)?;
+            trace!("amount_out {amount_out} within reserve");
+
+            // Calculate `amount_in` based on swap params.
+            let (amount_in, fee) =
+                self.calculate_amount_in(pool_id, amount_out, input, output, zero_for_one)?;
+            trace!("amount_in {amount_in} with fee {fee}");
+            self.shadow_quote(pool_id, input, output, false, amount_out, amount_in);
+            self.update_swapped_reserves(pool_id, input, output, amount_in, amount_out)?;
+
+            #[allow(deprecated)]
+            evm::log(FeeCharged {
+                currency: input,
+                fee,
+            });
+            #[allow(deprecated)]
+            evm::log(AmountInCalculated {
+                amount_out,
+                input,
+                output,
+                zero_for_one,
+            });
+
+            Ok(amount_in)
+        })
+    }
+
+    /// Swaps an exact `amount_in` of `input` for `output` in the registered
+    /// pool `pool_id`, and returns the amount of output tokens.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolPaused`] - If swaps in the pool are paused.
+    /// * [`Error::InsufficientLiquidity`] - If the output reserve of the pool
+    ///   is smaller than the resulting amount.
+    /// * Any error of [`IUniswapV4Curve::get_amount_out_from_exact_input`].
+    ///
+    /// # Events
+    ///
+    /// * [`FeeCharged`].
+    /// * [`AmountOutCalculated`].
+    /// * [`ShadowQuoteDiff`] - If the shadow mode is enabled.
+    fn swap_exact_input(
+        &mut self,
+        pool_id: B256,
+        amount_in: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Error> {
+        trace::capture(|| {
+            self.check_active()?;
+            self.check_pool_not_paused(pool_id)?;
+            self.check_router()?;
+            Self::check_swap_params(input, output, amount_in)?;
+            trace!("swap params valid");
+            self.check_currency(input)?;
+            self.check_currency(output)?;
+            trace!("currencies allowed");
+            self.check_swap_amount(amount_in)?;
+            trace!("amount_in {amount_in} above minimum");
+
+            let (amount_out, fee) =
+                self.calculate_amount_out(pool_id, amount_in, input, output, zero_for_one)?;
+            trace!("amount_out {amount_out} with fee {fee}");
+            // Pools of the pool manager pay out real currencies, so they
+            // cannot pay more than their reserve.
+            let (_, reserve_out) = self.directed_reserves(pool_id, input, output);
+            Self::check_liquidity(reserve_out, amount_out)?;
+            self.shadow_quote(pool_id, input, output, true, amount_in, amount_out);
+            self.update_swapped_reserves(pool_id, input, output, amount_in, amount_out)?;
+
+            #[allow(deprecated)]
+            evm::log(FeeCharged {
+                currency: input,
+                fee,
+            });
+            #[allow(deprecated)]
+            evm::log(AmountOutCalculated {
+                amount_in,
+                input,
+                output,
+                zero_for_one,
+            });
+
+            Ok(amount_out)
+        })
+    }
+
+    /// Calculates the amount of input tokens for an exact-output swap,
+    /// including the swap fee, and the fee charged on them.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `pool_id` - The pool to swap in.
+    /// * `amount_out` the amount of output tokens the user expects to receive.
+    /// * `input` - The input token.
+    /// * `output` - The output token.
+    /// * `zero_for_one` - True if the input token is `token0`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If `amount_in` does not fit into
+    ///   `uint256`.
+    /// * [`Error::InvalidPhase`] - If a bootstrap auction is scheduled.
+    /// * [`Error::BootstrapBuyOnly`] - If the swap sells `token0` during the
+    ///   bootstrap auction.
+    /// * [`Error::InsufficientLiquidity`] - If `amount_out` drains the output
+    ///   reserve during the bootstrap auction.
+    fn calculate_amount_in(
+        &self,
+        pool_id: B256,
+        amount_out: U256,
+        input: Currency,
+        output: Currency,
+        _zero_for_one: bool,
+    ) -> Result<(U256, U256), Error> {
+        let amount_in =
+            if let Some((weight_in, weight_out)) = self.bootstrap_weights(input, output)? {
+                // During the bootstrap auction, tokens trade along the weighted
+                // curve of the reserves.
+                let (reserve_in, reserve_out) = self.directed_reserves(pool_id, input, output);
+                WeightedCurve::calculate_amount_in(
+                    reserve_in,
+                    weight_in,
+                    reserve_out,
+                    weight_out,
+                    amount_out,
+                )?
+            } else {
+                // In constant-sum curve, tokens trade at a constant exchange rate,
+                // rounded up in favor of the pool.
+                let (numerator, denominator) = self.directed_rate(input, output);
+                Self::convert_in(amount_out, numerator, denominator)?
+            };
+        // The fee is added on top, rounded up as well.
+        let amount_in_with_fee = Self::add_fee(amount_in, self.fee_of(pool_id))?;
+
+        Ok((amount_in_with_fee, amount_in_with_fee - amount_in))
+    }
+
+    /// Returns the amount of output tokens for an exact-input swap, after
+    /// the swap fee, and the fee charged on the input tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `pool_id` - The pool to swap in.
+    /// * `amount_in` - The amount of input tokens.
+    /// * `input` - The input token.
+    /// * `output` - The output token.
+    /// * `zero_for_one` - True if the input token is `token_0`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If `amount_out` does not fit into
+    ///   `uint256`.
+    /// * [`Error::InvalidPhase`] - If a bootstrap auction is scheduled.
+    /// * [`Error::BootstrapBuyOnly`] - If the swap sells `token0` during the
+    ///   bootstrap auction.
+    fn calculate_amount_out(
+        &self,
+        pool_id: B256,
+        amount_in: U256,
+        input: Currency,
+        output: Currency,
+        _zero_for_one: bool,
+    ) -> Result<(U256, U256), Error> {
+        // The fee is deducted from the input first, rounded down.
+        let amount_in_after_fee = Self::deduct_fee(amount_in, self.fee_of(pool_id))?;
+        let amount_out =
+            if let Some((weight_in, weight_out)) = self.bootstrap_weights(input, output)? {
+                // During the bootstrap auction, tokens trade along the weighted
+                // curve of the reserves.
+                let (reserve_in, reserve_out) = self.directed_reserves(pool_id, input, output);
+                WeightedCurve::calculate_amount_out(
+                    reserve_in,
+                    weight_in,
+                    reserve_out,
+                    weight_out,
+                    amount_in_after_fee,
+                )?
+            } else {
+                // in constant-sum curve, tokens trade at a constant exchange rate,
+                // rounded down in favor of the pool.
+                let (numerator, denominator) = self.directed_rate(input, output);
+                Self::convert_out(amount_in_after_fee, numerator, denominator)?
+            };
+
+        Ok((amount_out, amount_in - amount_in_after_fee))
+    }
+
+    /// Returns the output of `amount_in` traded at the rate
+    /// `numerator / denominator`, rounded down in favor of the pool.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If the output does not fit into
+    ///   `uint256`.
+    fn convert_out(amount_in: U256, numerator: U256, denominator: U256) -> Result<U256, Error> {
+        math::mul_div(amount_in, numerator, denominator)
+            .ok_or(Error::AmountOverflow(AmountOverflow {}))
+    }
+
+    /// Returns the input buying `amount_out` at the rate
+    /// `numerator / denominator`, rounded up in favor of the pool.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If the input does not fit into
+    ///   `uint256`.
+    fn convert_in(amount_out: U256, numerator: U256, denominator: U256) -> Result<U256, Error> {
+        math::mul_div_up(amount_out, denominator, numerator)
+            .ok_or(Error::AmountOverflow(AmountOverflow {}))
+    }
+
+    /// Returns the rate `numerator / denominator` of `currency0` in
+    /// `currency1` in the direction of a swap, like [`Self::directed_rate`]
+    /// does for stored rates. A zero numerator or denominator trades at 1:1.
+    fn directed_state_rate(numerator: U256, denominator: U256, zero_for_one: bool) -> (U256, U256) {
+        if numerator.is_zero() || denominator.is_zero() {
+            (U256::from(1), U256::from(1))
+        } else if zero_for_one {
+            (numerator, denominator)
+        } else {
+            (denominator, numerator)
+        }
+    }
+
+    /// Returns the tracked reserves of `input` and `output` in the pool
+    /// `pool_id`.
+    fn directed_reserves(&self, pool_id: B256, input: Currency, output: Currency) -> (U256, U256) {
+        let (reserve0, reserve1) = self.reserves_of(pool_id);
+        if input < output {
+            (reserve0, reserve1)
+        } else {
+            (reserve1, reserve0)
+        }
+    }
+
+    /// Moves `amount_in`, fee included, into the reserve of `input` and
+    /// `amount_out` out of the reserve of `output` in the registered pool
+    /// `pool_id`. The output reserve is clamped at zero.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If the input reserve does not fit into
+    ///   `uint256`.
+    fn update_swapped_reserves(
+        &mut self,
+        pool_id: B256,
+        input: Currency,
+        output: Currency,
+        amount_in: U256,
+        amount_out: U256,
+    ) -> Result<(), Error> {
+        let (reserve_in, reserve_out) = self.directed_reserves(pool_id, input, output);
+        let reserve_in_after = reserve_in
+            .checked_add(amount_in)
+            .ok_or(Error::AmountOverflow(AmountOverflow {}))?;
+        let reserve_out_after = reserve_out.saturating_sub(amount_out);
+        if input < output {
+            self.set_reserves_of(pool_id, reserve_in_after, reserve_out_after);
+        } else {
+            self.set_reserves_of(pool_id, reserve_out_after, reserve_in_after);
+        }
+        Ok(())
+    }
+
+    /// Returns `amount` reduced by a fee of `fee_bps`, rounded down.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - The amount to charge the fee on.
+    /// * `fee_bps` - The fee in basis points.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidFee`] - If `fee_bps` exceeds [`bounds::MAX_FEE_BPS`].
+    fn deduct_fee(amount: U256, fee_bps: u16) -> Result<U256, Error> {
+        bounds::check_fee(fee_bps)?;
+        // Split `amount` to compute `amount * fee_bps / FEE_DENOMINATOR`
+        // without overflowing.
+        let denominator = U256::from(FEE_DENOMINATOR);
+        let fee_bps = U256::from(fee_bps);
+        let fee = amount / denominator * fee_bps + amount % denominator * fee_bps / denominator;
+        Ok(amount - fee)
+    }
+
+    /// Returns the amount that leaves exactly `amount` after a fee of
+    /// `fee_bps` is deducted, rounded up.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount` - The amount left after the fee.
+    /// * `fee_bps` - The fee in basis points.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidFee`] - If `fee_bps` exceeds [`bounds::MAX_FEE_BPS`].
+    /// * [`Error::AmountOverflow`] - If the result does not fit into
+    ///   `uint256`.
+    fn add_fee(amount: U256, fee_bps: u16) -> Result<U256, Error> {
+        bounds::check_fee(fee_bps)?;
+        let denominator = U256::from(FEE_DENOMINATOR);
+        let numerator = amount
+            .checked_mul(denominator)
+            .ok_or(Error::AmountOverflow(AmountOverflow {}))?;
+        Ok(numerator.div_ceil(denominator - U256::from(fee_bps)))
+    }
+
+    /// Checks that the quote a swap relies upon, whose `quote_valid_until`
+    /// is ABI-encoded in `hook_data`, has not expired, and returns the
+    /// slippage limit encoded after it, if any. Empty hook data skips the
+    /// check.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidHookData`] - If `hook_data` cannot be decoded.
+    /// * [`Error::QuoteExpired`] - If the quote expired.
+    fn check_hook_data(&self, hook_data: &[u8]) -> Result<Option<U256>, Error> {
+        if hook_data.is_empty() {
+            return Ok(None);
+        }
+        let invalid = |_| Error::InvalidHookData(InvalidHookData {});
+        let (valid_until, limit) = if hook_data.len() == 32 {
+            (u64::abi_decode(hook_data, true).map_err(invalid)?, None)
+        } else {
+            let (valid_until, limit) =
+                <(u64, U256)>::abi_decode_params(hook_data, true).map_err(invalid)?;
+            (valid_until, Some(limit))
+        };
+        let timestamp = self.vm().block_timestamp();
+        if timestamp > valid_until {
+            return Err(Error::QuoteExpired(QuoteExpired {
+                valid_until,
+                timestamp,
+            }));
+        }
+        Ok(limit)
+    }
+
+    /// Checks that a swap stays within the swapper's slippage `limit`: the
+    /// minimum `amount_out` of an exact input, or the maximum `amount_in`
+    /// of an exact output.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::SlippageExceeded`] - If the swap crosses `limit`.
+    fn check_slippage(
+        exact_input: bool,
+        limit: U256,
+        amount_in: U256,
+        amount_out: U256,
+    ) -> Result<(), Error> {
+        let exceeded = if exact_input {
+            amount_out < limit
+        } else {
+            amount_in > limit
+        };
+        if exceeded {
+            let actual = if exact_input { amount_out } else { amount_in };
+            return Err(Error::SlippageExceeded(SlippageExceeded { limit, actual }));
+        }
+        Ok(())
+    }
+
+    /// Checks that `reserve` can cover an outflow of `amount`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InsufficientLiquidity`] - If `reserve` is smaller than
+    ///   `amount`.
+    fn check_liquidity(reserve: U256, amount: U256) -> Result<(), Error> {
+        if amount > reserve {
+            return Err(Error::InsufficientLiquidity(InsufficientLiquidity {
+                available: reserve,
+                requested: amount,
+            }));
+        }
+        Ok(())
+    }
+}
+
+/// Unit tests
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{address, aliases::I24, keccak256, Address, FixedBytes};
+    use motsu::prelude::Contract;
+    use stylus_sdk::function_selector;
+
+    use super::*;
+    use crate::testing::{
+        deploy, deploy_multi_pool, hook, pool_key, seed_liquidity, CURRENCY_1, CURRENCY_2, HOOK,
+        POOLS, POOL_MANAGER, RESERVE,
+    };
+
+    #[test]
+    fn sample_test() {
+        assert_eq!(4, 2 + 2);
+    }
+
+    /// The Solidity caller of the test harness only calls exported
+    /// functions, with their exported parameters.
+    #[cfg(feature = "export-abi")]
+    #[test]
+    fn harness_caller_matches_exported_abi() {
+        assert_eq!(Vec::<String>::new(), harness::missing_functions());
+    }
+
+    /// Freezes the storage layout: moving a field breaks the state of
+    /// deployed contracts, so only appending fields is expected.
+    #[cfg(feature = "storage-layout")]
+    #[test]
+    fn storage_layout_is_frozen() {
+        let layout: Vec<_> = layout::storage_layout()
+            .into_iter()
+            .map(|slot| (slot.name, slot.slot, slot.offset, slot.bytes))
+            .collect();
+        assert_eq!(
+            vec![
+                ("version", 0, 0, 32),
+                ("owner", 1, 0, 20),
+                ("currency_policy", 1, 20, 1),
+                ("listed_currencies", 2, 0, 32),
+                ("min_swap_amount", 3, 0, 32),
+                ("router_only", 4, 0, 1),
+                ("routers", 5, 0, 32),
+                ("changelog", 6, 0, 32),
+                ("change_count", 7, 0, 8),
+                ("decommission_eta", 7, 8, 8),
+                ("decommissioned", 7, 16, 1),
+                ("admin_nonce", 8, 0, 32),
+                ("signers", 9, 0, 32),
+                ("signer_count", 10, 0, 1),
+                ("threshold", 10, 1, 1),
+                ("proposals", 11, 0, 32),
+                ("proposal_count", 12, 0, 32),
+                ("guardian", 13, 0, 20),
+                ("guardian_sunset", 13, 20, 8),
+                ("paused", 13, 28, 1),
+                ("shadow_mode", 13, 29, 1),
+                ("shadow_fee_bps", 13, 30, 2),
+                ("reporters", 14, 0, 32),
+                ("reporter_list", 15, 0, 32),
+                ("reports", 16, 0, 32),
+                ("max_deviation_bps", 17, 0, 2),
+                ("max_price_age", 17, 2, 8),
+                ("price", 18, 0, 32),
+                ("price_timestamp", 19, 0, 8),
+                ("default_rate", 20, 0, 64),
+                ("exchange_rates", 22, 0, 32),
+                ("fee_bps", 23, 0, 2),
+                ("reserve0", 24, 0, 32),
+                ("reserve1", 25, 0, 32),
+                ("bootstrap", 26, 0, 96),
+                ("after_swap_fee_bps", 29, 0, 2),
+                ("pools", 30, 0, 32),
+                ("pool_manager", 31, 0, 20),
+                ("claims_mode", 31, 20, 1),
+                ("claim_balances", 32, 0, 32),
+                ("fee_manager", 33, 0, 20),
+                ("currency0", 34, 0, 20),
+                ("currency1", 35, 0, 20),
+                ("total_shares", 36, 0, 32),
+                ("shares", 37, 0, 32),
+                ("self_test_passed", 38, 0, 1),
+                ("allowances", 39, 0, 32),
+                ("shadow_curve", 40, 0, 1),
+                ("pool_index", 41, 0, 32),
+            ],
+            layout
+        );
+    }
+
+    /// Signatures and selectors of every public function, as routed by the
+    /// contract.
+    fn selectors() -> Vec<(&'static str, [u8; 4])> {
+        vec![
+            ("version()", function_selector!("version")),
+            (
+                "getAmountInForExactOutput(uint256,address,address,bool)",
+                function_selector!("getAmountInForExactOutput", U256, Address, Address, bool),
+            ),
+            (
+                "getAmountOutFromExactInput(uint256,address,address,bool)",
+                function_selector!("getAmountOutFromExactInput", U256, Address, Address, bool),
+            ),
+            (
+                "quoteWithState(uint256,uint256,uint16,uint256,uint256,uint256,bool)",
+                function_selector!("quoteWithState", U256, U256, u16, U256, U256, U256, bool),
+            ),
+            (
+                "quoteInWithState(uint256,uint256,uint16,uint256,uint256,uint256,bool)",
+                function_selector!("quoteInWithState", U256, U256, u16, U256, U256, U256, bool),
+            ),
+            ("owner()", function_selector!("owner")),
+            ("currencyPolicy()", function_selector!("currencyPolicy")),
+            (
+                "setCurrencyPolicy(uint8)",
+                function_selector!("setCurrencyPolicy", u8),
+            ),
+            (
+                "isCurrencyListed(address)",
+                function_selector!("isCurrencyListed", Address),
+            ),
+            (
+                "setCurrencyListed(address,bool)",
+                function_selector!("setCurrencyListed", Address, bool),
+            ),
+            ("minSwapAmount()", function_selector!("minSwapAmount")),
+            (
+                "setMinSwapAmount(uint256)",
+                function_selector!("setMinSwapAmount", U256),
+            ),
+            ("routerOnly()", function_selector!("routerOnly")),
+            (
+                "setRouterOnly(bool)",
+                function_selector!("setRouterOnly", bool),
+            ),
+            ("isRouter(address)", function_selector!("isRouter", Address)),
+            (
+                "setRouter(address,bool)",
+                function_selector!("setRouter", Address, bool),
+            ),
+            ("changeCount()", function_selector!("changeCount")),
+            (
+                "recentChanges(uint64)",
+                function_selector!("recentChanges", u64),
+            ),
+            (
+                "quoteExactInputSingle((address,address,uint256,uint24,uint160))",
+                function_selector!("quoteExactInputSingle", QuoteExactInputSingleParams),
+            ),
+            (
+                "quoteExactOutputSingle((address,address,uint256,uint24,uint160))",
+                function_selector!("quoteExactOutputSingle", QuoteExactOutputSingleParams),
+            ),
+            ("quoteValidUntil()", function_selector!("quoteValidUntil")),
+            ("decommissionEta()", function_selector!("decommissionEta")),
+            ("decommissioned()", function_selector!("decommissioned")),
+            (
+                "scheduleDecommission()",
+                function_selector!("scheduleDecommission"),
+            ),
+            (
+                "cancelDecommission()",
+                function_selector!("cancelDecommission"),
+            ),
+            ("decommission()", function_selector!("decommission")),
+            ("adminNonce()", function_selector!("adminNonce")),
+            (
+                "executeSignedAdminAction(uint8,address,uint256,uint64,bytes)",
+                function_selector!("executeSignedAdminAction", u8, Address, U256, u64, Bytes),
+            ),
+            (
+                "transferOwnership(address)",
+                function_selector!("transferOwnership", Address),
+            ),
+            ("isSigner(address)", function_selector!("isSigner", Address)),
+            ("signerCount()", function_selector!("signerCount")),
+            ("threshold()", function_selector!("threshold")),
+            (
+                "configureMultisig(address[],uint8)",
+                function_selector!("configureMultisig", Vec<Address>, u8),
+            ),
+            ("proposalCount()", function_selector!("proposalCount")),
+            ("proposal(uint256)", function_selector!("proposal", U256)),
+            (
+                "proposeCriticalAction(uint8,address,uint256)",
+                function_selector!("proposeCriticalAction", u8, Address, U256),
+            ),
+            (
+                "approveCriticalAction(uint256)",
+                function_selector!("approveCriticalAction", U256),
+            ),
+            (
+                "executeCriticalAction(uint256)",
+                function_selector!("executeCriticalAction", U256),
+            ),
+            ("guardian()", function_selector!("guardian")),
+            ("guardianSunset()", function_selector!("guardianSunset")),
+            (
+                "setGuardian(address,uint64)",
+                function_selector!("setGuardian", Address, u64),
+            ),
+            ("paused()", function_selector!("paused")),
+            ("pause()", function_selector!("pause")),
+            ("unpause()", function_selector!("unpause")),
+            ("shadowMode()", function_selector!("shadowMode")),
+            ("shadowCurve()", function_selector!("shadowCurve")),
+            ("shadowFeeBps()", function_selector!("shadowFeeBps")),
+            (
+                "setShadowMode(bool,uint8,uint16)",
+                function_selector!("setShadowMode", bool, u8, u16),
+            ),
+            (
+                "isReporter(address)",
+                function_selector!("isReporter", Address),
+            ),
+            (
+                "setReporter(address,bool)",
+                function_selector!("setReporter", Address, bool),
+            ),
+            ("maxPriceAge()", function_selector!("maxPriceAge")),
+            (
+                "setMaxPriceAge(uint64)",
+                function_selector!("setMaxPriceAge", u64),
+            ),
+            (
+                "postPrice(uint256,uint64,uint256,bytes)",
+                function_selector!("postPrice", U256, u64, U256, Bytes),
+            ),
+            ("latestPrice()", function_selector!("latestPrice")),
+            (
+                "exchangeRate(address,address)",
+                function_selector!("exchangeRate", Address, Address),
+            ),
+            (
+                "setExchangeRate(address,address,uint256,uint256)",
+                function_selector!("setExchangeRate", Address, Address, U256, U256),
+            ),
+            ("reporters()", function_selector!("reporters")),
+            ("maxDeviationBps()", function_selector!("maxDeviationBps")),
+            (
+                "setMaxDeviationBps(uint16)",
+                function_selector!("setMaxDeviationBps", u16),
+            ),
+            (
+                "reporterNonce(address)",
+                function_selector!("reporterNonce", Address),
+            ),
+            (
+                "postPrices((uint256,uint64,uint256,bytes)[])",
+                function_selector!("postPrices", Vec<SignedPriceReport>),
+            ),
+            ("fee()", function_selector!("fee")),
+            ("getReserves()", function_selector!("getReserves")),
+            (
+                "setReserves(uint256,uint256)",
+                function_selector!("setReserves", U256, U256),
+            ),
+            (
+                "beforeSwap(address,(address,address,uint24,int24,address),(bool,int256,uint160),bytes)",
+                function_selector!("beforeSwap", Address, PoolKey, SwapParams, Bytes),
+            ),
+            ("phase()", function_selector!("phase")),
+            ("bootstrap()", function_selector!("bootstrap")),
+            ("scheduleBootstrap(uint64,uint64,uint256,uint256)", function_selector!("scheduleBootstrap", u64, u64, U256, U256)),
+            ("cancelBootstrap()", function_selector!("cancelBootstrap")),
+            ("afterSwapFeeBps()", function_selector!("afterSwapFeeBps")),
+            ("setAfterSwapFeeBps(uint16)", function_selector!("setAfterSwapFeeBps", u16)),
+            ("volume(bytes32)", function_selector!("volume", B256)),
+            ("lastPrice(bytes32)", function_selector!("lastPrice", B256)),
+            (
+                "afterSwap(address,(address,address,uint24,int24,address),(bool,int256,uint160),int256,bytes)",
+                function_selector!("afterSwap", Address, PoolKey, SwapParams, I256, Bytes),
+            ),
+            ("poolState()", function_selector!("poolState")),
+            (
+                "beforeAddLiquidity(address,(address,address,uint24,int24,address),(int24,int24,int256,bytes32),bytes)",
+                function_selector!("beforeAddLiquidity", Address, PoolKey, ModifyLiquidityParams, Bytes),
+            ),
+            (
+                "beforeRemoveLiquidity(address,(address,address,uint24,int24,address),(int24,int24,int256,bytes32),bytes)",
+                function_selector!("beforeRemoveLiquidity", Address, PoolKey, ModifyLiquidityParams, Bytes),
+            ),
+            ("poolKey(bytes32)", function_selector!("poolKey", B256)),
+            (
+                "poolId((address,address,uint24,int24,address))",
+                function_selector!("poolId", PoolKey),
+            ),
+            (
+                "beforeInitialize(address,(address,address,uint24,int24,address),uint160)",
+                function_selector!("beforeInitialize", Address, PoolKey, U160),
+            ),
+            (
+                "afterInitialize(address,(address,address,uint24,int24,address),uint160,int24)",
+                function_selector!("afterInitialize", Address, PoolKey, U160, I24),
+            ),
+            ("getHookPermissions()", function_selector!("getHookPermissions")),
+            ("poolReserves(bytes32)", function_selector!("poolReserves", B256)),
+            ("setPoolReserves(bytes32,uint256,uint256)", function_selector!("setPoolReserves", B256, U256, U256)),
+            ("poolFee(bytes32)", function_selector!("poolFee", B256)),
+            ("setPoolFee(bytes32,uint16)", function_selector!("setPoolFee", B256, u16)),
+            ("poolPaused(bytes32)", function_selector!("poolPaused", B256)),
+            ("setPoolPaused(bytes32,bool)", function_selector!("setPoolPaused", B256, bool)),
+            ("poolManager()", function_selector!("poolManager")),
+            (
+                "unlockPoolManager((uint8,(address,address,uint24,int24,address),(int24,int24,int256,bytes32),uint256,uint256))",
+                function_selector!("unlockPoolManager", UnlockCallbackData),
+            ),
+            ("unlockCallback(bytes)", function_selector!("unlockCallback", Bytes)),
+            ("claimsMode()", function_selector!("claimsMode")),
+            ("claimBalance(address)", function_selector!("claimBalance", Address)),
+            ("setClaimsMode(bool)", function_selector!("setClaimsMode", bool)),
+            ("poolPrice(bytes32)", function_selector!("poolPrice", B256)),
+            ("poolMaxPriceAge(bytes32)", function_selector!("poolMaxPriceAge", B256)),
+            ("setPoolMaxPriceAge(bytes32,uint64)", function_selector!("setPoolMaxPriceAge", B256, u64)),
+            ("feeManager()", function_selector!("feeManager")),
+            ("setFeeManager(address)", function_selector!("setFeeManager", Address)),
+            ("deposit(bytes32,uint256,uint256)", function_selector!("deposit", B256, U256, U256)),
+            ("withdraw(bytes32,uint256,uint256,address)", function_selector!("withdraw", B256, U256, U256, Address)),
+            ("bounds()", function_selector!("bounds")),
+            ("currencies()", function_selector!("currencies")),
+            ("setCurrencies(address,address)", function_selector!("setCurrencies", Address, Address)),
+            ("addLiquidity(uint256,uint256)", function_selector!("addLiquidity", U256, U256)),
+            ("selfTestPassed()", function_selector!("selfTestPassed")),
+            ("selfTest()", function_selector!("selfTest")),
+            ("removeLiquidity(uint256,uint256,uint256)", function_selector!("removeLiquidity", U256, U256, U256)),
+            ("name()", function_selector!("name")),
+            ("symbol()", function_selector!("symbol")),
+            ("decimals()", function_selector!("decimals")),
+            ("totalSupply()", function_selector!("totalSupply")),
+            ("balanceOf(address)", function_selector!("balanceOf", Address)),
+            ("allowance(address,address)", function_selector!("allowance", Address, Address)),
+            ("transfer(address,uint256)", function_selector!("transfer", Address, U256)),
+            ("approve(address,uint256)", function_selector!("approve", Address, U256)),
+            ("transferFrom(address,address,uint256)", function_selector!("transferFrom", Address, Address, U256)),
+            ("capabilities()", function_selector!("capabilities")),
+            ("getPrice(bytes32)", function_selector!("getPrice", B256)),
+            ("quoteAmountOut(uint256,address,address,bool)", function_selector!("quoteAmountOut", U256, Address, Address, bool)),
+            ("quoteAmountIn(uint256,address,address,bool)", function_selector!("quoteAmountIn", U256, Address, Address, bool)),
+        ]
+    }
+
+    #[test]
+    fn selectors_match_manifest() {
+        let manifest: Vec<(FixedBytes<4>, &str)> = include_str!("../abi/selectors.txt")
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (selector, signature) = line
+                    .split_once(' ')
+                    .expect("should be `<selector> <signature>`");
+                (
+                    selector.parse().expect("should be a hex selector"),
+                    signature,
+                )
+            })
+            .collect();
+
+        let selectors = selectors();
+        assert_eq!(
+            manifest.len(),
+            selectors.len(),
+            "manifest and contract should have the same functions"
+        );
+
+        for (signature, selector) in selectors {
+            assert_eq!(
+                keccak256(signature)[..4],
+                selector,
+                "selector of `{signature}` should match its signature"
+            );
+            let (frozen, _) = manifest
+                .iter()
+                .find(|(_, frozen_signature)| *frozen_signature == signature)
+                .unwrap_or_else(|| panic!("`{signature}` should be in the manifest"));
+            assert_eq!(
+                *frozen,
+                FixedBytes::from(selector),
+                "selector of `{signature}` should be frozen"
+            );
+        }
+    }
+
+    #[motsu::test]
+    fn calculates_amount_in(alice: Address) {
+        let contract = hook();
+        let amount_out = uint!(1_U256);
+        let expected_amount_in = amount_out; // 1:1 swap
+        let (amount_in, fee) = contract
+            .sender(alice)
+            .calculate_amount_in(
+                pool::DEFAULT_POOL_ID,
+                amount_out,
+                CURRENCY_1,
+                CURRENCY_2,
+                true,
+            )
+            .expect("should calculate `amount_in`");
+        assert_eq!(expected_amount_in, amount_in);
+        assert_eq!(U256::ZERO, fee);
+    }
+
+    #[motsu::test]
+    fn calculates_amount_out(alice: Address) {
+        let contract = hook();
+        let amount_in = uint!(2_U256);
+        let expected_amount_out = amount_in; // 1:1 swap
+        let (amount_out, fee) = contract
+            .sender(alice)
+            .calculate_amount_out(
+                pool::DEFAULT_POOL_ID,
+                amount_in,
+                CURRENCY_1,
+                CURRENCY_2,
+                true,
+            )
+            .expect("should calculate `amount_out`");
+        assert_eq!(expected_amount_out, amount_out);
+        assert_eq!(U256::ZERO, fee);
+    }
+
+    #[motsu::test]
+    fn constructor_emits_deployed(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract.assert_emitted(&Deployed {
+            version: String::from("1.0.0"),
+            owner: alice,
+            chain_id: contract.sender(alice).vm().chain_id(),
+            feature_bitmap: features::FEATURE_BITMAP,
+        });
+        assert_eq!(
+            features::FEATURE_BITMAP & features::TRACE != 0,
+            cfg!(feature = "trace")
+        );
+    }
+
+    #[motsu::test]
+    fn capabilities_list_compiled_subsystems(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        let capabilities = contract.sender(alice).capabilities();
+        assert_eq!(features::FEATURE_BITMAP, capabilities & 0xff);
+        assert_ne!(0, capabilities & features::ORACLE);
+        assert_ne!(0, capabilities & features::GOVERNANCE);
+        assert_eq!(
+            0,
+            capabilities & (features::LIMIT_ORDERS | features::REWARDS)
+        );
+    }
+
+    #[motsu::test]
+    fn charges_fee_bps(alice: Address) {
+        let contract = hook();
+        let err = contract
+            .sender(alice)
+            .constructor(
+                String::from("1.0.0"),
+                alice,
+                POOL_MANAGER,
+                U256::ZERO,
+                U256::ZERO,
+                FEE_DENOMINATOR,
+            )
+            .expect_err("should reject a 100% fee");
+        assert!(matches!(
+            err,
+            Error::InvalidFee(InvalidFee { fee_bps }) if fee_bps == FEE_DENOMINATOR
+        ));
+        contract
+            .sender(alice)
+            .constructor(
+                String::from("1.0.0"),
+                alice,
+                POOL_MANAGER,
+                U256::ZERO,
+                U256::ZERO,
+                30,
+            )
+            .expect("should initialize contract");
+        assert_eq!(30, contract.sender(alice).fee());
+        seed_liquidity(&contract, alice, uint!(10_000_U256), uint!(10_000_U256));
+
+        // 0.3% of 1_000 is 3.
+        let amount_out = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(1_000_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_out`");
+        assert_eq!(uint!(997_U256), amount_out);
+
+        // 997 / 0.997 is exactly 1_000, while 1 / 0.997 rounds up to 2.
+        let amount_in = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(997_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_in`");
+        assert_eq!(uint!(1_000_U256), amount_in);
+        let amount_in = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(1_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_in`");
+        assert_eq!(uint!(2_U256), amount_in);
+    }
+
+    #[motsu::test]
+    fn tracks_reserves(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        let err = contract
+            .sender(bob)
+            .set_reserves(uint!(1_000_U256), uint!(1_000_U256))
+            .expect_err("should reject non-owner");
+        assert!(matches!(err, Error::Unauthorized(_)));
+        seed_liquidity(&contract, alice, uint!(1_000_U256), uint!(1_000_U256));
+        contract.assert_emitted(&ReservesSet {
+            op_seq: 0,
+            reserve0: uint!(1_000_U256),
+            reserve1: uint!(1_000_U256),
+        });
+
+        // Legacy swaps move no currencies, so they leave the reserves alone.
+        contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(100_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_out`");
+        contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(300_U256), CURRENCY_2, CURRENCY_1, false)
+            .expect("should calculate `amount_in`");
+        assert_eq!(
+            (uint!(1_000_U256), uint!(1_000_U256)),
+            contract
+                .sender(alice)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+    }
+
+    #[motsu::test]
+    fn rejects_exact_output_above_reserve(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_reserves(uint!(1_000_U256), uint!(500_U256))
+            .expect("should set reserves");
+
+        let err = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(501_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should not exceed the output reserve");
+        assert!(matches!(
+            err,
+            Error::InsufficientLiquidity(InsufficientLiquidity { available, requested })
+                if available == uint!(500_U256) && requested == uint!(501_U256)
+        ));
+        assert_eq!(
+            (uint!(1_000_U256), uint!(500_U256)),
+            contract
+                .sender(alice)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+
+        contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(500_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should quote the whole output reserve");
+        assert_eq!(
+            (uint!(1_000_U256), uint!(500_U256)),
+            contract
+                .sender(alice)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+    }
+
+    #[motsu::test]
+    fn rejects_zero_amounts_and_identical_tokens(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+
+        let err = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(U256::ZERO, CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should reject a zero `amount_in`");
+        assert!(matches!(err, Error::ZeroAmount(_)));
+        let err = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(U256::ZERO, CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should reject a zero `amount_out`");
+        assert!(matches!(err, Error::ZeroAmount(_)));
+
+        let err = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(1_U256), CURRENCY_1, CURRENCY_1, true)
+            .expect_err("should reject identical tokens");
+        assert!(matches!(
+            err,
+            Error::InvalidTokenPair(InvalidTokenPair { input, output })
+                if input == CURRENCY_1 && output == CURRENCY_1
+        ));
+        let err = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(1_U256), CURRENCY_2, CURRENCY_2, false)
+            .expect_err("should reject identical tokens");
+        assert!(matches!(err, Error::InvalidTokenPair(_)));
+    }
+
+    #[motsu::test]
+    fn before_swap_returns_curve_delta(alice: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let (currency0, currency1, fee) = POOLS[0];
+        let key = || pool_key(currency0, currency1, fee);
+
+        // Exact input of 100 `currency0`.
+        let params = SwapParams {
+            zero_for_one: true,
+            amount_specified: I256::try_from(-100).expect("should fit"),
+            sqrt_price_limit_x96: U160::ZERO,
+        };
+        let (selector, delta, lp_fee) = contract
+            .swap(alice, key(), params, Bytes::default())
+            .expect("should price exact input");
+        assert_eq!(hooks::BEFORE_SWAP_SELECTOR, selector);
+        assert_eq!(100, hooks::specified_delta(delta));
+        assert_eq!(-100, hooks::unspecified_delta(delta));
+        assert_eq!(U24::ZERO, lp_fee);
+        assert_eq!(RESERVE + uint!(100_U256), contract.balance(currency0, HOOK));
+        assert_eq!(RESERVE - uint!(100_U256), contract.balance(currency1, HOOK));
+
+        // Exact output of 50 `currency0`.
+        let params = SwapParams {
+            zero_for_one: false,
+            amount_specified: I256::try_from(50).expect("should fit"),
+            sqrt_price_limit_x96: U160::ZERO,
+        };
+        let (_, delta, _) = contract
+            .swap(alice, key(), params, Bytes::default())
+            .expect("should price exact output");
+        assert_eq!(-50, hooks::specified_delta(delta));
+        assert_eq!(50, hooks::unspecified_delta(delta));
+        assert_eq!(
+            (RESERVE + uint!(50_U256), RESERVE - uint!(50_U256)),
+            contract
+                .sender(alice)
+                .pool_reserves(pool_ids[0])
+                .expect("should read pool reserves")
+        );
+        assert_eq!(RESERVE + uint!(50_U256), contract.balance(currency0, HOOK));
+        assert_eq!(RESERVE - uint!(50_U256), contract.balance(currency1, HOOK));
+        assert_eq!(
+            (RESERVE, RESERVE),
+            contract
+                .sender(alice)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+
+        let err = contract
+            .swap(
+                alice,
+                key(),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified: I256::ZERO,
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                Bytes::default(),
+            )
+            .expect_err("should reject a zero amount");
+        assert!(matches!(err, Error::ZeroAmount(_)));
+
+        let unregistered = pool_key(CURRENCY_1, CURRENCY_2, uint!(100_U24));
+        let err = contract
+            .swap(
+                alice,
+                unregistered.clone(),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified: I256::MINUS_ONE,
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                Bytes::default(),
+            )
+            .expect_err("should reject an unregistered pool");
+        assert!(matches!(
+            err,
+            Error::PoolNotRegistered(PoolNotRegistered { pool_id })
+                if pool_id == pool::pool_id(&unregistered)
+        ));
+    }
+
+    #[test]
+    fn bootstrap_phases_follow_the_schedule() {
+        use bootstrap::{phase_at, weight_at};
+
+        assert_eq!(Phase::Trading, phase_at(500, 0, 0));
+        assert_eq!(Phase::Scheduled, phase_at(99, 100, 200));
+        assert_eq!(Phase::Bootstrapping, phase_at(100, 100, 200));
+        assert_eq!(Phase::Bootstrapping, phase_at(199, 100, 200));
+        assert_eq!(Phase::Trading, phase_at(200, 100, 200));
+
+        let start_weight = uint!(800_000_000_000_000_000_U256);
+        let end_weight = uint!(200_000_000_000_000_000_U256);
+        let weight = |timestamp| weight_at(timestamp, 100, 200, start_weight, end_weight);
+        assert_eq!(start_weight, weight(0));
+        assert_eq!(start_weight, weight(100));
+        assert_eq!(uint!(500_000_000_000_000_000_U256), weight(150));
+        assert_eq!(end_weight, weight(200));
+        assert_eq!(end_weight, weight(300));
+    }
+
+    #[motsu::test]
+    fn bootstrap_sells_token0_before_trading(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        seed_liquidity(
+            &contract,
+            alice,
+            uint!(1_000_000_U256),
+            uint!(1_000_000_U256),
+        );
+        let now = contract.sender(alice).vm().block_timestamp();
+        let half = math::WAD / uint!(2_U256);
+
+        let err = contract
+            .sender(alice)
+            .schedule_bootstrap(now + 200, now + 100, half, half)
+            .expect_err("should reject an auction ending before its start");
+        assert!(matches!(err, Error::InvalidBootstrap(_)));
+        let err = contract
+            .sender(alice)
+            .schedule_bootstrap(now + 100, now + 200, half, math::WAD)
+            .expect_err("should reject an increasing weight");
+        assert!(matches!(err, Error::InvalidBootstrap(_)));
+
+        contract
+            .sender(alice)
+            .schedule_bootstrap(now + 1_000, now + 2_000, half, half)
+            .expect("should schedule bootstrap");
+        contract.assert_emitted(&BootstrapScheduled {
+            op_seq: 1,
+            start: now + 1_000,
+            end: now + 2_000,
+            start_weight: half,
+            end_weight: half,
+        });
+        assert_eq!(Phase::Scheduled as u8, contract.sender(alice).phase());
+        let err = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(10_000_U256), CURRENCY_2, CURRENCY_1, false)
+            .expect_err("should wait for the auction");
+        assert!(matches!(
+            err,
+            Error::InvalidPhase(InvalidPhase { phase }) if phase == Phase::Scheduled as u8
+        ));
+        contract.assert_emitted(&PoolStateChanged {
+            op_seq: 2,
+            from: PoolState::Active as u8,
+            to: PoolState::Bootstrapping as u8,
+        });
+        assert_eq!(
+            PoolState::Bootstrapping as u8,
+            contract.sender(alice).pool_state()
+        );
+        contract
+            .sender(alice)
+            .cancel_bootstrap()
+            .expect("should cancel bootstrap");
+        contract.assert_emitted(&BootstrapCancelled { op_seq: 3 });
+        assert_eq!(PoolState::Active as u8, contract.sender(alice).pool_state());
+        assert_eq!(Phase::Trading as u8, contract.sender(alice).phase());
+
+        // Equal weights price like a constant-product curve.
+        contract
+            .sender(alice)
+            .schedule_bootstrap(0, now + 1_000_000, half, half)
+            .expect("should schedule bootstrap");
+        assert_eq!(Phase::Bootstrapping as u8, contract.sender(alice).phase());
+        let err = contract
+            .sender(alice)
+            .cancel_bootstrap()
+            .expect_err("should not cancel a running auction");
+        assert!(matches!(err, Error::InvalidPhase(_)));
+        let err = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(10_000_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should not sell `token0`");
+        assert!(matches!(
+            err,
+            Error::BootstrapBuyOnly(BootstrapBuyOnly { input }) if input == CURRENCY_1
+        ));
+
+        // 1_000_000 * 10_000 / 1_010_000 is 9_900.99.
+        let amount_out = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(10_000_U256), CURRENCY_2, CURRENCY_1, false)
+            .expect("should buy `token0`");
+        assert_eq!(uint!(9_900_U256), amount_out);
+        // 1_000_000 * 10_000 / 990_000 is 10_101.01.
+        let amount_in = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(10_000_U256), CURRENCY_2, CURRENCY_1, false)
+            .expect("should buy `token0`");
+        assert_eq!(uint!(10_102_U256), amount_in);
+        assert_eq!(
+            (uint!(1_000_000_U256), uint!(1_000_000_U256)),
+            contract
+                .sender(alice)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+    }
+
+    #[motsu::test]
+    fn hook_address_encodes_permissions(alice: Address) {
+        let contract = hook();
+        let permissions = contract.sender(alice).get_hook_permissions();
+        assert!(permissions.before_swap && permissions.before_swap_return_delta);
+        assert!(!permissions.after_add_liquidity && !permissions.before_donate);
+        assert_eq!(0x3acc, hooks::permission_flags(&permissions));
+
+        hooks::check_hook_address(address!("0000000000000000000000000000000000003acc"))
+            .expect("should accept matching flags");
+        hooks::check_hook_address(address!("ffffffffffffffffffffffffffffffffffffbacc"))
+            .expect("should ignore bits above the flags");
+        let err = hooks::check_hook_address(address!("0000000000000000000000000000000000003ac8"))
+            .expect_err("should reject missing flags");
+        assert!(matches!(err, Error::HookAddressNotValid(_)));
+        let err = hooks::check_hook_address(address!("0000000000000000000000000000000000003acd"))
+            .expect_err("should reject extra flags");
+        assert!(matches!(err, Error::HookAddressNotValid(_)));
+
+        // The constructor checks the address the curve is deployed at.
+        let misplaced = Contract::<ConstantSumCurve>::new_at(address!(
+            "0000000000000000000000000000000000003ac8"
+        ));
+        let err = misplaced
+            .sender(alice)
+            .constructor(
+                String::from("1.0.0"),
+                alice,
+                POOL_MANAGER,
+                U256::ZERO,
+                U256::ZERO,
+                0,
+            )
+            .expect_err("should reject a misplaced hook");
+        assert!(matches!(err, Error::HookAddressNotValid(_)));
+        deploy(&contract, alice);
+    }
+
+    #[motsu::test]
+    fn initialize_registers_pool(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        let key = |fee| pool_key(CURRENCY_1, CURRENCY_2, fee);
+        let pool_id = contract.sender(alice).pool_id(key(uint!(3_000_U24)));
+        assert_eq!(
+            keccak256(
+                (
+                    CURRENCY_1,
+                    CURRENCY_2,
+                    uint!(3_000_U24),
+                    I24::ONE,
+                    Address::ZERO
+                )
+                    .abi_encode()
+            ),
+            pool_id
+        );
+        assert_eq!(
+            (Address::ZERO, Address::ZERO, U24::ZERO, I24::ZERO),
+            contract.sender(alice).pool_key(pool_id)
+        );
+
+        let err = contract
+            .sender(POOL_MANAGER)
+            .before_initialize(alice, key(uint!(42_U24)), SQRT_PRICE_1_1)
+            .expect_err("should reject an unsupported fee tier");
+        assert!(matches!(
+            err,
+            Error::UnsupportedFeeTier(UnsupportedFeeTier { fee }) if fee == uint!(42_U24)
+        ));
+
+        let selector = contract
+            .sender(POOL_MANAGER)
+            .before_initialize(alice, key(uint!(3_000_U24)), SQRT_PRICE_1_1)
+            .expect("should register pool");
+        assert_eq!(hooks::BEFORE_INITIALIZE_SELECTOR, selector);
+        contract.assert_emitted(&PoolRegistered {
+            pool_id,
+            currency0: CURRENCY_1,
+            currency1: CURRENCY_2,
+            fee: uint!(3_000_U24),
+            tick_spacing: I24::ONE,
+        });
+        assert_eq!(
+            (CURRENCY_1, CURRENCY_2, uint!(3_000_U24), I24::ONE),
+            contract.sender(alice).pool_key(pool_id)
+        );
+        let selector = contract
+            .sender(POOL_MANAGER)
+            .after_initialize(alice, key(uint!(3_000_U24)), SQRT_PRICE_1_1, I24::ZERO)
+            .expect("should acknowledge initialization");
+        assert_eq!(hooks::AFTER_INITIALIZE_SELECTOR, selector);
+
+        let err = contract
+            .sender(POOL_MANAGER)
+            .before_initialize(alice, key(uint!(3_000_U24)), SQRT_PRICE_1_1)
+            .expect_err("should register a pool once");
+        assert!(matches!(
+            err,
+            Error::PoolAlreadyRegistered(PoolAlreadyRegistered { pool_id: id }) if id == pool_id
+        ));
+
+        // The same pair with another fee tier is another pool.
+        contract
+            .sender(POOL_MANAGER)
+            .before_initialize(alice, key(uint!(500_U24)), SQRT_PRICE_1_1)
+            .expect("should register another pool");
+        let other_id = contract.sender(alice).pool_id(key(uint!(500_U24)));
+        assert_ne!(pool_id, other_id);
+        assert_eq!(
+            (CURRENCY_1, CURRENCY_2, uint!(500_U24), I24::ONE),
+            contract.sender(alice).pool_key(other_id)
+        );
+    }
+
+    #[motsu::test]
+    fn claims_mode_tracks_claim_balances(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy_multi_pool(&contract, alice);
+        let (currency0, currency1, fee) = POOLS[0];
+        let key = pool_key(currency0, currency1, fee);
+        let claims = || {
+            (
+                contract.sender(alice).claim_balance(currency0),
+                contract.sender(alice).claim_balance(currency1),
+            )
+        };
+        // The pool manager holds the claims tracked by the curve.
+        let swap = |zero_for_one: bool, amount: i64| {
+            let params = SwapParams {
+                zero_for_one,
+                amount_specified: I256::try_from(-amount).expect("should fit"),
+                sqrt_price_limit_x96: U160::ZERO,
+            };
+            contract
+                .swap(alice, key.clone(), params, Bytes::default())
+                .expect("should swap");
+            let manager = |currency| {
+                contract
+                    .manager
+                    .sender(alice)
+                    .claim_balance(HOOK, pool_manager::claim_id(currency))
+            };
+            assert_eq!((manager(currency0), manager(currency1)), claims());
+        };
+
+        let err = contract
+            .sender(bob)
+            .set_claims_mode(true)
+            .expect_err("should reject non-owner");
+        assert!(matches!(err, Error::Unauthorized(_)));
+
+        // Tokens are taken as ERC-20 until claims mode is enabled.
+        swap(true, 1_000);
+        assert_eq!((U256::ZERO, U256::ZERO), claims());
+
+        contract
+            .sender(alice)
+            .set_claims_mode(true)
+            .expect("should enable claims mode");
+        contract.assert_emitted(&ClaimsModeSet {
+            op_seq: 4,
+            enabled: true,
+        });
+        assert!(contract.sender(alice).claims_mode());
+
+        swap(true, 1_000);
+        assert_eq!((uint!(1_000_U256), U256::ZERO), claims());
+        // Payments burn claims first.
+        swap(false, 400);
+        assert_eq!((uint!(600_U256), uint!(400_U256)), claims());
+
+        // Claims are still spent once claims mode is disabled.
+        contract
+            .sender(alice)
+            .set_claims_mode(false)
+            .expect("should disable claims mode");
+        swap(true, 1_000);
+        assert_eq!((uint!(600_U256), U256::ZERO), claims());
+
+        assert_eq!(
+            U256::from_be_slice(currency0.as_slice()),
+            pool_manager::claim_id(currency0)
+        );
+    }
+
+    #[motsu::test]
+    fn legacy_swaps_cannot_drain_liquidity_providers(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_currencies(CURRENCY_1, CURRENCY_2)
+            .expect("should set currencies");
+        let amount = uint!(1_000_000_U256);
+        for provider in [alice, bob] {
+            contract.mint(CURRENCY_1, provider, amount);
+            contract.mint(CURRENCY_2, provider, amount);
+            contract
+                .sender(provider)
+                .add_liquidity(amount, amount)
+                .expect("should add liquidity");
+        }
+        let reserves = (amount * uint!(2_U256), amount * uint!(2_U256));
+
+        // Legacy swaps pay nothing in, so they must not move the reserves
+        // the shares are redeemed against.
+        for _ in 0..10 {
+            contract
+                .sender(bob)
+                .get_amount_out_from_exact_input(uint!(100_000_U256), CURRENCY_1, CURRENCY_2, true)
+                .expect("should calculate `amount_out`");
+            contract
+                .sender(bob)
+                .get_amount_in_for_exact_output(uint!(100_000_U256), CURRENCY_2, CURRENCY_1, false)
+                .expect("should calculate `amount_in`");
+        }
+        assert_eq!(
+            reserves,
+            contract
+                .sender(bob)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+        let err = contract
+            .sender(alice)
+            .set_reserves(U256::ZERO, U256::ZERO)
+            .expect_err("should not set reserves backing shares");
+        assert!(matches!(err, Error::SharesOutstanding(_)));
+
+        let shares = contract.sender(bob).balance_of(bob);
+        assert_eq!(
+            (amount, amount),
+            contract
+                .sender(bob)
+                .remove_liquidity(shares, U256::ZERO, U256::ZERO)
+                .expect("should remove liquidity")
+        );
+        let shares = contract.sender(alice).balance_of(alice);
+        // The locked shares keep their part of the reserves.
+        assert_eq!(
+            (uint!(999_500_U256), uint!(999_500_U256)),
+            contract
+                .sender(alice)
+                .remove_liquidity(shares, U256::ZERO, U256::ZERO)
+                .expect("should remove liquidity")
+        );
+        assert_eq!(amount, contract.balance(CURRENCY_1, bob));
+        assert_eq!(amount, contract.balance(CURRENCY_2, bob));
+    }
+
+    #[motsu::test]
+    fn self_test_passes_once(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        let err = contract
+            .sender(bob)
+            .self_test()
+            .expect_err("should reject non-owner");
+        assert!(matches!(err, Error::Unauthorized(_)));
+
+        // Quotes wait for a scheduled bootstrap auction to start.
+        let now = contract.sender(alice).vm().block_timestamp();
+        let half = math::WAD / uint!(2_U256);
+        contract
+            .sender(alice)
+            .schedule_bootstrap(now + 1_000, now + 2_000, half, half)
+            .expect("should schedule bootstrap");
+        let err = contract
+            .sender(alice)
+            .self_test()
+            .expect_err("should fail the first check");
+        assert!(matches!(
+            err,
+            Error::SelfTestFailed(SelfTestFailed { check: 0 })
+        ));
+        assert!(!contract.sender(alice).self_test_passed());
+        contract
+            .sender(alice)
+            .cancel_bootstrap()
+            .expect("should cancel bootstrap");
+
+        let op_seq = contract.sender(alice).change_count();
+        contract
+            .sender(alice)
+            .self_test()
+            .expect("should pass the self-test");
+        contract.assert_emitted(&AmountOutCalculated {
+            amount_in: self_test::CANARY_AMOUNT,
+            input: self_test::CANARY0,
+            output: self_test::CANARY1,
+            zero_for_one: true,
+        });
+        contract.assert_emitted(&AmountInCalculated {
+            amount_out: uint!(1_500_000_U256),
+            input: self_test::CANARY0,
+            output: self_test::CANARY1,
+            zero_for_one: true,
+        });
+        contract.assert_emitted(&AmountInCalculated {
+            amount_out: uint!(666_666_U256),
+            input: self_test::CANARY1,
+            output: self_test::CANARY0,
+            zero_for_one: false,
+        });
+        contract.assert_emitted(&SelfTestPassed { op_seq });
+        assert!(contract.sender(alice).self_test_passed());
+        // The dummy rate does not outlive the self-test.
+        assert_eq!(
+            (U256::ONE, U256::ONE),
+            contract
+                .sender(alice)
+                .exchange_rate(self_test::CANARY0, self_test::CANARY1)
+        );
+
+        let err = contract
+            .sender(alice)
+            .self_test()
+            .expect_err("should pass only once");
+        assert!(matches!(err, Error::SelfTestAlreadyPassed(_)));
+    }
+
+    #[motsu::test]
+    fn lock_rejects_reentrancy(alice: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let read_only = |err: Error| matches!(err, Error::ReadOnlyReentrancy(_));
+        let reentrant = |err: Error| matches!(err, Error::Reentrancy(_));
+        contract
+            .sender(alice)
+            .set_currencies(CURRENCY_1, CURRENCY_2)
+            .expect("should set currencies");
+
+        contract
+            .sender(alice)
+            .with_lock(|curve| {
+                assert!(curve.locked());
+                // Operations cannot be reentered.
+                assert!(reentrant(
+                    curve
+                        .with_lock(|_| Ok(()))
+                        .expect_err("should not nest operations")
+                ));
+                assert!(reentrant(
+                    curve
+                        .deposit(pool_ids[0], U256::ZERO, U256::ZERO)
+                        .expect_err("should lock deposits")
+                ));
+                assert!(reentrant(
+                    curve
+                        .withdraw(pool_ids[0], U256::ZERO, U256::ZERO, alice)
+                        .expect_err("should lock withdrawals")
+                ));
+                assert!(reentrant(
+                    curve
+                        .remove_liquidity(U256::ONE, U256::ZERO, U256::ZERO)
+                        .expect_err("should lock liquidity changes")
+                ));
+
+                // Views cannot read mid-operation state.
+                assert!(read_only(
+                    curve.get_reserves().expect_err("should lock reserves")
+                ));
+                assert!(read_only(
+                    curve.total_supply().expect_err("should lock the LP supply")
+                ));
+                assert!(read_only(
+                    curve
+                        .pool_reserves(pool_ids[0])
+                        .expect_err("should lock pool reserves")
+                ));
+                assert!(read_only(
+                    curve
+                        .get_price(pool_ids[0])
+                        .expect_err("should lock the price")
+                ));
+                assert!(read_only(
+                    curve
+                        .quote_amount_out(uint!(10_U256), CURRENCY_1, CURRENCY_2, true)
+                        .expect_err("should lock quotes")
+                ));
+                assert!(read_only(
+                    curve
+                        .get_amount_out_from_exact_input(
+                            uint!(10_U256),
+                            CURRENCY_1,
+                            CURRENCY_2,
+                            true
+                        )
+                        .expect_err("should lock legacy quotes")
+                ));
+                assert!(read_only(
+                    curve
+                        .latest_price()
+                        .expect_err("should lock the oracle price")
+                ));
+                Ok(())
+            })
+            .expect("should run with the lock");
+
+        // Finished operations release the lock, failed ones too.
+        assert!(!contract.sender(alice).locked());
+        contract
+            .sender(alice)
+            .with_lock(|_| Err::<(), _>(Error::Reentrancy(Reentrancy {})))
+            .expect_err("should fail");
+        assert!(!contract.sender(alice).locked());
+        let (currency0, currency1, fee) = POOLS[0];
+        contract
+            .swap(
+                alice,
+                pool_key(currency0, currency1, fee),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified: I256::MINUS_ONE,
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                Bytes::default(),
+            )
+            .expect("should swap");
+        assert_eq!(
+            (RESERVE + U256::ONE, RESERVE - U256::ONE),
+            contract
+                .sender(alice)
+                .pool_reserves(pool_ids[0])
+                .expect("should read pool reserves")
+        );
+    }
+
+    #[motsu::test]
+    fn empty_pools_reject_swaps(alice: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let (currency0, currency1, fee) = POOLS[0];
+        let swap = || {
+            contract.swap(
+                alice,
+                pool_key(currency0, currency1, fee),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified: I256::MINUS_ONE,
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                Bytes::default(),
+            )
+        };
+        contract
+            .sender(alice)
+            .withdraw(pool_ids[0], RESERVE, RESERVE, alice)
+            .expect("should drain pool");
+        contract
+            .sender(alice)
+            .set_pool_max_price_age(pool_ids[0], 600)
+            .expect("should peg pool");
+
+        // The empty pool fails before its oracle price is read.
+        let err = swap().expect_err("should reject an empty pool");
+        assert!(matches!(
+            err,
+            Error::PoolEmpty(PoolEmpty { pool_id }) if pool_id == pool_ids[0]
+        ));
+        assert_eq!(
+            (U256::ZERO, U256::ZERO),
+            contract.sender(alice).volume(pool_ids[0])
+        );
+
+        // A single reserve is enough to quote against.
+        contract
+            .sender(alice)
+            .set_pool_reserves(pool_ids[0], U256::ZERO, RESERVE)
+            .expect("should seed pool");
+        let err = swap().expect_err("should need a price");
+        assert!(matches!(err, Error::PriceTooOld(_)));
+    }
+
+    #[motsu::test]
+    fn rejects_liquidity_via_pool_manager(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        let key = || pool_key(CURRENCY_1, CURRENCY_2, U24::ZERO);
+        let params = |liquidity_delta| ModifyLiquidityParams {
+            tick_lower: I24::MIN,
+            tick_upper: I24::MAX,
+            liquidity_delta,
+            salt: FixedBytes::ZERO,
+        };
+
+        let err = contract
+            .sender(POOL_MANAGER)
+            .before_add_liquidity(alice, key(), params(I256::ONE), Bytes::default())
+            .expect_err("should reject added liquidity");
+        assert!(matches!(err, Error::LiquidityViaHookOnly(_)));
+        let err = contract
+            .sender(POOL_MANAGER)
+            .before_remove_liquidity(alice, key(), params(I256::MINUS_ONE), Bytes::default())
+            .expect_err("should reject removed liquidity");
+        assert!(matches!(err, Error::LiquidityViaHookOnly(_)));
+    }
+
+    #[motsu::test]
+    fn unlock_callback_checks_actions(alice: Address, bob: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let (currency0, currency1, fee) = POOLS[0];
+        let data = |action: UnlockAction, liquidity_delta| {
+            let data = UnlockCallbackData {
+                action: action as u8,
+                key: pool_key(currency0, currency1, fee),
+                params: ModifyLiquidityParams {
+                    tick_lower: I24::MIN,
+                    tick_upper: I24::MAX,
+                    liquidity_delta,
+                    salt: FixedBytes::ZERO,
+                },
+                amount0: RESERVE,
+                amount1: RESERVE,
+            };
+            Bytes::from(data.abi_encode())
+        };
+
+        let err = contract
+            .sender(bob)
+            .unlock_callback(data(UnlockAction::Donate, I256::ZERO))
+            .expect_err("should reject other callers");
+        assert!(matches!(err, Error::NotPoolManager(_)));
+        let err = contract
+            .sender(POOL_MANAGER)
+            .unlock_callback(Bytes::from(vec![1u8; 31]))
+            .expect_err("should reject malformed data");
+        assert!(matches!(err, Error::InvalidUnlockData(_)));
+
+        let mut invalid = data(UnlockAction::Donate, I256::ZERO).to_vec();
+        invalid[31] = 3;
+        let err = contract
+            .sender(POOL_MANAGER)
+            .unlock_callback(Bytes::from(invalid))
+            .expect_err("should reject unknown actions");
+        assert!(matches!(
+            err,
+            Error::InvalidUnlockAction(InvalidUnlockAction { action: 3 })
+        ));
+
+        for (action, liquidity_delta) in [
+            (UnlockAction::AddLiquidity, I256::ZERO),
+            (UnlockAction::AddLiquidity, I256::MINUS_ONE),
+            (UnlockAction::RemoveLiquidity, I256::ONE),
+        ] {
+            let err = contract
+                .sender(POOL_MANAGER)
+                .unlock_callback(data(action, liquidity_delta))
+                .expect_err("should reject mismatched liquidity");
+            assert!(matches!(
+                err,
+                Error::InvalidLiquidityDelta(InvalidLiquidityDelta { liquidity_delta: delta })
+                    if delta == liquidity_delta
+            ));
+        }
+
+        let unregistered = UnlockCallbackData {
+            action: UnlockAction::Donate as u8,
+            key: pool_key(CURRENCY_1, CURRENCY_2, U24::ZERO),
+            params: ModifyLiquidityParams {
+                tick_lower: I24::MIN,
+                tick_upper: I24::MAX,
+                liquidity_delta: I256::ZERO,
+                salt: FixedBytes::ZERO,
+            },
+            amount0: U256::ZERO,
+            amount1: U256::ZERO,
+        };
+        let err = contract
+            .sender(POOL_MANAGER)
+            .unlock_callback(Bytes::from(unregistered.abi_encode()))
+            .expect_err("should reject unregistered pools");
+        assert!(matches!(err, Error::PoolNotRegistered(_)));
+
+        // The mock pool manager owes nothing for liquidity changes until
+        // told otherwise.
+        for (action, liquidity_delta) in [
+            (UnlockAction::AddLiquidity, I256::ONE),
+            (UnlockAction::RemoveLiquidity, I256::MINUS_ONE),
+        ] {
+            let delta = contract
+                .sender(POOL_MANAGER)
+                .unlock_callback(data(action, liquidity_delta))
+                .expect("should run action");
+            assert_eq!(I256::ZERO.abi_encode(), delta.to_vec());
+        }
+
+        // Donating the whole reserves pays them to the pool manager.
+        let reserve = i128::try_from(RESERVE).expect("should fit");
+        let delta = contract
+            .sender(POOL_MANAGER)
+            .unlock_callback(data(UnlockAction::Donate, I256::ZERO))
+            .expect("should donate");
+        assert_eq!(
+            hooks::to_balance_delta(-reserve, -reserve).abi_encode(),
+            delta.to_vec()
+        );
+        contract.check_settled(&[currency0, currency1]);
+        assert_eq!(
+            (U256::ZERO, U256::ZERO),
+            contract
+                .sender(alice)
+                .pool_reserves(pool_ids[0])
+                .expect("should read pool reserves")
+        );
+        assert_eq!(RESERVE, contract.balance(currency1, HOOK));
+    }
+
+    #[motsu::test]
+    fn unlock_pool_manager_runs_pool_operations(alice: Address, bob: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let (currency0, currency1, fee) = POOLS[0];
+        let data = |action: UnlockAction, liquidity_delta| UnlockCallbackData {
+            action: action as u8,
+            key: pool_key(currency0, currency1, fee),
+            params: ModifyLiquidityParams {
+                tick_lower: I24::MIN,
+                tick_upper: I24::MAX,
+                liquidity_delta,
+                salt: FixedBytes::ZERO,
+            },
+            amount0: uint!(10_U256),
+            amount1: uint!(20_U256),
+        };
+        let reserves = || {
+            contract
+                .sender(alice)
+                .pool_reserves(pool_ids[0])
+                .expect("should read pool reserves")
+        };
+
+        let err = contract
+            .sender(bob)
+            .unlock_pool_manager(data(UnlockAction::Donate, I256::ZERO))
+            .expect_err("should reject non-owner");
+        assert!(matches!(err, Error::Unauthorized(_)));
+        let err = contract
+            .sender(alice)
+            .unlock_pool_manager(data(UnlockAction::AddLiquidity, I256::MINUS_ONE))
+            .expect_err("should check the action before unlocking");
+        assert!(matches!(err, Error::InvalidLiquidityDelta(_)));
+
+        // Adding liquidity pays the pool manager from the reserves.
+        let added = hooks::to_balance_delta(-100, -50);
+        contract.manager.sender(alice).set_liquidity_delta(added);
+        let delta = contract
+            .sender(alice)
+            .unlock_pool_manager(data(UnlockAction::AddLiquidity, I256::ONE))
+            .expect("should add liquidity");
+        assert_eq!(added, delta);
+        contract.assert_emitted(&UnlockActionExecuted {
+            pool_id: pool_ids[0],
+            action: UnlockAction::AddLiquidity as u8,
+            delta,
+        });
+        assert_eq!(
+            (RESERVE - uint!(100_U256), RESERVE - uint!(50_U256)),
+            reserves()
+        );
+        assert_eq!(RESERVE - uint!(100_U256), contract.balance(currency0, HOOK));
+
+        // Removing liquidity takes it back into the reserves.
+        let removed = hooks::to_balance_delta(30, 20);
+        contract.manager.sender(alice).set_liquidity_delta(removed);
+        let delta = contract
+            .sender(alice)
+            .unlock_pool_manager(data(UnlockAction::RemoveLiquidity, I256::MINUS_ONE))
+            .expect("should remove liquidity");
+        assert_eq!(removed, delta);
+        assert_eq!(
+            (RESERVE - uint!(70_U256), RESERVE - uint!(30_U256)),
+            reserves()
+        );
+        assert_eq!(RESERVE - uint!(30_U256), contract.balance(currency1, HOOK));
+
+        let delta = contract
+            .sender(alice)
+            .unlock_pool_manager(data(UnlockAction::Donate, I256::ZERO))
+            .expect("should donate");
+        assert_eq!(hooks::to_balance_delta(-10, -20), delta);
+        assert_eq!(
+            (RESERVE - uint!(80_U256), RESERVE - uint!(50_U256)),
+            reserves()
+        );
+        contract.check_settled(&[currency0, currency1]);
+    }
+
+    #[motsu::test]
+    fn hooks_only_accept_pool_manager(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        assert_eq!(POOL_MANAGER, contract.sender(alice).pool_manager());
+        let (currency0, currency1, fee) = POOLS[0];
+        let key = || pool_key(currency0, currency1, fee);
+        let params = || SwapParams {
+            zero_for_one: true,
+            amount_specified: I256::MINUS_ONE,
+            sqrt_price_limit_x96: U160::ZERO,
+        };
+
+        // Neither the owner nor another account can call the hooks.
+        for caller in [alice, bob] {
+            let err = contract
+                .sender(caller)
+                .before_initialize(caller, key(), SQRT_PRICE_1_1)
+                .expect_err("should reject initialization");
+            assert!(matches!(
+                err,
+                Error::NotPoolManager(NotPoolManager { caller: account }) if account == caller
+            ));
+            let err = contract
+                .sender(caller)
+                .before_swap(caller, key(), params(), Bytes::default())
+                .expect_err("should reject swap");
+            assert!(matches!(err, Error::NotPoolManager(_)));
+            let err = contract
+                .sender(caller)
+                .after_swap(
+                    caller,
+                    key(),
+                    params(),
+                    hooks::to_balance_delta(-1, 1),
+                    Bytes::default(),
+                )
+                .expect_err("should reject swap accounting");
+            assert!(matches!(err, Error::NotPoolManager(_)));
+        }
+
+        contract
+            .sender(POOL_MANAGER)
+            .before_initialize(bob, key(), SQRT_PRICE_1_1)
+            .expect("should register pool");
+        assert_eq!(
+            (currency0, currency1, fee, I24::ONE),
+            contract.sender(alice).pool_key(pool::pool_id(&key()))
+        );
+    }
+
+    #[motsu::test]
+    fn after_swap_accounts_and_takes_fee(alice: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        contract
+            .sender(alice)
+            .set_after_swap_fee_bps(100)
+            .expect("should set the after-swap fee");
+        contract.assert_emitted(&AfterSwapFeeSet {
+            op_seq: 4,
+            fee_bps: 100,
+        });
+        let (currency0, currency1, fee) = POOLS[0];
+        let key = || pool_key(currency0, currency1, fee);
+        let pool_id = pool_ids[0];
+
+        // The swapper paid 10_000 `currency0` for 9_900 `currency1`.
+        let params = SwapParams {
+            zero_for_one: true,
+            amount_specified: I256::try_from(-10_000).expect("should fit"),
+            sqrt_price_limit_x96: U160::ZERO,
+        };
+        let (selector, fee) = contract
+            .sender(POOL_MANAGER)
+            .after_swap(
+                alice,
+                key(),
+                params.clone(),
+                hooks::to_balance_delta(-10_000, 9_900),
+                Bytes::default(),
+            )
+            .expect("should account swap");
+        assert_eq!(hooks::AFTER_SWAP_SELECTOR, selector);
+        // 1% of the unspecified 9_900 `currency1`.
+        assert_eq!(99, fee);
+        contract.settle_after_swap(&key(), &params, fee);
+        contract.assert_emitted(&SwapAccounted {
+            pool_id,
+            amount0: -10_000,
+            amount1: 9_900,
+            fee: uint!(99_U256),
+        });
+        assert_eq!(
+            (uint!(10_000_U256), uint!(9_900_U256)),
+            contract.sender(alice).volume(pool_id)
+        );
+        assert_eq!(
+            uint!(990_000_000_000_000_000_U256),
+            contract.sender(alice).last_price(pool_id)
+        );
+        // The fee is taken into the reserve of `currency1`.
+        assert_eq!(
+            (RESERVE, RESERVE + uint!(99_U256)),
+            contract
+                .sender(alice)
+                .pool_reserves(pool_id)
+                .expect("should read pool reserves")
+        );
+        assert_eq!(RESERVE + uint!(99_U256), contract.balance(currency1, HOOK));
+
+        // The swapper paid 2_000 `currency1` for exactly 1_000 `currency0`.
+        let params = SwapParams {
+            zero_for_one: false,
+            amount_specified: I256::try_from(1_000).expect("should fit"),
+            sqrt_price_limit_x96: U160::ZERO,
+        };
+        let (_, fee) = contract
+            .sender(POOL_MANAGER)
+            .after_swap(
+                alice,
+                key(),
+                params.clone(),
+                hooks::to_balance_delta(1_000, -2_000),
+                Bytes::default(),
+            )
+            .expect("should account swap");
+        // 1% of the unspecified 2_000 `currency1`.
+        assert_eq!(20, fee);
+        contract.settle_after_swap(&key(), &params, fee);
+        assert_eq!(
+            (uint!(11_000_U256), uint!(11_900_U256)),
+            contract.sender(alice).volume(pool_id)
+        );
+        assert_eq!(
+            uint!(2_000_000_000_000_000_000_U256),
+            contract.sender(alice).last_price(pool_id)
+        );
+        assert_eq!(
+            (RESERVE, RESERVE + uint!(119_U256)),
+            contract
+                .sender(alice)
+                .pool_reserves(pool_id)
+                .expect("should read pool reserves")
+        );
+        assert_eq!(RESERVE + uint!(119_U256), contract.balance(currency1, HOOK));
+        assert_eq!(
+            (U256::ZERO, U256::ZERO),
+            contract.sender(alice).volume(B256::ZERO)
+        );
+
+        let err = contract
+            .sender(POOL_MANAGER)
+            .after_swap(
+                alice,
+                pool_key(CURRENCY_1, CURRENCY_2, U24::ZERO),
+                params,
+                hooks::to_balance_delta(1_000, -2_000),
+                Bytes::default(),
+            )
+            .expect_err("should reject an unregistered pool");
+        assert!(matches!(err, Error::PoolNotRegistered(_)));
+    }
+
+    #[motsu::test]
+    fn returns_amount_in_for_exact_output(alice: Address) {
+        let contract = hook();
+        let amount_out = uint!(1_U256);
+        let expected_amount_in = amount_out; // 1:1 swap
+        let zero_for_one = true;
+        contract.sender(alice).reserve1.set(amount_out);
+        let amount_in = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(amount_out, CURRENCY_1, CURRENCY_2, zero_for_one)
+            .expect("should calculate `amount_in`");
+        assert_eq!(expected_amount_in, amount_in);
+
+        // Assert emitted events.
+        contract.assert_emitted(&AmountInCalculated {
+            amount_out,
+            input: CURRENCY_1,
+            output: CURRENCY_2,
+            zero_for_one,
+        });
+    }
+
+    #[motsu::test]
+    fn returns_amount_out_from_exact_input(alice: Address) {
+        let contract = hook();
+        let amount_in = uint!(2_U256);
+        let expected_amount_out = amount_in; // 1:1 swap
+        let zero_for_one = true;
+        let amount_out = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(amount_in, CURRENCY_1, CURRENCY_2, zero_for_one)
+            .expect("should calculate `amount_out`");
+        assert_eq!(expected_amount_out, amount_out);
+
+        // Assert emitted events.
+        contract.assert_emitted(&AmountOutCalculated {
+            amount_in,
+            input: CURRENCY_1,
+            output: CURRENCY_2,
+            zero_for_one,
+        });
+    }
+
+    #[motsu::test]
+    fn allowlist_rejects_unlisted_currencies(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_currency_policy(CurrencyPolicy::Allowlist as u8)
+            .expect("should set the allowlist policy");
+        contract
+            .sender(alice)
+            .set_currency_listed(CURRENCY_1, true)
+            .expect("should list `CURRENCY_1`");
+        contract.assert_emitted(&CurrencyListed {
+            op_seq: 1,
+            currency: CURRENCY_1,
+            listed: true,
+        });
+
+        let err = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(1_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should reject unlisted `CURRENCY_2`");
+        assert!(matches!(
+            err,
+            Error::CurrencyNotAllowed(CurrencyNotAllowed { currency }) if currency == CURRENCY_2
+        ));
+
+        contract
+            .sender(alice)
+            .set_currency_listed(CURRENCY_2, true)
+            .expect("should list `CURRENCY_2`");
+        contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(1_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should swap listed currencies");
+    }
 
     #[motsu::test]
-    fn returns_amount_out_from_exact_input(contract: Contract<ConstantSumCurve>, alice: Address) {
-        let amount_in = uint!(2_U256);
-        let expected_amount_out = amount_in; // 1:1 swap
-        let zero_for_one = true;
+    fn denylist_rejects_listed_currencies(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_currency_policy(CurrencyPolicy::Denylist as u8)
+            .expect("should set the denylist policy");
+        contract.assert_emitted(&CurrencyPolicySet {
+            op_seq: 0,
+            policy: CurrencyPolicy::Denylist as u8,
+        });
+        contract
+            .sender(alice)
+            .set_currency_listed(CURRENCY_1, true)
+            .expect("should list `CURRENCY_1`");
+
+        let err = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(1_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should reject denylisted `CURRENCY_1`");
+        assert!(matches!(
+            err,
+            Error::CurrencyNotAllowed(CurrencyNotAllowed { currency }) if currency == CURRENCY_1
+        ));
+    }
+
+    #[motsu::test]
+    fn currency_policy_is_owner_only(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+
+        let err = contract
+            .sender(bob)
+            .set_currency_policy(CurrencyPolicy::Allowlist as u8)
+            .expect_err("should reject non-owner");
+        assert!(matches!(err, Error::Unauthorized(Unauthorized { account }) if account == bob));
+
+        let err = contract
+            .sender(alice)
+            .set_currency_policy(3)
+            .expect_err("should reject unknown policy");
+        assert!(matches!(
+            err,
+            Error::InvalidCurrencyPolicy(InvalidCurrencyPolicy { policy: 3 })
+        ));
+    }
+
+    #[motsu::test]
+    fn rejects_swaps_below_min_swap_amount(alice: Address) {
+        let contract = hook();
+        let min_swap_amount = uint!(100_U256);
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_min_swap_amount(min_swap_amount)
+            .expect("should set `min_swap_amount`");
+        contract.assert_emitted(&MinSwapAmountSet {
+            op_seq: 0,
+            min_swap_amount,
+        });
+
+        let err = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(99_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should reject dust swap");
+        assert!(matches!(
+            err,
+            Error::AmountTooSmall(AmountTooSmall { amount, .. }) if amount == uint!(99_U256)
+        ));
+
+        seed_liquidity(&contract, alice, min_swap_amount, min_swap_amount);
+        contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(min_swap_amount, CURRENCY_1, CURRENCY_2, true)
+            .expect("should swap exactly `min_swap_amount`");
+    }
+
+    #[motsu::test]
+    fn router_only_mode_rejects_direct_calls(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_router_only(true)
+            .expect("should enable router-only mode");
+        contract.assert_emitted(&RouterOnlySet {
+            op_seq: 0,
+            enabled: true,
+        });
+
+        let err = contract
+            .sender(bob)
+            .get_amount_out_from_exact_input(uint!(1_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should reject a direct call");
+        assert!(matches!(
+            err,
+            Error::RouterNotAllowed(RouterNotAllowed { caller }) if caller == bob
+        ));
+
+        contract
+            .sender(alice)
+            .set_router(bob, true)
+            .expect("should whitelist `bob`");
+        contract.assert_emitted(&RouterSet {
+            op_seq: 1,
+            router: bob,
+            allowed: true,
+        });
+        contract
+            .sender(bob)
+            .get_amount_out_from_exact_input(uint!(1_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should accept a whitelisted router");
+    }
+
+    #[motsu::test]
+    fn records_recent_changes(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_currency_listed(CURRENCY_1, true)
+            .expect("should list `CURRENCY_1`");
+        contract
+            .sender(alice)
+            .set_min_swap_amount(uint!(10_U256))
+            .expect("should set `min_swap_amount`");
+
+        assert_eq!(2, contract.sender(alice).change_count());
+        assert_eq!(
+            vec![
+                (
+                    0,
+                    ChangeKind::CurrencyListed as u8,
+                    CURRENCY_1.into_word(),
+                    uint!(1_U256)
+                ),
+                (
+                    1,
+                    ChangeKind::MinSwapAmount as u8,
+                    B256::ZERO,
+                    uint!(10_U256)
+                ),
+            ],
+            contract.sender(alice).recent_changes(0)
+        );
+        assert_eq!(1, contract.sender(alice).recent_changes(1).len());
+        assert!(contract.sender(alice).recent_changes(2).is_empty());
+    }
+
+    #[motsu::test]
+    fn changelog_evicts_oldest_changes(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        let total = changelog::CHANGELOG_CAPACITY + 6;
+        for amount in 0..total {
+            contract
+                .sender(alice)
+                .set_min_swap_amount(U256::from(amount))
+                .expect("should set `min_swap_amount`");
+        }
+
+        let changes = contract.sender(alice).recent_changes(0);
+        assert_eq!(changelog::CHANGELOG_CAPACITY as usize, changes.len());
+        assert_eq!(6, changes[0].0);
+        assert_eq!(U256::from(6), changes[0].3);
+        assert_eq!(total - 1, changes[changes.len() - 1].0);
+    }
+
+    #[motsu::test]
+    fn records_pool_id_of_pool_changes(alice: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let from_seq = contract.sender(alice).change_count();
+        contract
+            .sender(alice)
+            .set_pool_fee(pool_ids[1], 100)
+            .expect("should set pool fee");
+        contract
+            .sender(alice)
+            .set_pool_paused(pool_ids[2], true)
+            .expect("should pause pool");
+
+        assert_eq!(
+            vec![
+                (
+                    from_seq,
+                    ChangeKind::PoolFee as u8,
+                    pool_ids[1],
+                    uint!(100_U256)
+                ),
+                (
+                    from_seq + 1,
+                    ChangeKind::PoolPaused as u8,
+                    pool_ids[2],
+                    uint!(1_U256)
+                ),
+            ],
+            contract.sender(alice).recent_changes(from_seq)
+        );
+    }
+
+    #[motsu::test]
+    fn swaps_check_quote_validity(alice: Address) {
+        let contract = hook();
+        deploy_multi_pool(&contract, alice);
+        let now = contract.sender(alice).vm().block_timestamp();
+        let (currency0, currency1, fee) = POOLS[0];
+        let swap = |hook_data: Vec<u8>| {
+            contract.swap(
+                alice,
+                pool_key(currency0, currency1, fee),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified: I256::MINUS_ONE,
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                Bytes::from(hook_data),
+            )
+        };
+
+        let err = swap((now - 1).abi_encode()).expect_err("should reject expired quote");
+        assert!(matches!(
+            err,
+            Error::QuoteExpired(QuoteExpired { valid_until, timestamp })
+                if valid_until == now - 1 && timestamp == now
+        ));
+        let err = swap(vec![1u8; 5]).expect_err("should reject malformed hook data");
+        assert!(matches!(err, Error::InvalidHookData(_)));
+
+        swap(now.abi_encode()).expect("should swap until the quote expires");
+        swap(Vec::new()).expect("should swap without a quote");
+    }
+
+    #[motsu::test]
+    fn swaps_check_slippage_limit(alice: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let now = contract.sender(alice).vm().block_timestamp();
+        let (currency0, currency1, fee) = POOLS[0];
+        contract
+            .sender(alice)
+            .set_pool_fee(pool_ids[0], 100)
+            .expect("should set pool fee");
+        let swap = |amount_specified: i64, limit: U256| {
+            contract.swap(
+                alice,
+                pool_key(currency0, currency1, fee),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified: I256::try_from(amount_specified).expect("should fit"),
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                Bytes::from((now, limit).abi_encode_params()),
+            )
+        };
+
+        // An exact input of 1_000 pays out 990 after the 1% fee.
+        let err = swap(-1_000, uint!(991_U256)).expect_err("should reject a low output");
+        assert!(matches!(
+            err,
+            Error::SlippageExceeded(SlippageExceeded { limit, actual })
+                if limit == uint!(991_U256) && actual == uint!(990_U256)
+        ));
+        swap(-1_000, uint!(990_U256)).expect("should pay out the minimum");
+
+        // An exact output of 990 costs 1_000 with the fee.
+        let err = swap(990, uint!(999_U256)).expect_err("should reject a high input");
+        assert!(matches!(
+            err,
+            Error::SlippageExceeded(SlippageExceeded { limit, actual })
+                if limit == uint!(999_U256) && actual == uint!(1_000_U256)
+        ));
+        swap(990, uint!(1_000_U256)).expect("should cost the maximum");
+
+        let err = contract
+            .swap(
+                alice,
+                pool_key(currency0, currency1, fee),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified: I256::MINUS_ONE,
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                Bytes::from(vec![0u8; 48]),
+            )
+            .expect_err("should reject malformed hook data");
+        assert!(matches!(err, Error::InvalidHookData(_)));
+    }
+
+    #[motsu::test]
+    fn decommission_waits_for_timelock(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+
+        let err = contract
+            .sender(alice)
+            .decommission()
+            .expect_err("should not decommission without schedule");
+        assert!(matches!(
+            err,
+            Error::DecommissionNotReady(DecommissionNotReady { eta: 0 })
+        ));
+
+        contract
+            .sender(alice)
+            .schedule_decommission()
+            .expect("should schedule decommission");
+        let eta = contract.sender(alice).decommission_eta();
+        assert_ne!(0, eta);
+        contract.assert_emitted(&DecommissionScheduled { op_seq: 0, eta });
+
+        let err = contract
+            .sender(alice)
+            .decommission()
+            .expect_err("should not decommission before `eta`");
+        assert!(matches!(
+            err,
+            Error::DecommissionNotReady(DecommissionNotReady { eta: not_ready }) if not_ready == eta
+        ));
+
+        contract
+            .sender(alice)
+            .cancel_decommission()
+            .expect("should cancel decommission");
+        assert_eq!(0, contract.sender(alice).decommission_eta());
+    }
+
+    #[motsu::test]
+    fn decommissioned_contract_rejects_mutations(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract.sender(alice).decommissioned.set(true);
+
+        let err = contract
+            .sender(alice)
+            .set_min_swap_amount(uint!(1_U256))
+            .expect_err("should reject mutation");
+        assert!(matches!(err, Error::ContractDecommissioned(_)));
+        let err = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(1_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should reject swap");
+        assert!(matches!(err, Error::ContractDecommissioned(_)));
+
+        let err = contract
+            .sender(alice)
+            .quote_exact_input_single(QuoteExactInputSingleParams {
+                token_in: CURRENCY_1,
+                token_out: CURRENCY_2,
+                amount_in: uint!(1_U256),
+                fee: U24::ZERO,
+                sqrt_price_limit_x96: U160::ZERO,
+            })
+            .expect_err("should not quote a swap that would revert");
+        assert!(matches!(err, Error::ContractDecommissioned(_)));
+
+        assert_eq!(String::from("1.0.0"), contract.sender(alice).version());
+    }
+
+    #[test]
+    fn signature_type_hashes_match_their_types() {
+        assert_eq!(
+            keccak256("AdminAction(uint8 action,address subject,uint256 value,uint256 nonce,uint64 deadline)"),
+            admin::ADMIN_ACTION_TYPEHASH
+        );
+        assert_eq!(
+            keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)"),
+            signature::DOMAIN_TYPEHASH
+        );
+        assert_eq!(
+            keccak256("PriceReport(uint256 price,uint64 timestamp,uint256 nonce)"),
+            oracle::PRICE_REPORT_TYPEHASH
+        );
+    }
+
+    #[motsu::test]
+    fn guardian_can_only_pause_until_sunset(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_guardian(bob, u64::MAX)
+            .expect("should set guardian");
+
+        contract.sender(bob).pause().expect("should pause");
+        assert!(contract.sender(alice).paused());
+        let err = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(1_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should reject swap");
+        assert!(matches!(err, Error::ContractPaused(_)));
+
+        let err = contract
+            .sender(bob)
+            .unpause()
+            .expect_err("should not unpause");
+        assert!(matches!(err, Error::Unauthorized(_)));
+        let err = contract
+            .sender(bob)
+            .set_min_swap_amount(uint!(1_U256))
+            .expect_err("should not change params");
+        assert!(matches!(err, Error::Unauthorized(_)));
+        contract.sender(alice).unpause().expect("should unpause");
+        assert!(!contract.sender(alice).paused());
+
+        contract
+            .sender(alice)
+            .set_guardian(bob, 0)
+            .expect("should set sunset");
+        let err = contract
+            .sender(bob)
+            .pause()
+            .expect_err("should expire at sunset");
+        assert!(matches!(
+            err,
+            Error::GuardianExpired(GuardianExpired { sunset: 0 })
+        ));
+    }
+
+    #[motsu::test]
+    fn pool_state_guards_transitions(alice: Address) {
+        let contract = hook();
+        assert_eq!(
+            PoolState::Uninitialized as u8,
+            contract.sender(alice).pool_state()
+        );
+        deploy(&contract, alice);
+        assert_eq!(PoolState::Active as u8, contract.sender(alice).pool_state());
+
+        let err = contract
+            .sender(alice)
+            .unpause()
+            .expect_err("should not unpause an active pool");
+        assert!(matches!(
+            err,
+            Error::InvalidStateTransition(InvalidStateTransition { from, to })
+                if from == PoolState::Active as u8 && to == PoolState::Active as u8
+        ));
+
+        contract.sender(alice).pause().expect("should pause");
+        contract.assert_emitted(&PoolStateChanged {
+            op_seq: 1,
+            from: PoolState::Active as u8,
+            to: PoolState::Paused as u8,
+        });
+        assert_eq!(PoolState::Paused as u8, contract.sender(alice).pool_state());
+        let err = contract
+            .sender(alice)
+            .pause()
+            .expect_err("should not pause twice");
+        assert!(matches!(err, Error::InvalidStateTransition(_)));
+
+        contract.sender(alice).unpause().expect("should unpause");
+        contract.assert_emitted(&PoolStateChanged {
+            op_seq: 3,
+            from: PoolState::Paused as u8,
+            to: PoolState::Active as u8,
+        });
+        assert_eq!(PoolState::Active as u8, contract.sender(alice).pool_state());
+    }
+
+    #[motsu::test]
+    fn shadow_mode_logs_candidate_quotes(alice: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let (currency0, currency1, fee) = POOLS[0];
+        let swap = |amount_specified: I256| {
+            contract.swap(
+                alice,
+                pool_key(currency0, currency1, fee),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified,
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                Bytes::default(),
+            )
+        };
+        let amount = uint!(1_000_U256);
+        let specified = I256::try_from(amount).expect("should fit");
+
+        contract
+            .sender(alice)
+            .set_shadow_mode(true, ShadowCurve::ConstantProduct as u8, 30)
+            .expect("should enable shadow mode");
+        swap(-specified).expect("should swap exact input");
+        // The candidate deducts its fee from the input, and trades the rest
+        // along `x * y = k` of the reserves before the swap.
+        let amount_in = amount - amount * U256::from(30) / U256::from(FEE_DENOMINATOR);
+        contract.assert_emitted(&ShadowQuoteDiff {
+            pool_id: pool_ids[0],
+            exact_input: true,
+            amount,
+            active: amount,
+            shadow: RESERVE * amount_in / (RESERVE + amount_in),
+        });
+        // The candidate does not move the reserves.
+        assert_eq!(
+            (RESERVE + amount, RESERVE - amount),
+            contract
+                .sender(alice)
+                .pool_reserves(pool_ids[0])
+                .expect("should read pool reserves")
+        );
+
+        contract
+            .sender(alice)
+            .set_shadow_mode(true, ShadowCurve::ConstantSum as u8, 50)
+            .expect("should change the candidate curve");
+        swap(specified).expect("should swap exact output");
+        // 1000 at 1:1, plus a fee of 0.5% of the input, rounded up.
+        contract.assert_emitted(&ShadowQuoteDiff {
+            pool_id: pool_ids[0],
+            exact_input: false,
+            amount,
+            active: amount,
+            shadow: uint!(1_006_U256),
+        });
+
+        let err = contract
+            .sender(alice)
+            .set_shadow_mode(true, 2, 30)
+            .expect_err("should reject an unknown curve");
+        assert!(matches!(
+            err,
+            Error::InvalidShadowCurve(InvalidShadowCurve { curve: 2 })
+        ));
+        assert_eq!(
+            ShadowCurve::ConstantSum as u8,
+            contract.sender(alice).shadow_curve()
+        );
+    }
+
+    #[motsu::test]
+    fn applies_exchange_rate_with_rounding(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        // One unit of `CURRENCY_2` trades for 2/3 units of `CURRENCY_1`.
+        contract
+            .sender(alice)
+            .set_exchange_rate(CURRENCY_2, CURRENCY_1, uint!(2_U256), uint!(3_U256))
+            .expect("should set exchange rate");
+        assert_eq!(
+            (uint!(3_U256), uint!(2_U256)),
+            contract.sender(alice).exchange_rate(CURRENCY_1, CURRENCY_2)
+        );
+
         let amount_out = contract
             .sender(alice)
-            .get_amount_out_from_exact_input(amount_in, CURRENCY_1, CURRENCY_2, zero_for_one)
+            .get_amount_out_from_exact_input(uint!(3_U256), CURRENCY_1, CURRENCY_2, true)
             .expect("should calculate `amount_out`");
-        assert_eq!(expected_amount_out, amount_out);
+        assert_eq!(uint!(4_U256), amount_out);
+        let amount_out = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(4_U256), CURRENCY_2, CURRENCY_1, false)
+            .expect("should calculate `amount_out`");
+        assert_eq!(uint!(2_U256), amount_out);
+        seed_liquidity(&contract, alice, uint!(100_U256), uint!(100_U256));
+        let amount_in = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(5_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_in`");
+        assert_eq!(uint!(4_U256), amount_in);
 
-        // Assert emitted events.
-        contract.assert_emitted(&AmountOutCalculated {
+        let err = contract
+            .sender(alice)
+            .set_exchange_rate(CURRENCY_1, CURRENCY_2, uint!(1_U256), U256::ZERO)
+            .expect_err("should reject a zero denominator");
+        assert!(matches!(err, Error::InvalidExchangeRate(_)));
+    }
+
+    #[motsu::test]
+    fn quote_views_match_swaps(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_exchange_rate(CURRENCY_2, CURRENCY_1, uint!(2_U256), uint!(3_U256))
+            .expect("should set exchange rate");
+        seed_liquidity(&contract, alice, uint!(100_U256), uint!(100_U256));
+
+        // Quotes leave the reserves untouched, and swaps then match them.
+        let amount_out = contract
+            .sender(alice)
+            .quote_amount_out(uint!(3_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should quote `amount_out`");
+        let amount_in = contract
+            .sender(alice)
+            .quote_amount_in(uint!(5_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should quote `amount_in`");
+        assert_eq!((uint!(4_U256), uint!(4_U256)), (amount_out, amount_in));
+        assert_eq!(
+            (uint!(100_U256), uint!(100_U256)),
+            contract
+                .sender(alice)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+        assert_eq!(
+            amount_out,
+            contract
+                .sender(alice)
+                .get_amount_out_from_exact_input(uint!(3_U256), CURRENCY_1, CURRENCY_2, true)
+                .expect("should swap exact input")
+        );
+        assert_eq!(
             amount_in,
-            input: CURRENCY_1,
-            output: CURRENCY_2,
-            zero_for_one,
+            contract
+                .sender(alice)
+                .get_amount_in_for_exact_output(uint!(5_U256), CURRENCY_1, CURRENCY_2, true)
+                .expect("should swap exact output")
+        );
+
+        let err = contract
+            .sender(alice)
+            .quote_amount_in(uint!(1_000_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should not quote above the reserve");
+        assert!(matches!(err, Error::InsufficientLiquidity(_)));
+        let err = contract
+            .sender(alice)
+            .quote_amount_out(U256::ZERO, CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should reject a zero amount");
+        assert!(matches!(err, Error::ZeroAmount(_)));
+    }
+
+    #[motsu::test]
+    fn setters_enforce_bounds(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        assert_eq!(
+            (
+                bounds::MAX_FEE_BPS,
+                bounds::MAX_SPREAD_BPS,
+                bounds::MIN_LOCK,
+                bounds::MAX_SKEW
+            ),
+            contract.sender(alice).bounds()
+        );
+        let out_of_bounds = |err: Error, parameter: bounds::Parameter, bound: U256| {
+            matches!(
+                err,
+                Error::ParameterOutOfBounds(ParameterOutOfBounds { parameter: p, bound: b, .. })
+                    if p == parameter as u8 && b == bound
+            )
+        };
+
+        let err = contract
+            .sender(alice)
+            .set_shadow_mode(
+                true,
+                ShadowCurve::ConstantSum as u8,
+                bounds::MAX_FEE_BPS + 1,
+            )
+            .expect_err("should reject a fee above the bound");
+        assert!(matches!(err, Error::InvalidFee(_)));
+        contract
+            .sender(alice)
+            .set_after_swap_fee_bps(bounds::MAX_FEE_BPS)
+            .expect("should accept the maximum fee");
+
+        let err = contract
+            .sender(alice)
+            .set_max_deviation_bps(bounds::MAX_SPREAD_BPS + 1)
+            .expect_err("should reject a spread above the bound");
+        assert!(out_of_bounds(
+            err,
+            bounds::Parameter::Spread,
+            U256::from(bounds::MAX_SPREAD_BPS)
+        ));
+        contract
+            .sender(alice)
+            .set_max_deviation_bps(bounds::MAX_SPREAD_BPS)
+            .expect("should accept the maximum spread");
+
+        let skewed = bounds::MAX_SKEW + U256::ONE;
+        let err = contract
+            .sender(alice)
+            .set_exchange_rate(CURRENCY_1, CURRENCY_2, U256::ONE, skewed)
+            .expect_err("should reject a skew above the bound");
+        assert!(out_of_bounds(
+            err,
+            bounds::Parameter::Skew,
+            bounds::MAX_SKEW
+        ));
+        contract
+            .sender(alice)
+            .set_exchange_rate(CURRENCY_1, CURRENCY_2, bounds::MAX_SKEW, U256::ONE)
+            .expect("should accept the maximum skew");
+
+        // Auctions starting in the past are locked from now on.
+        let now = contract.sender(alice).vm().block_timestamp();
+        let half = math::WAD / uint!(2_U256);
+        let err = contract
+            .sender(alice)
+            .schedule_bootstrap(0, now + bounds::MIN_LOCK - 1, half, half)
+            .expect_err("should reject an auction shorter than the lock");
+        assert!(out_of_bounds(
+            err,
+            bounds::Parameter::Lock,
+            U256::from(bounds::MIN_LOCK)
+        ));
+        contract
+            .sender(alice)
+            .schedule_bootstrap(now + 1, now + 1 + bounds::MIN_LOCK, half, half)
+            .expect("should accept an auction lasting the lock");
+    }
+
+    #[test]
+    fn math_is_full_precision() {
+        assert_eq!(
+            Some(U256::MAX / uint!(2_U256)),
+            math::mul_div(U256::MAX, uint!(3_U256), uint!(6_U256))
+        );
+        assert_eq!(
+            Some(uint!(4_U256)),
+            math::mul_div_up(uint!(10_U256), uint!(1_U256), uint!(3_U256))
+        );
+        assert_eq!(None, math::mul_div(U256::MAX, uint!(3_U256), uint!(2_U256)));
+        assert_eq!(U256::from(SQRT_PRICE_1_1), math::sqrt(U256::from(1) << 192));
+        assert_eq!(U256::from(u128::MAX), math::sqrt(U256::MAX));
+        assert_eq!(uint!(3_U256), math::sqrt(uint!(15_U256)));
+    }
+
+    #[motsu::test]
+    fn constant_product_keeps_invariant(
+        contract: Contract<constant_product::ConstantProductCurve>,
+        alice: Address,
+    ) {
+        contract
+            .sender(alice)
+            .constructor(String::from("1.0.0"), alice);
+        for currency in [CURRENCY_1, CURRENCY_2] {
+            contract
+                .sender(alice)
+                .set_reserve(currency, uint!(1_000_U256))
+                .expect("should set reserve");
+        }
+
+        // 1_000 * 100 / 1_100 rounds down to 90.
+        let amount_out = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(100_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_out`");
+        assert_eq!(uint!(90_U256), amount_out);
+        assert_eq!(
+            uint!(1_100_U256),
+            contract.sender(alice).reserve(CURRENCY_1)
+        );
+        assert_eq!(uint!(910_U256), contract.sender(alice).reserve(CURRENCY_2));
+
+        // 1_100 * 10 / 900 rounds up to 13.
+        let amount_in = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(10_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_in`");
+        assert_eq!(uint!(13_U256), amount_in);
+
+        let err = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(900_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should not drain the output reserve");
+        assert!(matches!(
+            err,
+            Error::InsufficientLiquidity(InsufficientLiquidity { available, requested })
+                if available == uint!(900_U256) && requested == uint!(900_U256)
+        ));
+    }
+
+    #[motsu::test]
+    fn constant_product_first_deposit_sets_price(
+        contract: Contract<constant_product::ConstantProductCurve>,
+        alice: Address,
+        bob: Address,
+    ) {
+        contract
+            .sender(alice)
+            .constructor(String::from("1.0.0"), alice);
+        let two = uint!(2_U256) * math::WAD;
+        let deposit = |amount0: u64, amount1: u64, expected_price: U256, tolerance_bps: u16| {
+            contract.sender(alice).deposit(
+                CURRENCY_1,
+                CURRENCY_2,
+                U256::from(amount0),
+                U256::from(amount1),
+                expected_price,
+                tolerance_bps,
+            )
+        };
+
+        // A fat-fingered first deposit, 20:1 instead of 2:1, reverts.
+        let err = deposit(1_000, 20_000, two, 100).expect_err("should reject the price");
+        assert!(matches!(
+            err,
+            Error::PriceOutOfTolerance(PriceOutOfTolerance { expected_price, price })
+                if expected_price == two && price == uint!(20_U256) * math::WAD
+        ));
+        let err = deposit(1_000, 0, two, 100).expect_err("should need both currencies");
+        assert!(matches!(err, Error::ZeroAmount(_)));
+        let err = contract
+            .sender(bob)
+            .deposit(CURRENCY_1, CURRENCY_2, U256::ONE, U256::ONE, math::WAD, 0)
+            .expect_err("should reject other accounts");
+        assert!(matches!(err, Error::Unauthorized(_)));
+
+        let price = deposit(1_000, 2_010, two, 100).expect("should set the price within 1%");
+        assert_eq!(uint!(2_010_000_000_000_000_000_U256), price);
+        assert_eq!(
+            uint!(1_000_U256),
+            contract.sender(alice).reserve(CURRENCY_1)
+        );
+        assert_eq!(
+            uint!(2_010_U256),
+            contract.sender(alice).reserve(CURRENCY_2)
+        );
+        contract.assert_emitted(&constant_product::ReserveSet {
+            currency: CURRENCY_2,
+            reserve: uint!(2_010_U256),
+        });
+
+        // Later deposits are checked against the price they leave.
+        let err = deposit(0, 1_000, price, 100).expect_err("should not move the price");
+        assert!(matches!(err, Error::PriceOutOfTolerance(_)));
+        deposit(1_000, 2_010, price, 0).expect("should deposit proportionally");
+        assert_eq!(
+            uint!(2_000_U256),
+            contract.sender(alice).reserve(CURRENCY_1)
+        );
+    }
+
+    #[motsu::test]
+    fn stable_swap_trades_near_one_to_one(
+        contract: Contract<stable_swap::StableSwapCurve>,
+        alice: Address,
+        bob: Address,
+    ) {
+        contract
+            .sender(alice)
+            .constructor(String::from("1.0.0"), alice, uint!(100_U256))
+            .expect("should initialize contract");
+        for currency in [CURRENCY_1, CURRENCY_2] {
+            contract
+                .sender(alice)
+                .set_reserve(currency, uint!(1_000_000_U256))
+                .expect("should set reserve");
+        }
+
+        let amount_out = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(1_000_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_out`");
+        assert_eq!(uint!(999_U256), amount_out);
+        assert_eq!(
+            uint!(1_001_000_U256),
+            contract.sender(alice).reserve(CURRENCY_1)
+        );
+        assert_eq!(
+            uint!(999_001_U256),
+            contract.sender(alice).reserve(CURRENCY_2)
+        );
+
+        let amount_in = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(1_000_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_in`");
+        assert_eq!(uint!(1_000_U256), amount_in);
+
+        let err = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(998_001_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should not drain the output reserve");
+        assert!(matches!(err, Error::InsufficientLiquidity(_)));
+
+        let err = contract
+            .sender(bob)
+            .set_amplification(uint!(10_U256))
+            .expect_err("should reject non-owner");
+        assert!(matches!(err, Error::Unauthorized(_)));
+        let err = contract
+            .sender(alice)
+            .set_amplification(U256::ZERO)
+            .expect_err("should reject zero amplification");
+        assert!(matches!(err, Error::InvalidAmplification(_)));
+        contract
+            .sender(alice)
+            .set_amplification(uint!(10_U256))
+            .expect("should set amplification");
+        contract.assert_emitted(&stable_swap::AmplificationSet {
+            amplification: uint!(10_U256),
         });
     }
+
+    #[test]
+    fn stable_swap_converges_for_realistic_reserves() {
+        // Reserves between 1e3 and 2^96, amplification between 1 and 5_000.
+        for seed in 0u64..512 {
+            let hash = keccak256(seed.to_be_bytes());
+            let x0 = U256::from_be_slice(&hash[0..12]) + uint!(1_000_U256);
+            let x1 = U256::from_be_slice(&hash[12..24]) + uint!(1_000_U256);
+            let amplification = U256::from(u16::from_be_bytes([hash[24], hash[25]]) % 5_000 + 1);
+
+            let d = stable_swap::get_d(x0, x1, amplification).expect("should converge for `D`");
+            let y = stable_swap::get_y(x0, d, amplification).expect("should converge for `y`");
+            let tolerance = uint!(2_U256) + x1 / uint!(1_000_000_000_000_U256);
+            assert!(
+                y.abs_diff(x1) <= tolerance,
+                "x0: {x0}, x1: {x1}, amplification: {amplification}, y: {y}"
+            );
+
+            let amount_in = x0 / uint!(100_U256);
+            let amount_out = stable_swap::StableSwapCurve::calculate_amount_out(
+                x0,
+                x1,
+                amount_in,
+                amplification,
+            )
+            .expect("should calculate `amount_out`");
+            assert!(amount_out < x1);
+        }
+    }
+
+    #[motsu::test]
+    fn weighted_curve_keeps_invariant(
+        contract: Contract<weighted::WeightedCurve>,
+        alice: Address,
+        bob: Address,
+    ) {
+        contract
+            .sender(alice)
+            .constructor(String::from("1.0.0"), alice);
+        for currency in [CURRENCY_1, CURRENCY_2] {
+            contract
+                .sender(alice)
+                .set_reserve(currency, uint!(1_000_000_U256))
+                .expect("should set reserve");
+        }
+
+        let err = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(10_000_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should require weights");
+        assert!(matches!(
+            err,
+            Error::InvalidWeight(InvalidWeight { currency, weight })
+                if currency == CURRENCY_1 && weight.is_zero()
+        ));
+
+        let err = contract
+            .sender(bob)
+            .set_weight(CURRENCY_1, uint!(800_000_000_000_000_000_U256))
+            .expect_err("should reject non-owner");
+        assert!(matches!(err, Error::Unauthorized(_)));
+        let err = contract
+            .sender(alice)
+            .set_weight(CURRENCY_1, math::WAD + U256::from(1))
+            .expect_err("should reject weights above 100%");
+        assert!(matches!(err, Error::InvalidWeight(_)));
+
+        // 80/20 pool.
+        contract
+            .sender(alice)
+            .set_weight(CURRENCY_1, uint!(800_000_000_000_000_000_U256))
+            .expect("should set weight");
+        contract
+            .sender(alice)
+            .set_weight(CURRENCY_2, uint!(200_000_000_000_000_000_U256))
+            .expect("should set weight");
+
+        // 1_000_000 * (1 - (1_000_000 / 1_010_000)^4) is 39_019.66.
+        let amount_out = contract
+            .sender(alice)
+            .get_amount_out_from_exact_input(uint!(10_000_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_out`");
+        assert_eq!(uint!(39_019_U256), amount_out);
+        assert_eq!(
+            uint!(1_010_000_U256),
+            contract.sender(alice).reserve(CURRENCY_1)
+        );
+        assert_eq!(
+            uint!(960_981_U256),
+            contract.sender(alice).reserve(CURRENCY_2)
+        );
+
+        // 1_010_000 * ((960_981 / 950_981)^(1 / 4) - 1) is 2_644.24.
+        let amount_in = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(10_000_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect("should calculate `amount_in`");
+        assert_eq!(uint!(2_645_U256), amount_in);
+
+        let err = contract
+            .sender(alice)
+            .get_amount_in_for_exact_output(uint!(950_981_U256), CURRENCY_1, CURRENCY_2, true)
+            .expect_err("should not drain the output reserve");
+        assert!(matches!(err, Error::InsufficientLiquidity(_)));
+    }
+
+    #[test]
+    fn pow_wad_is_exact_for_exact_powers() {
+        let wad = |x: u64| U256::from(x) * math::WAD / uint!(1_000_U256);
+        assert_eq!(Some(wad(8_000)), math::pow_wad(wad(2_000), wad(3_000)));
+        assert_eq!(Some(wad(2_000)), math::pow_wad(wad(4_000), wad(500)));
+        assert_eq!(Some(wad(500)), math::pow_wad(wad(250), wad(500)));
+        assert_eq!(Some(math::WAD), math::pow_wad(wad(1_234), U256::ZERO));
+        assert_eq!(Some(U256::ZERO), math::pow_wad(U256::ZERO, wad(1_000)));
+        assert_eq!(None, math::log2_wad(U256::ZERO));
+        assert_eq!(None, math::pow_wad(wad(2_000), wad(300_000)));
+    }
+
+    #[test]
+    fn pow_wad_bounds_precision_loss() {
+        let within_bound = |actual: U256, exact: U256, slack: u64| {
+            let bound = math::mul_div_up(exact, math::MAX_POW_RELATIVE_ERROR, math::WAD)
+                .expect("should fit")
+                + U256::from(slack);
+            actual.abs_diff(exact) <= bound
+        };
+        // Bases between 1e-6 and 1e4.
+        for seed in 0u64..1_024 {
+            let hash = keccak256(seed.to_be_bytes());
+            let base = U256::from_be_slice(&hash[0..16])
+                % uint!(10_000_000_000_000_000_000_000_U256)
+                + uint!(1_000_000_000_000_U256);
+
+            // Integer exponents, against repeated multiplication which rounds
+            // down once per step.
+            let mut exact = math::WAD;
+            for k in 0u64..=4 {
+                let power =
+                    math::pow_wad(base, U256::from(k) * math::WAD).expect("should calculate power");
+                assert!(
+                    within_bound(power, exact, k + 1),
+                    "base: {base}, exponent: {k}, power: {power}, exact: {exact}"
+                );
+                exact = math::mul_div(exact, base, math::WAD).expect("should fit");
+            }
+
+            // Square roots, against the integer square root.
+            let root = math::sqrt(base * math::WAD);
+            let power = math::pow_wad(base, math::WAD >> 1).expect("should calculate power");
+            assert!(
+                within_bound(power, root, 1),
+                "base: {base}, power: {power}, root: {root}"
+            );
+            let power_up = math::pow_wad_up(base, math::WAD >> 1).expect("should calculate power");
+            assert!(power_up > root);
+
+            let log = math::log2_wad(base).expect("should calculate logarithm");
+            let exp = math::exp2_wad(log).expect("should calculate exponential");
+            assert!(within_bound(exp, base, 1), "base: {base}, exp: {exp}");
+        }
+    }
+
+    #[test]
+    fn quotes_with_state() {
+        let reserve = uint!(1_000_U256);
+        let one = U256::from(1);
+        let amount_out = ConstantSumCurve::quote_with_state(
+            reserve,
+            reserve,
+            30,
+            one,
+            one,
+            uint!(100_U256),
+            true,
+        )
+        .expect("should quote `amount_out`");
+        // 0.3% of 100 rounds down to 0.
+        assert_eq!(uint!(100_U256), amount_out);
+
+        let amount_out = ConstantSumCurve::quote_with_state(
+            reserve,
+            reserve,
+            30,
+            one,
+            one,
+            uint!(1_000_U256),
+            true,
+        )
+        .expect("should quote `amount_out`");
+        assert_eq!(uint!(997_U256), amount_out);
+
+        let amount_in = ConstantSumCurve::quote_in_with_state(
+            reserve,
+            reserve,
+            30,
+            one,
+            one,
+            uint!(997_U256),
+            false,
+        )
+        .expect("should quote `amount_in`");
+        assert_eq!(uint!(1_000_U256), amount_in);
+
+        // An unset rate trades at 1:1.
+        let amount_out = ConstantSumCurve::quote_with_state(
+            reserve,
+            reserve,
+            30,
+            U256::ZERO,
+            U256::ZERO,
+            uint!(1_000_U256),
+            true,
+        )
+        .expect("should quote `amount_out`");
+        assert_eq!(uint!(997_U256), amount_out);
+    }
+
+    #[motsu::test]
+    fn quotes_with_state_match_swaps(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        seed_liquidity(&contract, alice, RESERVE, RESERVE);
+        // 1 `CURRENCY_1` trades for 3/2 `CURRENCY_2`.
+        let (numerator, denominator) = (uint!(3_U256), uint!(2_U256));
+        contract
+            .sender(alice)
+            .set_exchange_rate(CURRENCY_1, CURRENCY_2, numerator, denominator)
+            .expect("should set exchange rate");
+        let (reserve0, reserve1) = contract
+            .sender(alice)
+            .get_reserves()
+            .expect("should read reserves");
+        let fee_bps = contract.sender(alice).fee();
+
+        for zero_for_one in [true, false] {
+            let (input, output) = if zero_for_one {
+                (CURRENCY_1, CURRENCY_2)
+            } else {
+                (CURRENCY_2, CURRENCY_1)
+            };
+            let amount = uint!(1_001_U256);
+            let amount_out = ConstantSumCurve::quote_with_state(
+                reserve0,
+                reserve1,
+                fee_bps,
+                numerator,
+                denominator,
+                amount,
+                zero_for_one,
+            )
+            .expect("should quote `amount_out`");
+            assert_eq!(
+                contract
+                    .sender(alice)
+                    .quote_amount_out(amount, input, output, zero_for_one)
+                    .expect("should quote the swap"),
+                amount_out
+            );
+            let amount_in = ConstantSumCurve::quote_in_with_state(
+                reserve0,
+                reserve1,
+                fee_bps,
+                numerator,
+                denominator,
+                amount,
+                zero_for_one,
+            )
+            .expect("should quote `amount_in`");
+            assert_eq!(
+                contract
+                    .sender(alice)
+                    .quote_amount_in(amount, input, output, zero_for_one)
+                    .expect("should quote the swap"),
+                amount_in
+            );
+        }
+    }
+
+    #[test]
+    fn quote_with_state_reverts_when_liquidity_is_insufficient() {
+        let err = ConstantSumCurve::quote_with_state(
+            uint!(1_000_U256),
+            uint!(10_U256),
+            0,
+            U256::from(1),
+            U256::from(1),
+            uint!(11_U256),
+            true,
+        )
+        .expect_err("should not quote above `reserve1`");
+        assert!(matches!(
+            err,
+            Error::InsufficientLiquidity(InsufficientLiquidity { available, requested })
+                if available == uint!(10_U256) && requested == uint!(11_U256)
+        ));
+    }
+
+    #[test]
+    fn quote_with_state_reverts_on_invalid_fee() {
+        let err = ConstantSumCurve::quote_in_with_state(
+            U256::MAX,
+            U256::MAX,
+            FEE_DENOMINATOR,
+            U256::from(1),
+            U256::from(1),
+            uint!(1_U256),
+            true,
+        )
+        .expect_err("should not quote with a 100% fee");
+        assert!(matches!(
+            err,
+            Error::InvalidFee(InvalidFee {
+                fee_bps: FEE_DENOMINATOR
+            })
+        ));
+    }
 }
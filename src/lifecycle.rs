@@ -0,0 +1,113 @@
+//! Lifecycle of the pool, as a single state derived from the contract's
+//! flags.
+//!
+//! The pool starts `Uninitialized` until its constructor runs, and is then
+//! `Active`, or `Bootstrapping` while a bootstrap auction is scheduled or
+//! running. Pausing overrides both until the pool is unpaused, and the
+//! decommission retires the pool for good.
+//!
+//! Transitions by the owner or the guardian are checked against
+//! [`PoolState::can_transition_to`] and emit `PoolStateChanged`. The end of
+//! a bootstrap is driven by the block timestamp instead, so it emits no
+//! event.
+use alloy_primitives::{B256, U256};
+use stylus_sdk::{evm, prelude::*};
+
+use crate::{
+    bootstrap::Phase, changelog::ChangeKind, ConstantSumCurve, Error, InvalidStateTransition,
+    PoolStateChanged,
+};
+
+/// State of the pool's lifecycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PoolState {
+    /// The constructor has not run yet.
+    Uninitialized = 0,
+    /// A bootstrap auction is scheduled or running.
+    Bootstrapping = 1,
+    /// Swaps trade at the exchange rate.
+    Active = 2,
+    /// Swaps are paused.
+    Paused = 3,
+    /// The contract is decommissioned and cannot be mutated.
+    Retired = 4,
+}
+
+impl PoolState {
+    /// Returns true if the pool can move from `self` to `to`.
+    pub fn can_transition_to(self, to: PoolState) -> bool {
+        use PoolState::*;
+        matches!(
+            (self, to),
+            (Uninitialized, Active)
+                | (Active, Bootstrapping)
+                | (Bootstrapping, Active)
+                | (Active | Bootstrapping, Paused)
+                | (Paused, Active | Bootstrapping)
+                | (Active | Bootstrapping | Paused, Retired)
+        )
+    }
+}
+
+impl ConstantSumCurve {
+    /// Returns the current [`PoolState`].
+    pub(crate) fn current_state(&self) -> PoolState {
+        if self.decommissioned.get() {
+            PoolState::Retired
+        } else if self.paused.get() {
+            PoolState::Paused
+        } else {
+            self.unpaused_state()
+        }
+    }
+
+    /// Returns the [`PoolState`] the pool is in once unpaused.
+    pub(crate) fn unpaused_state(&self) -> PoolState {
+        if self.version.get_string().is_empty() {
+            PoolState::Uninitialized
+        } else if self.current_phase() == Phase::Trading {
+            PoolState::Active
+        } else {
+            PoolState::Bootstrapping
+        }
+    }
+
+    /// Checks that the pool can move from its current state to `to`, and
+    /// returns the current state.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidStateTransition`] - If the transition is not
+    ///   allowed.
+    pub(crate) fn check_transition(&self, to: PoolState) -> Result<PoolState, Error> {
+        let from = self.current_state();
+        if !from.can_transition_to(to) {
+            return Err(Error::InvalidStateTransition(InvalidStateTransition {
+                from: from as u8,
+                to: to as u8,
+            }));
+        }
+        Ok(from)
+    }
+
+    /// Emits [`PoolStateChanged`] if the pool left the state `from`.
+    ///
+    /// # Events
+    ///
+    /// * [`PoolStateChanged`].
+    pub(crate) fn emit_state_change(&mut self, from: PoolState) {
+        let to = self.current_state();
+        if to == from {
+            return;
+        }
+        let op_seq = self.record_change(ChangeKind::PoolState, B256::ZERO, U256::from(to as u8));
+
+        #[allow(deprecated)]
+        evm::log(PoolStateChanged {
+            op_seq,
+            from: from as u8,
+            to: to as u8,
+        });
+    }
+}
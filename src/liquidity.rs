@@ -0,0 +1,662 @@
+//! Liquidity of the default pool, provided against LP shares.
+//!
+//! Once the owner sets the currencies of the default pool, anyone can
+//! deposit both of them into its reserves and receive LP shares, as in the
+//! constant-sum hook of v4-by-example. Shares are minted in proportion to the
+//! value added to the reserves, valued in `currency1` at the pool's exchange
+//! rate, so deposits of any ratio neither dilute nor favour other providers.
+//! The first deposit mints one share per unit of value, and locks
+//! [`MINIMUM_LIQUIDITY`] of them for good, like Uniswap v2.
+//!
+//! The reserves backing the shares are accounted, not read from balances:
+//! only deposits and withdrawals move them. Tokens sent to the hook are not
+//! added to them, legacy swaps through [`crate::IUniswapV4Curve`] only quote
+//! against them, and the owner cannot set them while shares are outstanding.
+//!
+//! Burning shares pays out their part of both reserves, rounded down in
+//! favour of the remaining providers, above minimums set by the provider.
+//! Shares are balances of the hook's own ERC-20 LP token, see
+//! [`crate::lp_token`].
+use alloy_primitives::{uint, Address, U256};
+use stylus_sdk::{evm, prelude::*};
+
+use crate::{
+    math, pool::DEFAULT_POOL_ID, AmountOverflow, ConstantSumCurve, Currency, Error,
+    InsufficientLiquidityBurned, InsufficientLiquidityMinted, InsufficientOutputAmount,
+    InsufficientShares, LiquidityAdded, LiquidityRemoved, PoolNotRegistered, SharesOutstanding,
+    Transfer,
+};
+
+/// LP shares locked by the first deposit, held by the zero address.
+pub const MINIMUM_LIQUIDITY: U256 = uint!(1_000_U256);
+
+/// Checks that a withdrawal pays out at least `min`.
+///
+/// # Errors
+///
+/// * [`Error::InsufficientOutputAmount`] - If `amount` is below `min`.
+pub fn check_min_amount(amount: U256, min: U256) -> Result<(), Error> {
+    if amount < min {
+        return Err(Error::InsufficientOutputAmount(InsufficientOutputAmount {
+            amount,
+            min,
+        }));
+    }
+    Ok(())
+}
+
+/// Interface of the liquidity of the default pool.
+pub trait ILiquidity {
+    /// The error type associated to the trait implementation.
+    type Error: Into<alloc::vec::Vec<u8>>;
+
+    /// Deposits `amount0` of `currency0` and `amount1` of `currency1` into
+    /// the reserves of the default pool, and mints LP shares to the caller
+    /// in proportion to the value added. The first deposit locks
+    /// [`MINIMUM_LIQUIDITY`] of its shares.
+    ///
+    /// ERC-20 tokens are pulled with `transferFrom`, so the caller approves
+    /// them first. Native ETH is paid as the value of the call.
+    ///
+    /// Returns the minted shares.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `amount0` - The amount of `currency0` to deposit.
+    /// * `amount1` - The amount of `currency1` to deposit.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::PoolNotRegistered`] - If the currencies of the default
+    ///   pool are not set.
+    /// * [`Error::UnexpectedNativeValue`] - If value is sent for a pool of
+    ///   two ERC-20 tokens.
+    /// * [`Error::NativeValueMismatch`] - If the value sent differs from the
+    ///   native amount.
+    /// * [`Error::AmountOverflow`] - If a value does not fit into `uint256`.
+    /// * [`Error::InsufficientLiquidityMinted`] - If the deposit is too small
+    ///   to mint any share, beyond the locked ones for the first deposit.
+    /// * [`Error::TransferFailed`] - If an ERC-20 token cannot be pulled.
+    /// * [`Error::Reentrancy`] - If another operation holds the lock.
+    ///
+    /// # Events
+    ///
+    /// * [`LiquidityAdded`].
+    fn add_liquidity(&mut self, amount0: U256, amount1: U256) -> Result<U256, Self::Error>;
+
+    /// Burns `shares` LP shares of the caller, and pays out its proportional
+    /// part of both reserves of the default pool, rounded down.
+    ///
+    /// Returns the amounts of `currency0` and `currency1` paid out.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `shares` - The LP shares to burn.
+    /// * `min0` - The minimum amount of `currency0` to receive.
+    /// * `min1` - The minimum amount of `currency1` to receive.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::PoolNotRegistered`] - If the currencies of the default
+    ///   pool are not set.
+    /// * [`Error::InsufficientShares`] - If the caller holds fewer than
+    ///   `shares`.
+    /// * [`Error::InsufficientLiquidityBurned`] - If the shares pay nothing
+    ///   out.
+    /// * [`Error::InsufficientOutputAmount`] - If an amount is below its
+    ///   minimum.
+    /// * [`Error::TransferFailed`] - If a currency cannot be sent.
+    /// * [`Error::Reentrancy`] - If another operation holds the lock.
+    ///
+    /// # Events
+    ///
+    /// * [`LiquidityRemoved`].
+    fn remove_liquidity(
+        &mut self,
+        shares: U256,
+        min0: U256,
+        min1: U256,
+    ) -> Result<(U256, U256), Self::Error>;
+}
+
+#[public]
+impl ILiquidity for ConstantSumCurve {
+    type Error = Error;
+
+    #[payable]
+    fn add_liquidity(&mut self, amount0: U256, amount1: U256) -> Result<U256, Self::Error> {
+        meter!("addLiquidity", U256, U256);
+        self.check_active()?;
+        let (currency0, currency1) = self.default_currencies()?;
+        self.check_native_value(DEFAULT_POOL_ID, (currency0, amount0), (currency1, amount1))?;
+
+        let provider = self.vm().msg_sender();
+        let shares = self.with_lock(|curve| {
+            let (shares, first) =
+                curve.shares_for_deposit((currency0, amount0), (currency1, amount1))?;
+            let (reserve0, reserve1) = curve.reserves_of(DEFAULT_POOL_ID);
+            let overflow = || Error::AmountOverflow(AmountOverflow {});
+            let reserve0 = reserve0.checked_add(amount0).ok_or_else(overflow)?;
+            let reserve1 = reserve1.checked_add(amount1).ok_or_else(overflow)?;
+            curve.pull_currency(currency0, provider, amount0)?;
+            curve.pull_currency(currency1, provider, amount1)?;
+            curve.set_reserves_of(DEFAULT_POOL_ID, reserve0, reserve1);
+            if first {
+                curve.mint_shares(Address::ZERO, MINIMUM_LIQUIDITY)?;
+            }
+            curve.mint_shares(provider, shares)?;
+            Ok(shares)
+        })?;
+
+        #[allow(deprecated)]
+        evm::log(LiquidityAdded {
+            provider,
+            amount0,
+            amount1,
+            shares,
+        });
+
+        Ok(shares)
+    }
+
+    fn remove_liquidity(
+        &mut self,
+        shares: U256,
+        min0: U256,
+        min1: U256,
+    ) -> Result<(U256, U256), Self::Error> {
+        meter!("removeLiquidity", U256, U256, U256);
+        self.check_active()?;
+        let (currency0, currency1) = self.default_currencies()?;
+        let provider = self.vm().msg_sender();
+        let (amount0, amount1) = self.with_lock(|curve| {
+            let (amount0, amount1) = curve.amounts_for_shares(shares);
+            if amount0.is_zero() && amount1.is_zero() {
+                return Err(Error::InsufficientLiquidityBurned(
+                    InsufficientLiquidityBurned {},
+                ));
+            }
+            check_min_amount(amount0, min0)?;
+            check_min_amount(amount1, min1)?;
+
+            curve.burn_shares(provider, shares)?;
+            let (reserve0, reserve1) = curve.reserves_of(DEFAULT_POOL_ID);
+            curve.set_reserves_of(DEFAULT_POOL_ID, reserve0 - amount0, reserve1 - amount1);
+            curve.push_currency(currency0, provider, amount0)?;
+            curve.push_currency(currency1, provider, amount1)?;
+            Ok((amount0, amount1))
+        })?;
+
+        #[allow(deprecated)]
+        evm::log(LiquidityRemoved {
+            provider,
+            amount0,
+            amount1,
+            shares,
+        });
+
+        Ok((amount0, amount1))
+    }
+}
+
+impl ConstantSumCurve {
+    /// Returns the currencies of the default pool.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolNotRegistered`] - If the owner has not set them.
+    pub(crate) fn default_currencies(&self) -> Result<(Currency, Currency), Error> {
+        // `currency1` sorts after `currency0`, so it is only zero until set.
+        let currency1 = self.currency1.get();
+        if currency1.is_zero() {
+            return Err(Error::PoolNotRegistered(PoolNotRegistered {
+                pool_id: DEFAULT_POOL_ID,
+            }));
+        }
+        Ok((self.currency0.get(), currency1))
+    }
+
+    /// Checks that no LP shares are backed by the reserves of the default
+    /// pool.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::SharesOutstanding`] - If LP shares are minted.
+    pub(crate) fn check_no_shares(&self) -> Result<(), Error> {
+        let total_shares = self.total_shares.get();
+        if !total_shares.is_zero() {
+            return Err(Error::SharesOutstanding(SharesOutstanding { total_shares }));
+        }
+        Ok(())
+    }
+
+    /// Returns the value of `amount0` of `currency0` and `amount1` of
+    /// `currency1` in `currency1`, at the exchange rate of the pair.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If the value does not fit into
+    ///   `uint256`.
+    fn liquidity_value(
+        &self,
+        (currency0, amount0): (Currency, U256),
+        (currency1, amount1): (Currency, U256),
+    ) -> Result<U256, Error> {
+        let overflow = || Error::AmountOverflow(AmountOverflow {});
+        let (numerator, denominator) = self.exchange_rate(currency0, currency1);
+        math::mul_div(amount0, numerator, denominator)
+            .and_then(|value0| value0.checked_add(amount1))
+            .ok_or_else(overflow)
+    }
+
+    /// Returns the LP shares minted for a deposit of `amount0` and
+    /// `amount1` into the default pool of `currency0` and `currency1`,
+    /// rounded down, and whether it is the first deposit, which locks
+    /// [`MINIMUM_LIQUIDITY`] of them.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If a value does not fit into `uint256`.
+    /// * [`Error::InsufficientLiquidityMinted`] - If no share is minted.
+    pub(crate) fn shares_for_deposit(
+        &self,
+        (currency0, amount0): (Currency, U256),
+        (currency1, amount1): (Currency, U256),
+    ) -> Result<(U256, bool), Error> {
+        let value = self.liquidity_value((currency0, amount0), (currency1, amount1))?;
+        let total_shares = self.total_shares.get();
+        let (reserve0, reserve1) = self.reserves_of(DEFAULT_POOL_ID);
+        let reserves_value = self.liquidity_value((currency0, reserve0), (currency1, reserve1))?;
+        let first = total_shares.is_zero();
+        let shares = if first {
+            value.saturating_sub(MINIMUM_LIQUIDITY)
+        } else if reserves_value.is_zero() {
+            value
+        } else {
+            math::mul_div(value, total_shares, reserves_value)
+                .ok_or(Error::AmountOverflow(AmountOverflow {}))?
+        };
+        if shares.is_zero() {
+            return Err(Error::InsufficientLiquidityMinted(
+                InsufficientLiquidityMinted {},
+            ));
+        }
+        Ok((shares, first))
+    }
+
+    /// Mints `amount` LP shares to `to`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If the total supply of shares does not
+    ///   fit into `uint256`.
+    ///
+    /// # Events
+    ///
+    /// * [`Transfer`].
+    pub(crate) fn mint_shares(&mut self, to: Address, amount: U256) -> Result<(), Error> {
+        let total_shares = self
+            .total_shares
+            .get()
+            .checked_add(amount)
+            .ok_or(Error::AmountOverflow(AmountOverflow {}))?;
+        // A balance never exceeds the total supply, so it cannot overflow.
+        let balance = self.shares.get(to);
+        self.shares.setter(to).set(balance + amount);
+        self.total_shares.set(total_shares);
+
+        #[allow(deprecated)]
+        evm::log(Transfer {
+            from: Address::ZERO,
+            to,
+            value: amount,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the parts of the reserves of the default pool backing
+    /// `shares` LP shares, rounded down, or zeroes if there are no shares.
+    pub(crate) fn amounts_for_shares(&self, shares: U256) -> (U256, U256) {
+        let total_shares = self.total_shares.get();
+        if total_shares.is_zero() {
+            return (U256::ZERO, U256::ZERO);
+        }
+        // `shares` beyond the total are rejected when burnt, so the parts
+        // can be clamped to the reserves.
+        let shares = shares.min(total_shares);
+        let (reserve0, reserve1) = self.reserves_of(DEFAULT_POOL_ID);
+        let part = |reserve: U256| math::mul_div(reserve, shares, total_shares).unwrap_or(reserve);
+        (part(reserve0), part(reserve1))
+    }
+
+    /// Burns `amount` LP shares of `from`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InsufficientShares`] - If `from` holds fewer than `amount`.
+    ///
+    /// # Events
+    ///
+    /// * [`Transfer`].
+    pub(crate) fn burn_shares(&mut self, from: Address, amount: U256) -> Result<(), Error> {
+        let balance = self.shares.get(from);
+        if balance < amount {
+            return Err(Error::InsufficientShares(InsufficientShares {
+                balance,
+                shares: amount,
+            }));
+        }
+        self.shares.setter(from).set(balance - amount);
+        let total_shares = self.total_shares.get();
+        self.total_shares.set(total_shares - amount);
+
+        #[allow(deprecated)]
+        evm::log(Transfer {
+            from,
+            to: Address::ZERO,
+            value: amount,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lp_token::ILpToken,
+        testing::{deploy, hook, CURRENCY_1, CURRENCY_2, HOOK},
+        CurrenciesSet, PoolAlreadyRegistered, Unauthorized, UnsortedCurrencies,
+    };
+
+    #[motsu::test]
+    fn add_liquidity_mints_proportional_shares(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        let amount = uint!(1_000_U256);
+        let err = contract
+            .sender(alice)
+            .add_liquidity(amount, amount)
+            .expect_err("should need the currencies");
+        assert!(matches!(err, Error::PoolNotRegistered(_)));
+
+        let err = contract
+            .sender(bob)
+            .set_currencies(CURRENCY_1, CURRENCY_2)
+            .expect_err("should reject non-owner");
+        assert!(matches!(err, Error::Unauthorized(_)));
+        let err = contract
+            .sender(alice)
+            .set_currencies(CURRENCY_2, CURRENCY_1)
+            .expect_err("should reject unsorted currencies");
+        assert!(matches!(err, Error::UnsortedCurrencies(_)));
+        contract
+            .sender(alice)
+            .set_currencies(CURRENCY_1, CURRENCY_2)
+            .expect("should set currencies");
+        contract.assert_emitted(&CurrenciesSet {
+            op_seq: 0,
+            currency0: CURRENCY_1,
+            currency1: CURRENCY_2,
+        });
+        let err = contract
+            .sender(alice)
+            .set_currencies(CURRENCY_1, CURRENCY_2)
+            .expect_err("should set currencies once");
+        assert!(matches!(err, Error::PoolAlreadyRegistered(_)));
+
+        // The first deposit mints one share per unit of value, but locks
+        // some of them.
+        contract.mint(CURRENCY_1, alice, amount);
+        contract.mint(CURRENCY_2, alice, amount);
+        contract.mint(CURRENCY_1, bob, uint!(500_U256));
+        let shares = contract
+            .sender(alice)
+            .add_liquidity(amount, amount)
+            .expect("should add liquidity");
+        assert_eq!(uint!(2_000_U256) - MINIMUM_LIQUIDITY, shares);
+        contract.assert_emitted(&LiquidityAdded {
+            provider: alice,
+            amount0: amount,
+            amount1: amount,
+            shares,
+        });
+
+        // Later deposits mint shares in proportion to the value added.
+        let shares = contract
+            .sender(bob)
+            .add_liquidity(uint!(500_U256), U256::ZERO)
+            .expect("should add one-sided liquidity");
+        assert_eq!(uint!(500_U256), shares);
+        assert_eq!(
+            (uint!(1_500_U256), amount),
+            contract
+                .sender(alice)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+        assert_eq!(uint!(1_000_U256), contract.sender(alice).balance_of(alice));
+        assert_eq!(
+            MINIMUM_LIQUIDITY,
+            contract.sender(alice).balance_of(Address::ZERO)
+        );
+        assert_eq!(shares, contract.sender(alice).balance_of(bob));
+        assert_eq!(
+            uint!(2_500_U256),
+            contract
+                .sender(alice)
+                .total_supply()
+                .expect("should read total supply")
+        );
+        assert_eq!(uint!(1_500_U256), contract.balance(CURRENCY_1, HOOK));
+        assert_eq!(amount, contract.balance(CURRENCY_2, HOOK));
+
+        let err = contract
+            .sender(bob)
+            .add_liquidity(U256::ZERO, U256::ZERO)
+            .expect_err("should mint some shares");
+        assert!(matches!(err, Error::InsufficientLiquidityMinted(_)));
+    }
+
+    #[motsu::test]
+    fn minimum_liquidity_defeats_share_inflation(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_currencies(CURRENCY_1, CURRENCY_2)
+            .expect("should set currencies");
+        let donation = uint!(1_000_000_U256);
+        contract.mint(CURRENCY_1, bob, uint!(2_000_U256) + donation);
+        let err = contract
+            .sender(bob)
+            .add_liquidity(MINIMUM_LIQUIDITY, U256::ZERO)
+            .expect_err("should mint shares beyond the locked ones");
+        assert!(matches!(err, Error::InsufficientLiquidityMinted(_)));
+
+        // The attacker mints a single share, then tries to inflate its value
+        // by donating to the hook.
+        let minimum = MINIMUM_LIQUIDITY + U256::ONE;
+        let shares = contract
+            .sender(bob)
+            .add_liquidity(minimum, U256::ZERO)
+            .expect("should add liquidity");
+        assert_eq!(U256::ONE, shares);
+        contract.assert_emitted(&Transfer {
+            from: Address::ZERO,
+            to: Address::ZERO,
+            value: MINIMUM_LIQUIDITY,
+        });
+        contract
+            .token(CURRENCY_1)
+            .sender(bob)
+            .transfer(HOOK, donation)
+            .expect("should donate");
+        assert_eq!(
+            (minimum, U256::ZERO),
+            contract
+                .sender(alice)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+        let err = contract
+            .sender(alice)
+            .set_reserves(minimum + donation, U256::ZERO)
+            .expect_err("should not set reserves backing shares");
+        assert!(matches!(
+            err,
+            Error::SharesOutstanding(SharesOutstanding { total_shares })
+                if total_shares == minimum
+        ));
+
+        // The donation is not accounted, so the victim gets a fair share.
+        let deposit = uint!(1_000_000_U256);
+        contract.mint(CURRENCY_1, alice, deposit);
+        let shares = contract
+            .sender(alice)
+            .add_liquidity(deposit, U256::ZERO)
+            .expect("should add liquidity");
+        assert_eq!(deposit, shares);
+        let (amount0, _) = contract
+            .sender(alice)
+            .remove_liquidity(shares, U256::ZERO, U256::ZERO)
+            .expect("should remove liquidity");
+        assert_eq!(deposit, amount0);
+
+        let (amount0, _) = contract
+            .sender(bob)
+            .remove_liquidity(U256::ONE, U256::ZERO, U256::ZERO)
+            .expect("should remove liquidity");
+        assert_eq!(U256::ONE, amount0);
+        assert_eq!(deposit, contract.balance(CURRENCY_1, alice));
+    }
+
+    #[motsu::test]
+    fn remove_liquidity_pays_out_proportionally(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_currencies(CURRENCY_1, CURRENCY_2)
+            .expect("should set currencies");
+        contract.mint(CURRENCY_1, alice, uint!(1_500_U256));
+        contract.mint(CURRENCY_2, alice, uint!(600_U256));
+        contract
+            .sender(alice)
+            .add_liquidity(uint!(1_500_U256), uint!(600_U256))
+            .expect("should add liquidity");
+        assert_eq!(
+            uint!(2_100_U256),
+            contract
+                .sender(alice)
+                .total_supply()
+                .expect("should read total supply")
+        );
+
+        let shares = uint!(700_U256);
+        let err = contract
+            .sender(alice)
+            .remove_liquidity(shares, U256::ZERO, uint!(201_U256))
+            .expect_err("should enforce the minimum of `currency1`");
+        assert!(matches!(
+            err,
+            Error::InsufficientOutputAmount(InsufficientOutputAmount { amount, min })
+                if amount == uint!(200_U256) && min == uint!(201_U256)
+        ));
+        let amounts = contract
+            .sender(alice)
+            .remove_liquidity(shares, uint!(500_U256), uint!(200_U256))
+            .expect("should remove liquidity");
+        assert_eq!((uint!(500_U256), uint!(200_U256)), amounts);
+        assert_eq!(uint!(500_U256), contract.balance(CURRENCY_1, alice));
+        assert_eq!(uint!(200_U256), contract.balance(CURRENCY_2, alice));
+        contract.assert_emitted(&LiquidityRemoved {
+            provider: alice,
+            amount0: uint!(500_U256),
+            amount1: uint!(200_U256),
+            shares,
+        });
+        assert_eq!(
+            (uint!(1_000_U256), uint!(400_U256)),
+            contract
+                .sender(alice)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+        assert_eq!(
+            uint!(1_400_U256),
+            contract
+                .sender(alice)
+                .total_supply()
+                .expect("should read total supply")
+        );
+
+        // Payouts round down in favour of the remaining providers.
+        let err = contract
+            .sender(alice)
+            .remove_liquidity(U256::ONE, U256::ZERO, U256::ZERO)
+            .expect_err("should pay something out");
+        assert!(matches!(err, Error::InsufficientLiquidityBurned(_)));
+        let amounts = contract
+            .sender(alice)
+            .remove_liquidity(uint!(2_U256), U256::ZERO, U256::ZERO)
+            .expect("should remove liquidity");
+        assert_eq!((U256::ONE, U256::ZERO), amounts);
+
+        let err = contract
+            .sender(bob)
+            .remove_liquidity(uint!(100_U256), U256::ZERO, U256::ZERO)
+            .expect_err("should burn held shares only");
+        assert!(matches!(
+            err,
+            Error::InsufficientShares(InsufficientShares { balance, shares })
+                if balance.is_zero() && shares == uint!(100_U256)
+        ));
+    }
+
+    #[motsu::test]
+    fn add_liquidity_rejects_overflows(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_currencies(CURRENCY_1, CURRENCY_2)
+            .expect("should set currencies");
+        contract.mint(CURRENCY_1, bob, U256::MAX);
+        contract
+            .sender(bob)
+            .add_liquidity(U256::MAX, U256::ZERO)
+            .expect("should add liquidity");
+        assert_eq!(
+            U256::MAX,
+            contract
+                .sender(bob)
+                .total_supply()
+                .expect("should read total supply")
+        );
+
+        contract.mint(CURRENCY_1, alice, U256::ONE);
+        contract.mint(CURRENCY_2, alice, U256::ONE);
+        let overflow = |err: Error| matches!(err, Error::AmountOverflow(_));
+        assert!(overflow(
+            contract
+                .sender(alice)
+                .add_liquidity(U256::ONE, U256::ZERO)
+                .expect_err("should not overflow the reserve")
+        ));
+        assert!(overflow(
+            contract
+                .sender(alice)
+                .add_liquidity(U256::ZERO, U256::ONE)
+                .expect_err("should not overflow the total supply")
+        ));
+    }
+}
@@ -0,0 +1,399 @@
+//! ERC-20 token of the LP shares of the default pool.
+//!
+//! The hook is its own LP token: shares minted by `addLiquidity` and burnt
+//! by `removeLiquidity` are ERC-20 balances, transferable without an
+//! external token contract. Mints and burns emit `Transfer` from and to the
+//! zero address, like any ERC-20 supply change.
+use alloc::string::String;
+
+use alloy_primitives::{Address, U256};
+use stylus_sdk::{evm, prelude::*};
+
+use crate::{
+    Approval, ConstantSumCurve, Error, InsufficientAllowance, InsufficientShares, InvalidReceiver,
+    Transfer,
+};
+
+/// Name of the LP token.
+pub const NAME: &str = "Constant Sum LP";
+
+/// Symbol of the LP token.
+pub const SYMBOL: &str = "CSLP";
+
+/// Decimals of the LP token.
+pub const DECIMALS: u8 = 18;
+
+/// Interface of the ERC-20 LP token of the default pool.
+pub trait ILpToken {
+    /// The error type associated to the trait implementation.
+    type Error: Into<alloc::vec::Vec<u8>>;
+
+    /// Returns the name of the LP token.
+    fn name(&self) -> String;
+
+    /// Returns the symbol of the LP token.
+    fn symbol(&self) -> String;
+
+    /// Returns the decimals of the LP token.
+    fn decimals(&self) -> u8;
+
+    /// Returns the total LP shares of the default pool.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    fn total_supply(&self) -> Result<U256, Self::Error>;
+
+    /// Returns the LP shares of the default pool held by `account`.
+    fn balance_of(&self, account: Address) -> U256;
+
+    /// Returns the LP shares of `owner` that `spender` can move.
+    fn allowance(&self, owner: Address, spender: Address) -> U256;
+
+    /// Moves `amount` LP shares from the caller to `to`.
+    ///
+    /// Returns true, as ERC-20 requires.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `to` - The recipient of the shares.
+    /// * `amount` - The shares to move.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::InvalidReceiver`] - If `to` is the zero address.
+    /// * [`Error::InsufficientShares`] - If the caller holds fewer than
+    ///   `amount`.
+    ///
+    /// # Events
+    ///
+    /// * [`Transfer`].
+    fn transfer(&mut self, to: Address, amount: U256) -> Result<bool, Self::Error>;
+
+    /// Lets `spender` move `amount` LP shares of the caller, or any amount
+    /// if `amount` is `U256::MAX`.
+    ///
+    /// Returns true, as ERC-20 requires.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `spender` - The account allowed to move the shares.
+    /// * `amount` - The new allowance.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    ///
+    /// # Events
+    ///
+    /// * [`Approval`].
+    fn approve(&mut self, spender: Address, amount: U256) -> Result<bool, Self::Error>;
+
+    /// Moves `amount` LP shares from `from` to `to`, spending the caller's
+    /// allowance.
+    ///
+    /// Returns true, as ERC-20 requires.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `from` - The holder of the shares.
+    /// * `to` - The recipient of the shares.
+    /// * `amount` - The shares to move.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::InsufficientAllowance`] - If the caller's allowance is
+    ///   smaller than `amount`.
+    /// * [`Error::InvalidReceiver`] - If `to` is the zero address.
+    /// * [`Error::InsufficientShares`] - If `from` holds fewer than
+    ///   `amount`.
+    ///
+    /// # Events
+    ///
+    /// * [`Transfer`].
+    fn transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<bool, Self::Error>;
+}
+
+#[public]
+impl ILpToken for ConstantSumCurve {
+    type Error = Error;
+
+    fn name(&self) -> String {
+        String::from(NAME)
+    }
+
+    fn symbol(&self) -> String {
+        String::from(SYMBOL)
+    }
+
+    fn decimals(&self) -> u8 {
+        DECIMALS
+    }
+
+    fn total_supply(&self) -> Result<U256, Self::Error> {
+        self.check_view_unlocked()?;
+        Ok(self.total_shares.get())
+    }
+
+    fn balance_of(&self, account: Address) -> U256 {
+        self.shares.get(account)
+    }
+
+    fn allowance(&self, owner: Address, spender: Address) -> U256 {
+        self.allowances.getter(owner).get(spender)
+    }
+
+    fn transfer(&mut self, to: Address, amount: U256) -> Result<bool, Self::Error> {
+        meter!("transfer", Address, U256);
+        self.check_active()?;
+        let from = self.vm().msg_sender();
+        self.transfer_shares(from, to, amount)?;
+        Ok(true)
+    }
+
+    fn approve(&mut self, spender: Address, amount: U256) -> Result<bool, Self::Error> {
+        meter!("approve", Address, U256);
+        self.check_active()?;
+        let owner = self.vm().msg_sender();
+        self.approve_shares(owner, spender, amount);
+        Ok(true)
+    }
+
+    fn transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<bool, Self::Error> {
+        meter!("transferFrom", Address, Address, U256);
+        self.check_active()?;
+        let spender = self.vm().msg_sender();
+        self.spend_allowance(from, spender, amount)?;
+        self.transfer_shares(from, to, amount)?;
+        Ok(true)
+    }
+}
+
+impl ConstantSumCurve {
+    /// Moves `amount` LP shares from `from` to `to`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidReceiver`] - If `to` is the zero address.
+    /// * [`Error::InsufficientShares`] - If `from` holds fewer than `amount`.
+    ///
+    /// # Events
+    ///
+    /// * [`Transfer`].
+    pub(crate) fn transfer_shares(
+        &mut self,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), Error> {
+        if to.is_zero() {
+            return Err(Error::InvalidReceiver(InvalidReceiver { receiver: to }));
+        }
+        let balance = self.shares.get(from);
+        if balance < amount {
+            return Err(Error::InsufficientShares(InsufficientShares {
+                balance,
+                shares: amount,
+            }));
+        }
+        self.shares.setter(from).set(balance - amount);
+        let balance = self.shares.get(to);
+        self.shares.setter(to).set(balance + amount);
+
+        #[allow(deprecated)]
+        evm::log(Transfer {
+            from,
+            to,
+            value: amount,
+        });
+
+        Ok(())
+    }
+
+    /// Lets `spender` move `amount` LP shares of `owner`.
+    ///
+    /// # Events
+    ///
+    /// * [`Approval`].
+    pub(crate) fn approve_shares(&mut self, owner: Address, spender: Address, amount: U256) {
+        self.allowances.setter(owner).setter(spender).set(amount);
+
+        #[allow(deprecated)]
+        evm::log(Approval {
+            owner,
+            spender,
+            value: amount,
+        });
+    }
+
+    /// Spends `amount` of the allowance of `spender` over the LP shares of
+    /// `owner`. An unlimited allowance of `U256::MAX` is left untouched.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InsufficientAllowance`] - If the allowance is smaller than
+    ///   `amount`.
+    pub(crate) fn spend_allowance(
+        &mut self,
+        owner: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<(), Error> {
+        let allowance = self.allowances.getter(owner).get(spender);
+        if allowance == U256::MAX {
+            return Ok(());
+        }
+        if allowance < amount {
+            return Err(Error::InsufficientAllowance(InsufficientAllowance {
+                allowance,
+                needed: amount,
+            }));
+        }
+        self.allowances
+            .setter(owner)
+            .setter(spender)
+            .set(allowance - amount);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::uint;
+
+    use super::*;
+    use crate::{
+        liquidity::{ILiquidity, MINIMUM_LIQUIDITY},
+        testing::{deploy, hook, CURRENCY_1, CURRENCY_2},
+    };
+
+    #[motsu::test]
+    fn lp_shares_are_erc20(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .set_currencies(CURRENCY_1, CURRENCY_2)
+            .expect("should set currencies");
+        contract.mint(CURRENCY_1, alice, uint!(1_000_U256));
+        contract.mint(CURRENCY_2, alice, uint!(1_000_U256));
+        let shares = contract
+            .sender(alice)
+            .add_liquidity(uint!(1_000_U256), uint!(1_000_U256))
+            .expect("should add liquidity");
+        contract.assert_emitted(&Transfer {
+            from: Address::ZERO,
+            to: alice,
+            value: shares,
+        });
+        assert_eq!(String::from("CSLP"), contract.sender(alice).symbol());
+        assert_eq!(18, contract.sender(alice).decimals());
+        assert_eq!(
+            shares + MINIMUM_LIQUIDITY,
+            contract
+                .sender(alice)
+                .total_supply()
+                .expect("should read total supply")
+        );
+
+        let amount = uint!(500_U256);
+        assert!(contract
+            .sender(alice)
+            .transfer(bob, amount)
+            .expect("should transfer shares"));
+        contract.assert_emitted(&Transfer {
+            from: alice,
+            to: bob,
+            value: amount,
+        });
+        assert_eq!(shares - amount, contract.sender(alice).balance_of(alice));
+        assert_eq!(amount, contract.sender(alice).balance_of(bob));
+        let err = contract
+            .sender(alice)
+            .transfer(Address::ZERO, amount)
+            .expect_err("should not transfer to the zero address");
+        assert!(matches!(err, Error::InvalidReceiver(_)));
+        let err = contract
+            .sender(bob)
+            .transfer(alice, shares)
+            .expect_err("should not exceed the balance");
+        assert!(matches!(err, Error::InsufficientShares(_)));
+
+        let err = contract
+            .sender(bob)
+            .transfer_from(alice, bob, amount)
+            .expect_err("should need an allowance");
+        assert!(matches!(
+            err,
+            Error::InsufficientAllowance(InsufficientAllowance { allowance, needed })
+                if allowance.is_zero() && needed == amount
+        ));
+        contract
+            .sender(alice)
+            .approve(bob, uint!(300_U256))
+            .expect("should approve");
+        contract.assert_emitted(&Approval {
+            owner: alice,
+            spender: bob,
+            value: uint!(300_U256),
+        });
+        contract
+            .sender(bob)
+            .transfer_from(alice, bob, uint!(200_U256))
+            .expect("should spend the allowance");
+        assert_eq!(
+            uint!(100_U256),
+            contract.sender(alice).allowance(alice, bob)
+        );
+        assert_eq!(uint!(700_U256), contract.sender(alice).balance_of(bob));
+
+        // Unlimited allowances are not spent.
+        contract
+            .sender(alice)
+            .approve(bob, U256::MAX)
+            .expect("should approve");
+        contract
+            .sender(bob)
+            .transfer_from(alice, bob, uint!(100_U256))
+            .expect("should spend the allowance");
+        assert_eq!(U256::MAX, contract.sender(alice).allowance(alice, bob));
+
+        // Transferred shares are redeemable like minted ones.
+        contract
+            .sender(bob)
+            .remove_liquidity(uint!(800_U256), U256::ZERO, U256::ZERO)
+            .expect("should remove liquidity");
+        contract.assert_emitted(&Transfer {
+            from: bob,
+            to: Address::ZERO,
+            value: uint!(800_U256),
+        });
+        assert_eq!(
+            uint!(1_200_U256),
+            contract
+                .sender(alice)
+                .total_supply()
+                .expect("should read total supply")
+        );
+        assert_eq!(uint!(400_U256), contract.balance(CURRENCY_1, bob));
+        assert_eq!(uint!(400_U256), contract.balance(CURRENCY_2, bob));
+    }
+}
@@ -6,5 +6,19 @@ pub extern "C" fn main() {}
 
 #[cfg(feature = "export-abi")]
 fn main() {
+    #[cfg(feature = "fixtures")]
+    if std::env::args().any(|arg| arg == "--fixtures") {
+        print!("{}", stylus_uniswap_workshop::fixtures::to_json());
+        return;
+    }
+    #[cfg(feature = "storage-layout")]
+    if std::env::args().any(|arg| arg == "--storage-layout") {
+        print!("{}", stylus_uniswap_workshop::layout::to_json());
+        return;
+    }
+    if std::env::args().any(|arg| arg == "--harness") {
+        stylus_uniswap_workshop::harness::print_harness();
+        return;
+    }
     stylus_uniswap_workshop::print_from_args();
 }
@@ -0,0 +1,167 @@
+//! Full-precision integer math helpers.
+//!
+//! Fixed-point helpers work on 18-decimal numbers, where [`WAD`] is `1`.
+use alloy_primitives::{uint, I256, U256, U512};
+
+/// The fixed-point `1`.
+pub const WAD: U256 = uint!(1_000_000_000_000_000_000_U256);
+
+/// Bound of the relative error of [`pow_wad`], in [`WAD`] units.
+///
+/// The error measured against exact powers stays below `3e-16`, with a
+/// further absolute error of one unit.
+pub const MAX_POW_RELATIVE_ERROR: U256 = uint!(10_000_U256);
+
+/// `ln(2)` as a fixed-point number.
+const LN_2: U256 = uint!(693_147_180_559_945_309_U256);
+
+/// Number of fractional bits computed by [`log2_wad`].
+const LOG2_ITERATIONS: usize = 64;
+
+/// Returns `a * b / denominator` rounded down, computed without overflowing
+/// the intermediate product, or `None` if the result does not fit into
+/// `uint256`.
+///
+/// # Panics
+///
+/// * If `denominator` is zero.
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    let (quotient, _) = a
+        .widening_mul::<256, 4, 512, 8>(b)
+        .div_rem(U512::from(denominator));
+    narrow(quotient)
+}
+
+/// Returns `a * b / denominator` rounded up, computed without overflowing
+/// the intermediate product, or `None` if the result does not fit into
+/// `uint256`.
+///
+/// # Panics
+///
+/// * If `denominator` is zero.
+pub fn mul_div_up(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    let (quotient, remainder) = a
+        .widening_mul::<256, 4, 512, 8>(b)
+        .div_rem(U512::from(denominator));
+    if remainder.is_zero() {
+        narrow(quotient)
+    } else {
+        narrow(quotient + U512::from(1))
+    }
+}
+
+/// Returns the square root of `x`, rounded down.
+pub fn sqrt(x: U256) -> U256 {
+    if x.is_zero() {
+        return U256::ZERO;
+    }
+    // Start above the root, so that Newton's iterations decrease towards
+    // its floor.
+    let mut z = U256::from(1) << x.bit_len().div_ceil(2);
+    loop {
+        let y = (z + x / z) >> 1;
+        if y >= z {
+            return z;
+        }
+        z = y;
+    }
+}
+
+/// Returns the binary logarithm of the fixed-point number `x`, or `None` if
+/// `x` is zero.
+pub fn log2_wad(x: U256) -> Option<I256> {
+    if x.is_zero() {
+        return None;
+    }
+    // Normalize `x` into `[1, 2)`, keeping the exponent as the integer part.
+    let mut y = x;
+    let integer = if y >= WAD {
+        let shift = (y / WAD).bit_len() - 1;
+        y >>= shift;
+        I256::try_from(shift).ok()?
+    } else {
+        let mut shift = WAD.bit_len() - y.bit_len();
+        y <<= shift;
+        if y < WAD {
+            y <<= 1;
+            shift += 1;
+        }
+        -I256::try_from(shift).ok()?
+    };
+    // Each squaring doubles the logarithm, so crossing `2` yields the next
+    // bit of the fraction.
+    let mut fraction = U256::ZERO;
+    let mut delta = WAD >> 1;
+    for _ in 0..LOG2_ITERATIONS {
+        y = y * y / WAD;
+        if y >= WAD << 1 {
+            fraction += delta;
+            y >>= 1;
+        }
+        delta >>= 1;
+    }
+    integer
+        .checked_mul(I256::try_from(WAD).ok()?)?
+        .checked_add(I256::try_from(fraction).ok()?)
+}
+
+/// Returns `2^x` for the fixed-point number `x`, rounded down, or `None` if
+/// the result does not fit into `uint256`.
+pub fn exp2_wad(x: I256) -> Option<U256> {
+    let wad = I256::try_from(WAD).ok()?;
+    let integer = x.div_euclid(wad);
+    let fraction = x.rem_euclid(wad).into_raw();
+    // `2^fraction = e^(fraction * ln(2))`, summed as a Taylor series that
+    // converges within a few dozen terms for exponents below `ln(2)`.
+    let z = fraction * LN_2 / WAD;
+    let mut sum = WAD;
+    let mut term = WAD;
+    let mut i = U256::from(1);
+    while !term.is_zero() {
+        term = term * z / WAD / i;
+        sum += term;
+        i += U256::from(1);
+    }
+    if integer.is_negative() {
+        let shift = integer.unsigned_abs();
+        if shift >= U256::from(U256::BITS) {
+            return Some(U256::ZERO);
+        }
+        Some(sum >> shift.to::<usize>())
+    } else {
+        let shift = integer.into_raw();
+        if shift + U256::from(sum.bit_len()) > U256::from(U256::BITS) {
+            return None;
+        }
+        Some(sum << shift.to::<usize>())
+    }
+}
+
+/// Returns `base^exponent` for fixed-point numbers, within
+/// [`MAX_POW_RELATIVE_ERROR`] of the exact power, or `None` if the result
+/// does not fit into `uint256`.
+pub fn pow_wad(base: U256, exponent: U256) -> Option<U256> {
+    if exponent.is_zero() {
+        return Some(WAD);
+    }
+    if base.is_zero() {
+        return Some(U256::ZERO);
+    }
+    let log = log2_wad(base)?;
+    let product = log.checked_mul(I256::try_from(exponent).ok()?)?;
+    exp2_wad(product / I256::try_from(WAD).ok()?)
+}
+
+/// Returns [`pow_wad`] rounded up by its maximum error, so that it is never
+/// below the exact power.
+pub fn pow_wad_up(base: U256, exponent: U256) -> Option<U256> {
+    let power = pow_wad(base, exponent)?;
+    power
+        .checked_add(mul_div_up(power, MAX_POW_RELATIVE_ERROR, WAD)?)?
+        .checked_add(U256::from(1))
+}
+
+/// Returns `x` as a `uint256`, or `None` if it does not fit.
+fn narrow(x: U512) -> Option<U256> {
+    (x.bit_len() <= 256).then(|| x.to::<U256>())
+}
@@ -0,0 +1,312 @@
+//! Mock contracts called by the curve in tests: ERC-20 tokens and the
+//! Uniswap V4 pool manager, deployed at the fixed addresses of
+//! [`crate::testing`].
+//!
+//! The pool manager keeps v4's delta accounting per account and currency:
+//! `take` and `mint` debit the caller, `settle` and `burn` credit it, and
+//! `unlock` reverts unless the caller cleared all of its deltas, like v4's
+//! `CurrencyNotSettled`. Deltas returned by hooks are accounted by tests
+//! with [`MockPoolManager::account`], as the pool manager would after each
+//! hook call.
+use alloc::vec::Vec;
+
+use alloy_primitives::{Address, B256, I256, U256};
+use stylus_sdk::{
+    abi::Bytes,
+    call::Call,
+    prelude::*,
+    storage::{StorageAddress, StorageMap, StorageSigned, StorageU256},
+};
+
+use crate::{currency, hooks, ModifyLiquidityParams, PoolKey};
+
+sol_interface! {
+    /// ERC-20 balances read by the pool manager.
+    interface IERC20Balance {
+        function balanceOf(address account) external view returns (uint256);
+        function transfer(address to, uint256 amount) external returns (bool);
+    }
+
+    /// Callback of the pool manager's `unlock`.
+    interface IUnlockCallback {
+        function unlockCallback(bytes data) external returns (bytes);
+    }
+}
+
+/// ERC-20 token with unchecked allowances and a public `mint`.
+#[storage]
+pub struct MockErc20 {
+    balances: StorageMap<Address, StorageU256>,
+    total_supply: StorageU256,
+}
+
+// SAFETY: The mock is only instantiated as top-level storage in tests.
+unsafe impl TopLevelStorage for MockErc20 {}
+
+#[public]
+impl MockErc20 {
+    /// Returns the balance of `account`.
+    pub fn balance_of(&self, account: Address) -> U256 {
+        self.balances.get(account)
+    }
+
+    /// Returns the amount of minted tokens, saturating at `uint256` max.
+    pub fn total_supply(&self) -> U256 {
+        self.total_supply.get()
+    }
+
+    /// Mints `amount` to `to`.
+    pub fn mint(&mut self, to: Address, amount: U256) {
+        let balance = self.balances.get(to);
+        self.balances.setter(to).set(balance + amount);
+        let total_supply = self.total_supply.get();
+        self.total_supply.set(total_supply.saturating_add(amount));
+    }
+
+    /// Moves `amount` from the caller to `to`.
+    pub fn transfer(&mut self, to: Address, amount: U256) -> Result<bool, Vec<u8>> {
+        let from = self.vm().msg_sender();
+        self.move_balance(from, to, amount)?;
+        Ok(true)
+    }
+
+    /// Moves `amount` from `from` to `to`, without checking allowances.
+    pub fn transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<bool, Vec<u8>> {
+        self.move_balance(from, to, amount)?;
+        Ok(true)
+    }
+}
+
+impl MockErc20 {
+    /// Moves `amount` from `from` to `to`, reverting if `from` holds less.
+    fn move_balance(&mut self, from: Address, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        let balance = self.balances.get(from);
+        let remaining = balance
+            .checked_sub(amount)
+            .ok_or_else(|| b"insufficient balance".to_vec())?;
+        self.balances.setter(from).set(remaining);
+        let balance = self.balances.get(to);
+        self.balances.setter(to).set(balance + amount);
+        Ok(())
+    }
+}
+
+/// Uniswap V4 pool manager accounting the deltas of its callers.
+#[storage]
+pub struct MockPoolManager {
+    deltas: StorageMap<Address, StorageMap<Address, StorageSigned<256, 4>>>,
+    nonzero_deltas: StorageU256,
+    claims: StorageMap<Address, StorageMap<U256, StorageU256>>,
+    synced_currency: StorageAddress,
+    synced_reserve: StorageU256,
+    liquidity_delta: StorageSigned<256, 4>,
+}
+
+// SAFETY: The mock is only instantiated as top-level storage in tests.
+unsafe impl TopLevelStorage for MockPoolManager {}
+
+#[public]
+impl MockPoolManager {
+    /// Calls back the caller's `unlockCallback` with `data`, and reverts
+    /// unless all deltas are cleared afterwards.
+    pub fn unlock(&mut self, data: Bytes) -> Result<Bytes, Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let call = Call::new_mutating(self);
+        let result = IUnlockCallback::new(caller)
+            .unlock_callback(self.vm(), call, data)
+            .map_err(Vec::<u8>::from)?;
+        if !self.nonzero_deltas.get().is_zero() {
+            return Err(b"CurrencyNotSettled".to_vec());
+        }
+        Ok(result)
+    }
+
+    /// Sends `amount` of `currency` to `to`, debiting the caller.
+    pub fn take(&mut self, currency: Address, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        self.account_delta(caller, currency, -signed(amount))?;
+        if currency::is_native(currency) {
+            return self.vm().transfer_eth(to, amount);
+        }
+        let call = Call::new_mutating(self);
+        IERC20Balance::new(currency)
+            .transfer(self.vm(), call, to, amount)
+            .map_err(Vec::<u8>::from)?;
+        Ok(())
+    }
+
+    /// Records the balance of `currency` to be paid by the next `settle`.
+    pub fn sync(&mut self, currency: Address) -> Result<(), Vec<u8>> {
+        let reserve = self.balance_of(currency)?;
+        self.synced_currency.set(currency);
+        self.synced_reserve.set(reserve);
+        Ok(())
+    }
+
+    /// Credits the caller with the tokens received since `sync`, or the
+    /// value of the call for native ETH.
+    #[payable]
+    pub fn settle(&mut self) -> Result<U256, Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let currency = self.synced_currency.get();
+        let paid = if currency::is_native(currency) {
+            self.vm().msg_value()
+        } else {
+            self.balance_of(currency)? - self.synced_reserve.get()
+        };
+        self.synced_currency.set(Address::ZERO);
+        self.synced_reserve.set(U256::ZERO);
+        self.account_delta(caller, currency, signed(paid))?;
+        Ok(paid)
+    }
+
+    /// Mints `amount` of the claims `id` to `to`, debiting the caller.
+    pub fn mint(&mut self, to: Address, id: U256, amount: U256) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        self.account_delta(caller, claim_currency(id), -signed(amount))?;
+        let balance = self.claims.getter(to).get(id);
+        self.claims.setter(to).setter(id).set(balance + amount);
+        Ok(())
+    }
+
+    /// Burns `amount` of the claims `id` of `from`, crediting the caller.
+    pub fn burn(&mut self, from: Address, id: U256, amount: U256) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let balance = self.claims.getter(from).get(id);
+        let remaining = balance
+            .checked_sub(amount)
+            .ok_or_else(|| b"insufficient claims".to_vec())?;
+        self.claims.setter(from).setter(id).set(remaining);
+        self.account_delta(caller, claim_currency(id), signed(amount))
+    }
+
+    /// Returns the delta set by [`Self::set_liquidity_delta`] as the
+    /// caller's delta, and accounts it.
+    pub fn modify_liquidity(
+        &mut self,
+        key: PoolKey,
+        _params: ModifyLiquidityParams,
+        _hook_data: Bytes,
+    ) -> Result<(I256, I256), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let delta = self.liquidity_delta.get();
+        self.account_balance_delta(caller, &key, delta)?;
+        Ok((delta, I256::ZERO))
+    }
+
+    /// Debits the caller with `amount0` and `amount1`.
+    pub fn donate(
+        &mut self,
+        key: PoolKey,
+        amount0: U256,
+        amount1: U256,
+        _hook_data: Bytes,
+    ) -> Result<I256, Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        let delta = hooks::to_balance_delta(
+            -i128::try_from(amount0).map_err(|_| b"overflow".to_vec())?,
+            -i128::try_from(amount1).map_err(|_| b"overflow".to_vec())?,
+        );
+        self.account_balance_delta(caller, &key, delta)?;
+        Ok(delta)
+    }
+
+    /// Sets the delta returned by `modifyLiquidity`.
+    pub fn set_liquidity_delta(&mut self, delta: I256) {
+        self.liquidity_delta.set(delta);
+    }
+
+    /// Accounts `delta` of `currency` to `account`, as the pool manager
+    /// does with the deltas returned by hooks.
+    pub fn account(
+        &mut self,
+        account: Address,
+        currency: Address,
+        delta: I256,
+    ) -> Result<(), Vec<u8>> {
+        self.account_delta(account, currency, delta)
+    }
+
+    /// Returns the delta of `currency` owed to `account`, or owed by it if
+    /// negative.
+    pub fn currency_delta(&self, account: Address, currency: Address) -> I256 {
+        self.deltas.getter(account).get(currency)
+    }
+
+    /// Returns the claims `id` of `owner`.
+    pub fn claim_balance(&self, owner: Address, id: U256) -> U256 {
+        self.claims.getter(owner).get(id)
+    }
+}
+
+impl MockPoolManager {
+    /// Adds `delta` to the delta of `currency` of `account`, counting the
+    /// nonzero deltas.
+    fn account_delta(
+        &mut self,
+        account: Address,
+        currency: Address,
+        delta: I256,
+    ) -> Result<(), Vec<u8>> {
+        let before = self.deltas.getter(account).get(currency);
+        let after = before
+            .checked_add(delta)
+            .ok_or_else(|| b"delta overflow".to_vec())?;
+        self.deltas.setter(account).setter(currency).set(after);
+        let count = self.nonzero_deltas.get();
+        if before.is_zero() && !after.is_zero() {
+            self.nonzero_deltas.set(count + U256::ONE);
+        } else if !before.is_zero() && after.is_zero() {
+            self.nonzero_deltas.set(count - U256::ONE);
+        }
+        Ok(())
+    }
+
+    /// Accounts the `BalanceDelta` `delta` of the pool `key` to `account`.
+    fn account_balance_delta(
+        &mut self,
+        account: Address,
+        key: &PoolKey,
+        delta: I256,
+    ) -> Result<(), Vec<u8>> {
+        let (amount0, amount1) = hooks::balance_delta_amounts(delta);
+        self.account_delta(
+            account,
+            key.currency0,
+            I256::try_from(amount0).expect("should fit"),
+        )?;
+        self.account_delta(
+            account,
+            key.currency1,
+            I256::try_from(amount1).expect("should fit"),
+        )
+    }
+
+    /// Returns the pool manager's balance of the token `currency`, or zero
+    /// for native ETH.
+    fn balance_of(&mut self, currency: Address) -> Result<U256, Vec<u8>> {
+        if currency::is_native(currency) {
+            return Ok(U256::ZERO);
+        }
+        let manager = self.vm().contract_address();
+        let call = Call::new_mutating(self);
+        IERC20Balance::new(currency)
+            .balance_of(self.vm(), call, manager)
+            .map_err(Vec::<u8>::from)
+    }
+}
+
+/// Returns `amount` as a signed delta.
+fn signed(amount: U256) -> I256 {
+    I256::try_from(amount).expect("amount should fit into int256")
+}
+
+/// Returns the currency of the claims `id`.
+fn claim_currency(id: U256) -> Address {
+    Address::from_word(B256::from(id))
+}
@@ -0,0 +1,639 @@
+//! Threshold multisig for critical actions.
+//!
+//! Once the owner configures a set of signers and a threshold, critical
+//! actions (see [`CriticalAction`]) can no longer be executed by the owner
+//! alone: a signer proposes the action, other signers approve it, and it can
+//! be executed once the threshold of approvals is met.
+use alloc::vec::Vec;
+
+use alloy_primitives::{aliases::U8, Address, B256, U256};
+use stylus_sdk::{
+    evm,
+    prelude::*,
+    storage::{StorageAddress, StorageBool, StorageMap, StorageU256, StorageU8},
+};
+
+use crate::{
+    changelog::ChangeKind, ConstantSumCurve, CriticalActionApproved, CriticalActionExecuted,
+    CriticalActionProposed, Error, InvalidCriticalAction, InvalidSigner, InvalidThreshold,
+    MultisigAlreadyConfigured, NotSigner, ProposalAlreadyApproved, ProposalAlreadyExecuted,
+    SignerSet, ThresholdNotMet, ThresholdSet, UnknownProposal,
+};
+
+/// Critical action that requires a threshold of signer approvals once the
+/// multisig is configured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CriticalAction {
+    /// Transfers the ownership to `subject`.
+    TransferOwnership = 0,
+    /// Adds `subject` to the signers if `value` is non-zero, or removes it
+    /// otherwise.
+    SetSigner = 1,
+    /// Sets the threshold to `value`.
+    SetThreshold = 2,
+}
+
+impl TryFrom<u8> for CriticalAction {
+    type Error = Error;
+
+    fn try_from(action: u8) -> Result<Self, Self::Error> {
+        match action {
+            0 => Ok(Self::TransferOwnership),
+            1 => Ok(Self::SetSigner),
+            2 => Ok(Self::SetThreshold),
+            _ => Err(Error::InvalidCriticalAction(InvalidCriticalAction {
+                action,
+            })),
+        }
+    }
+}
+
+/// A proposal: its [`CriticalAction`], subject, value, number of approvals
+/// and whether it was executed.
+pub type ProposalView = (u8, Address, U256, u8, bool);
+
+/// State of a proposed critical action.
+#[storage]
+pub struct Proposal {
+    action: StorageU8,
+    subject: StorageAddress,
+    value: StorageU256,
+    approvals: StorageU8,
+    executed: StorageBool,
+    approved: StorageMap<Address, StorageBool>,
+}
+
+/// Interface of the threshold multisig for critical actions.
+pub trait IMultisig {
+    /// The error type associated to the trait implementation.
+    type Error: Into<alloc::vec::Vec<u8>>;
+
+    /// Returns true if `account` is a signer of the multisig.
+    fn is_signer(&self, account: Address) -> bool;
+
+    /// Returns the number of signers of the multisig.
+    fn signer_count(&self) -> u8;
+
+    /// Returns the number of approvals needed to execute a proposal, or zero
+    /// if the multisig is not configured.
+    fn threshold(&self) -> u8;
+
+    /// Configures the multisig guarding critical actions, which the owner
+    /// can no longer execute alone afterwards. Later changes to the signers
+    /// and threshold go through proposals.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `signers` - The distinct, non-zero signers.
+    /// * `threshold` - The number of approvals needed to execute a proposal.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::MultisigAlreadyConfigured`] - If the multisig is already
+    ///   configured.
+    /// * [`Error::InvalidSigner`] - If a signer is zero or duplicated.
+    /// * [`Error::InvalidThreshold`] - If `threshold` is zero or exceeds the
+    ///   number of signers.
+    ///
+    /// # Events
+    ///
+    /// * [`SignerSet`] for each signer.
+    /// * [`ThresholdSet`].
+    fn configure_multisig(
+        &mut self,
+        signers: Vec<Address>,
+        threshold: u8,
+    ) -> Result<(), Self::Error>;
+
+    /// Returns the number of proposals, i.e. the id of the next proposal.
+    fn proposal_count(&self) -> U256;
+
+    /// Returns the proposal with `id` as an
+    /// `(action, subject, value, approvals, executed)` tuple.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `id` - The id of the proposal.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::UnknownProposal`] - If no proposal with `id` exists.
+    fn proposal(&self, id: U256) -> Result<ProposalView, Self::Error>;
+
+    /// Proposes a [`CriticalAction`], approved by the caller, and
+    /// returns the id of the proposal.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `action` - The `u8` discriminant of the
+    ///   [`CriticalAction`].
+    /// * `subject` - The affected address, or zero if none.
+    /// * `value` - The new value.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::NotSigner`] - If the caller is not a signer.
+    /// * [`Error::InvalidCriticalAction`] - If `action` is not a valid
+    ///   [`CriticalAction`].
+    ///
+    /// # Events
+    ///
+    /// * [`CriticalActionProposed`].
+    /// * [`CriticalActionApproved`].
+    fn propose_critical_action(
+        &mut self,
+        action: u8,
+        subject: Address,
+        value: U256,
+    ) -> Result<U256, Self::Error>;
+
+    /// Approves the proposal with `id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `id` - The id of the proposal.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::NotSigner`] - If the caller is not a signer.
+    /// * [`Error::UnknownProposal`] - If no proposal with `id` exists.
+    /// * [`Error::ProposalAlreadyExecuted`] - If the proposal was executed.
+    /// * [`Error::ProposalAlreadyApproved`] - If the caller already approved
+    ///   the proposal.
+    ///
+    /// # Events
+    ///
+    /// * [`CriticalActionApproved`].
+    fn approve_critical_action(&mut self, id: U256) -> Result<(), Self::Error>;
+
+    /// Executes the proposal with `id` once it has enough approvals.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `id` - The id of the proposal.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::NotSigner`] - If the caller is not a signer.
+    /// * [`Error::UnknownProposal`] - If no proposal with `id` exists.
+    /// * [`Error::ProposalAlreadyExecuted`] - If the proposal was executed.
+    /// * [`Error::ThresholdNotMet`] - If the proposal has fewer approvals
+    ///   than the threshold.
+    /// * Any error of the [`CriticalAction`].
+    ///
+    /// # Events
+    ///
+    /// * [`CriticalActionExecuted`].
+    /// * The event of the [`CriticalAction`].
+    fn execute_critical_action(&mut self, id: U256) -> Result<(), Self::Error>;
+}
+
+#[public]
+impl IMultisig for ConstantSumCurve {
+    type Error = Error;
+
+    fn is_signer(&self, account: Address) -> bool {
+        self.signers.get(account)
+    }
+
+    fn signer_count(&self) -> u8 {
+        self.signer_count.get().to::<u8>()
+    }
+
+    fn threshold(&self) -> u8 {
+        self.threshold.get().to::<u8>()
+    }
+
+    fn configure_multisig(
+        &mut self,
+        signers: Vec<Address>,
+        threshold: u8,
+    ) -> Result<(), Self::Error> {
+        meter!("configureMultisig", Vec<Address>, u8);
+        self.check_active()?;
+        self.only_owner()?;
+        if self.multisig_enabled() {
+            return Err(Error::MultisigAlreadyConfigured(
+                MultisigAlreadyConfigured {},
+            ));
+        }
+        for signer in signers {
+            self.update_signer(signer, true)?;
+        }
+        self.update_threshold(threshold)
+    }
+
+    fn proposal_count(&self) -> U256 {
+        self.proposal_count.get()
+    }
+
+    fn proposal(&self, id: U256) -> Result<ProposalView, Self::Error> {
+        self.proposal_view(id)
+    }
+
+    fn propose_critical_action(
+        &mut self,
+        action: u8,
+        subject: Address,
+        value: U256,
+    ) -> Result<U256, Self::Error> {
+        meter!("proposeCriticalAction", u8, Address, U256);
+        self.check_active()?;
+        let proposer = self.only_signer()?;
+        self.propose(proposer, action, subject, value)
+    }
+
+    fn approve_critical_action(&mut self, id: U256) -> Result<(), Self::Error> {
+        meter!("approveCriticalAction", U256);
+        self.check_active()?;
+        let signer = self.only_signer()?;
+        self.approve_proposal(id, signer)
+    }
+
+    fn execute_critical_action(&mut self, id: U256) -> Result<(), Self::Error> {
+        meter!("executeCriticalAction", U256);
+        self.check_active()?;
+        self.only_signer()?;
+        self.execute(id)
+    }
+}
+
+impl ConstantSumCurve {
+    /// Returns true if the multisig is configured.
+    pub(crate) fn multisig_enabled(&self) -> bool {
+        !self.threshold.get().is_zero()
+    }
+
+    /// Checks that the caller is a signer and returns it.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::NotSigner`] - If the caller is not a signer.
+    pub(crate) fn only_signer(&self) -> Result<Address, Error> {
+        let account = self.vm().msg_sender();
+        if !self.signers.get(account) {
+            return Err(Error::NotSigner(NotSigner { account }));
+        }
+        Ok(account)
+    }
+
+    /// Returns the proposal with `id`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::UnknownProposal`] - If no proposal with `id` exists.
+    pub(crate) fn proposal_view(&self, id: U256) -> Result<ProposalView, Error> {
+        self.check_proposal(id)?;
+        let proposal = self.proposals.getter(id);
+        Ok((
+            proposal.action.get().to::<u8>(),
+            proposal.subject.get(),
+            proposal.value.get(),
+            proposal.approvals.get().to::<u8>(),
+            proposal.executed.get(),
+        ))
+    }
+
+    /// Stores a new proposal approved by `proposer` and returns its id.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidCriticalAction`] - If `action` is not a valid
+    ///   [`CriticalAction`].
+    ///
+    /// # Events
+    ///
+    /// * [`CriticalActionProposed`].
+    /// * [`CriticalActionApproved`].
+    pub(crate) fn propose(
+        &mut self,
+        proposer: Address,
+        action: u8,
+        subject: Address,
+        value: U256,
+    ) -> Result<U256, Error> {
+        CriticalAction::try_from(action)?;
+        let id = self.proposal_count.get();
+        self.proposal_count.set(id + U256::from(1));
+        let mut proposal = self.proposals.setter(id);
+        proposal.action.set(U8::from(action));
+        proposal.subject.set(subject);
+        proposal.value.set(value);
+
+        #[allow(deprecated)]
+        evm::log(CriticalActionProposed {
+            id,
+            proposer,
+            action,
+            subject,
+            value,
+        });
+
+        self.approve_proposal(id, proposer)?;
+        Ok(id)
+    }
+
+    /// Records the approval of the proposal with `id` by `signer`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::UnknownProposal`] - If no proposal with `id` exists.
+    /// * [`Error::ProposalAlreadyExecuted`] - If the proposal was executed.
+    /// * [`Error::ProposalAlreadyApproved`] - If `signer` already approved
+    ///   the proposal.
+    ///
+    /// # Events
+    ///
+    /// * [`CriticalActionApproved`].
+    pub(crate) fn approve_proposal(&mut self, id: U256, signer: Address) -> Result<(), Error> {
+        self.check_pending(id)?;
+        let mut proposal = self.proposals.setter(id);
+        if proposal.approved.get(signer) {
+            return Err(Error::ProposalAlreadyApproved(ProposalAlreadyApproved {
+                id,
+                signer,
+            }));
+        }
+        proposal.approved.setter(signer).set(true);
+        let approvals = proposal.approvals.get().to::<u8>() + 1;
+        proposal.approvals.set(U8::from(approvals));
+
+        #[allow(deprecated)]
+        evm::log(CriticalActionApproved {
+            id,
+            signer,
+            approvals,
+        });
+
+        Ok(())
+    }
+
+    /// Executes the proposal with `id` if it has enough approvals.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::UnknownProposal`] - If no proposal with `id` exists.
+    /// * [`Error::ProposalAlreadyExecuted`] - If the proposal was executed.
+    /// * [`Error::ThresholdNotMet`] - If the proposal has fewer approvals
+    ///   than the threshold.
+    /// * Any error of the [`CriticalAction`].
+    ///
+    /// # Events
+    ///
+    /// * [`CriticalActionExecuted`].
+    /// * The event of the [`CriticalAction`].
+    pub(crate) fn execute(&mut self, id: U256) -> Result<(), Error> {
+        self.check_pending(id)?;
+        let threshold = self.threshold.get().to::<u8>();
+        let mut proposal = self.proposals.setter(id);
+        let approvals = proposal.approvals.get().to::<u8>();
+        if approvals < threshold {
+            return Err(Error::ThresholdNotMet(ThresholdNotMet {
+                id,
+                approvals,
+                threshold,
+            }));
+        }
+        proposal.executed.set(true);
+        let action = proposal.action.get().to::<u8>();
+        let subject = proposal.subject.get();
+        let value = proposal.value.get();
+
+        #[allow(deprecated)]
+        evm::log(CriticalActionExecuted { id });
+
+        match CriticalAction::try_from(action)? {
+            CriticalAction::TransferOwnership => self.update_owner(subject),
+            CriticalAction::SetSigner => {
+                self.update_signer(subject, !value.is_zero())?;
+                self.check_threshold(threshold)?;
+            }
+            CriticalAction::SetThreshold => {
+                self.update_threshold(value.saturating_to::<u8>())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds `signer` to or removes it from the signers.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidSigner`] - If `signer` is zero, or is already (or
+    ///   is not) a signer.
+    ///
+    /// # Events
+    ///
+    /// * [`SignerSet`].
+    pub(crate) fn update_signer(&mut self, signer: Address, enabled: bool) -> Result<(), Error> {
+        if signer.is_zero() || self.signers.get(signer) == enabled {
+            return Err(Error::InvalidSigner(InvalidSigner { signer }));
+        }
+        self.signers.setter(signer).set(enabled);
+        let signer_count = self.signer_count.get();
+        let signer_count = if enabled {
+            signer_count + U8::from(1)
+        } else {
+            signer_count - U8::from(1)
+        };
+        self.signer_count.set(signer_count);
+        let op_seq = self.record_change(
+            ChangeKind::Signer,
+            signer.into_word(),
+            U256::from(u8::from(enabled)),
+        );
+
+        #[allow(deprecated)]
+        evm::log(SignerSet {
+            op_seq,
+            signer,
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the number of approvals needed to execute a proposal.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidThreshold`] - If `threshold` is zero or exceeds the
+    ///   number of signers.
+    ///
+    /// # Events
+    ///
+    /// * [`ThresholdSet`].
+    pub(crate) fn update_threshold(&mut self, threshold: u8) -> Result<(), Error> {
+        self.check_threshold(threshold)?;
+        self.threshold.set(U8::from(threshold));
+        let op_seq = self.record_change(ChangeKind::Threshold, B256::ZERO, U256::from(threshold));
+
+        #[allow(deprecated)]
+        evm::log(ThresholdSet { op_seq, threshold });
+
+        Ok(())
+    }
+
+    /// Checks that `threshold` is between one and the number of signers.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidThreshold`] - If `threshold` is zero or exceeds the
+    ///   number of signers.
+    fn check_threshold(&self, threshold: u8) -> Result<(), Error> {
+        let signers = self.signer_count.get().to::<u8>();
+        if threshold == 0 || threshold > signers {
+            return Err(Error::InvalidThreshold(InvalidThreshold {
+                threshold,
+                signers,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Checks that a proposal with `id` exists.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::UnknownProposal`] - If no proposal with `id` exists.
+    fn check_proposal(&self, id: U256) -> Result<(), Error> {
+        if id >= self.proposal_count.get() {
+            return Err(Error::UnknownProposal(UnknownProposal { id }));
+        }
+        Ok(())
+    }
+
+    /// Checks that a proposal with `id` exists and was not executed.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::UnknownProposal`] - If no proposal with `id` exists.
+    /// * [`Error::ProposalAlreadyExecuted`] - If the proposal was executed.
+    fn check_pending(&self, id: U256) -> Result<(), Error> {
+        self.check_proposal(id)?;
+        if self.proposals.getter(id).executed.get() {
+            return Err(Error::ProposalAlreadyExecuted(ProposalAlreadyExecuted {
+                id,
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        admin::IAdmin,
+        testing::{deploy, hook},
+        MultisigRequired,
+    };
+
+    #[motsu::test]
+    fn multisig_transfers_ownership_at_threshold(
+        alice: Address,
+        bob: Address,
+        charlie: Address,
+        dave: Address,
+    ) {
+        let contract = hook();
+        deploy(&contract, alice);
+        contract
+            .sender(alice)
+            .configure_multisig(vec![bob, charlie, dave], 2)
+            .expect("should configure multisig");
+        assert_eq!(3, contract.sender(alice).signer_count());
+        assert_eq!(2, contract.sender(alice).threshold());
+
+        let err = contract
+            .sender(alice)
+            .transfer_ownership(bob)
+            .expect_err("should require the multisig");
+        assert!(matches!(err, Error::MultisigRequired(_)));
+
+        let id = contract
+            .sender(bob)
+            .propose_critical_action(CriticalAction::TransferOwnership as u8, charlie, U256::ZERO)
+            .expect("should propose");
+        let err = contract
+            .sender(bob)
+            .execute_critical_action(id)
+            .expect_err("should not execute below threshold");
+        assert!(matches!(
+            err,
+            Error::ThresholdNotMet(ThresholdNotMet {
+                approvals: 1,
+                threshold: 2,
+                ..
+            })
+        ));
+        let err = contract
+            .sender(bob)
+            .approve_critical_action(id)
+            .expect_err("should not approve twice");
+        assert!(matches!(err, Error::ProposalAlreadyApproved(_)));
+
+        contract
+            .sender(dave)
+            .approve_critical_action(id)
+            .expect("should approve");
+        contract
+            .sender(dave)
+            .execute_critical_action(id)
+            .expect("should execute");
+        assert_eq!(charlie, contract.sender(alice).owner());
+        assert_eq!(
+            (0, charlie, U256::ZERO, 2, true),
+            contract.sender(alice).proposal(id).expect("should exist")
+        );
+
+        let err = contract
+            .sender(dave)
+            .execute_critical_action(id)
+            .expect_err("should not execute twice");
+        assert!(matches!(err, Error::ProposalAlreadyExecuted(_)));
+    }
+
+    #[motsu::test]
+    fn multisig_rejects_invalid_configuration(alice: Address, bob: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+
+        let err = contract
+            .sender(bob)
+            .propose_critical_action(0, bob, U256::ZERO)
+            .expect_err("should only allow signers");
+        assert!(matches!(err, Error::NotSigner(_)));
+        let err = contract
+            .sender(alice)
+            .configure_multisig(vec![bob], 2)
+            .expect_err("should not exceed the number of signers");
+        assert!(matches!(
+            err,
+            Error::InvalidThreshold(InvalidThreshold {
+                threshold: 2,
+                signers: 1
+            })
+        ));
+        let err = contract
+            .sender(alice)
+            .configure_multisig(vec![bob, bob], 1)
+            .expect_err("should reject duplicated signers");
+        assert!(matches!(err, Error::InvalidSigner(_)));
+    }
+}
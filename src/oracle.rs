@@ -0,0 +1,869 @@
+//! Push oracle fed with prices signed off-chain by registered reporters.
+//!
+//! Reporters sign an EIP-712 `PriceReport` (see [`PRICE_REPORT_TYPEHASH`])
+//! that anyone can relay, alone or in batches. Each reporter's reports must
+//! use its next nonce, be newer than its previous report and not be older
+//! than the maximum price age.
+//!
+//! Every accepted report (or batch) updates the aggregate price: the median of the
+//! fresh reports, after dropping those deviating from it by more than the
+//! maximum deviation (if set). The aggregate is as old as the oldest report
+//! it is computed from, and stays usable for the maximum price age.
+//!
+//! Pools pegged to the oracle bound the age of the aggregate with their own
+//! maximum price age instead, and reject older prices with `PriceTooOld`.
+use alloc::vec::Vec;
+
+use alloy_primitives::{
+    aliases::{U16, U64},
+    b256, keccak256, Address, B256, U256,
+};
+use alloy_sol_types::SolValue;
+use stylus_sdk::{
+    abi::Bytes,
+    evm,
+    prelude::*,
+    storage::{StorageU256, StorageU64},
+};
+
+use crate::{
+    bounds, changelog::ChangeKind, math, ConstantSumCurve, Error, InvalidPriceTimestamp,
+    InvalidReportNonce, MaxDeviationSet, MaxPriceAgeSet, PoolMaxPriceAgeSet, PriceAggregated,
+    PricePosted, PriceTooOld, ReporterSet, SignedPriceReport, StalePrice, TooManyReporters,
+    Unauthorized, FEE_DENOMINATOR,
+};
+
+/// EIP-712 type hash of a price report,
+/// `keccak256("PriceReport(uint256 price,uint64 timestamp,uint256 nonce)")`.
+pub const PRICE_REPORT_TYPEHASH: B256 =
+    b256!("d12e91e8ae98312abae5823fd3533b3ccafcd5c8c0758b890267549088a33d7a");
+
+/// Maximum number of registered reporters, bounding the cost of
+/// aggregating their reports.
+pub const MAX_REPORTERS: u8 = 16;
+
+/// State of the latest report of a reporter.
+#[storage]
+pub struct Report {
+    price: StorageU256,
+    timestamp: StorageU64,
+    nonce: StorageU256,
+}
+
+/// Interface of the push oracle.
+pub trait IOracle {
+    /// The error type associated to the trait implementation.
+    type Error: Into<alloc::vec::Vec<u8>>;
+
+    /// Returns true if `reporter` can sign price reports.
+    fn is_reporter(&self, reporter: Address) -> bool;
+
+    /// Returns the registered reporters.
+    fn reporters(&self) -> Vec<Address>;
+
+    /// Registers or unregisters a price reporter, up to
+    /// [`MAX_REPORTERS`].
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `reporter` - The reporter's signing address.
+    /// * `allowed` - True to register `reporter`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::TooManyReporters`] - If the maximum number of reporters is
+    ///   already registered.
+    ///
+    /// # Events
+    ///
+    /// * [`ReporterSet`].
+    fn set_reporter(&mut self, reporter: Address, allowed: bool) -> Result<(), Self::Error>;
+
+    /// Returns the maximum age of a price, in seconds.
+    fn max_price_age(&self) -> u64;
+
+    /// Sets the maximum age of a price, beyond which reports are rejected
+    /// and the latest price is stale.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `max_price_age` - The new maximum age, in seconds.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`MaxPriceAgeSet`].
+    fn set_max_price_age(&mut self, max_price_age: u64) -> Result<(), Self::Error>;
+
+    /// Returns the maximum deviation of a report from the median of all
+    /// reports, in basis points, or zero if unlimited.
+    fn max_deviation_bps(&self) -> u16;
+
+    /// Sets the maximum deviation of a report from the median of all
+    /// reports, beyond which it is left out of the aggregate price.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `max_deviation_bps` - The new maximum deviation in basis points, or
+    ///   zero for no limit.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::ParameterOutOfBounds`] - If `max_deviation_bps` exceeds
+    ///   [`bounds::MAX_SPREAD_BPS`].
+    ///
+    /// # Events
+    ///
+    /// * [`MaxDeviationSet`].
+    fn set_max_deviation_bps(&mut self, max_deviation_bps: u16) -> Result<(), Self::Error>;
+
+    /// Returns the nonce `reporter` must sign for its next price report.
+    fn reporter_nonce(&self, reporter: Address) -> U256;
+
+    /// Posts a price report signed by a registered reporter and updates the
+    /// aggregate price. Anyone can relay the signature.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `price` - The reported price.
+    /// * `timestamp` - The time at which the price was observed.
+    /// * `nonce` - The reporter's next nonce.
+    /// * `signature` - The reporter's `r || s || v` signature of the EIP-712
+    ///   `PriceReport` message (see [`PRICE_REPORT_TYPEHASH`]).
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::InvalidPriceTimestamp`] - If `timestamp` is in the future
+    ///   or not newer than the reporter's previous report.
+    /// * [`Error::StalePrice`] - If `timestamp` is older than the maximum
+    ///   price age.
+    /// * [`Error::InvalidSignature`] - If `signature` cannot be recovered.
+    /// * [`Error::Unauthorized`] - If the signer is not a reporter.
+    /// * [`Error::InvalidReportNonce`] - If `nonce` is not the reporter's
+    ///   next nonce.
+    ///
+    /// # Events
+    ///
+    /// * [`PricePosted`].
+    /// * [`PriceAggregated`].
+    fn post_price(
+        &mut self,
+        price: U256,
+        timestamp: u64,
+        nonce: U256,
+        signature: Bytes,
+    ) -> Result<(), Self::Error>;
+
+    /// Posts a batch of price reports, possibly from different reporters,
+    /// and updates the aggregate price once, minimizing the gas of keepers.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `reports` - The signed reports, with strictly increasing timestamps.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::InvalidPriceTimestamp`] - If the timestamps do not strictly
+    ///   increase, or a report is invalid like in [`Self::post_price`].
+    /// * Any other error of [`Self::post_price`].
+    ///
+    /// # Events
+    ///
+    /// * [`PricePosted`] for each report.
+    /// * [`PriceAggregated`].
+    fn post_prices(&mut self, reports: Vec<SignedPriceReport>) -> Result<(), Self::Error>;
+
+    /// Returns the aggregate price and the time at which its oldest report
+    /// was observed.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::StalePrice`] - If no price was aggregated or the aggregate
+    ///   is older than the maximum price age.
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    fn latest_price(&self) -> Result<(U256, u64), Self::Error>;
+
+    /// Returns the aggregate price and the time at which its oldest report
+    /// was observed, if it is fresh enough for the pool `pool_id`.
+    ///
+    /// Pools pegged to the oracle bound the age of the price with their own
+    /// maximum price age, other pools with the contract-wide one.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `pool_id` - The `PoolId` of the pool.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PriceTooOld`] - If the pool is pegged to the oracle and no
+    ///   price was aggregated or the aggregate is older than its maximum
+    ///   price age.
+    /// * [`Error::StalePrice`] - If the pool is not pegged to the oracle and
+    ///   the aggregate is stale.
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    fn pool_price(&self, pool_id: B256) -> Result<(U256, u64), Self::Error>;
+
+    /// Returns the maximum age of the oracle price for swaps in the pool
+    /// `pool_id`, or zero if the pool is not pegged to the oracle.
+    fn pool_max_price_age(&self, pool_id: B256) -> u64;
+
+    /// Pegs the registered pool `pool_id` to the oracle: its swaps then
+    /// revert unless the aggregate price is at most `max_price_age` seconds
+    /// old. A zero `max_price_age` unpegs the pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `pool_id` - The `PoolId` of the pool.
+    /// * `max_price_age` - The maximum age of the price in seconds.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is neither the fee manager
+    ///   nor the owner.
+    /// * [`Error::PoolNotRegistered`] - If the pool is not registered.
+    ///
+    /// # Events
+    ///
+    /// * [`PoolMaxPriceAgeSet`].
+    fn set_pool_max_price_age(
+        &mut self,
+        pool_id: B256,
+        max_price_age: u64,
+    ) -> Result<(), Self::Error>;
+}
+
+#[public]
+impl IOracle for ConstantSumCurve {
+    type Error = Error;
+
+    fn is_reporter(&self, reporter: Address) -> bool {
+        self.reporters.get(reporter)
+    }
+
+    fn reporters(&self) -> Vec<Address> {
+        (0..self.reporter_list.len())
+            .filter_map(|i| self.reporter_list.get(i))
+            .collect()
+    }
+
+    fn set_reporter(&mut self, reporter: Address, allowed: bool) -> Result<(), Self::Error> {
+        meter!("setReporter", Address, bool);
+        self.check_active()?;
+        self.only_owner()?;
+        self.update_reporter(reporter, allowed)?;
+        let op_seq = self.record_change(
+            ChangeKind::Reporter,
+            reporter.into_word(),
+            U256::from(u8::from(allowed)),
+        );
+
+        #[allow(deprecated)]
+        evm::log(ReporterSet {
+            op_seq,
+            reporter,
+            allowed,
+        });
+
+        Ok(())
+    }
+
+    fn max_price_age(&self) -> u64 {
+        self.max_price_age.get().to::<u64>()
+    }
+
+    fn set_max_price_age(&mut self, max_price_age: u64) -> Result<(), Self::Error> {
+        meter!("setMaxPriceAge", u64);
+        self.check_active()?;
+        self.only_owner()?;
+        self.max_price_age.set(U64::from(max_price_age));
+        let op_seq = self.record_change(
+            ChangeKind::MaxPriceAge,
+            B256::ZERO,
+            U256::from(max_price_age),
+        );
+
+        #[allow(deprecated)]
+        evm::log(MaxPriceAgeSet {
+            op_seq,
+            max_price_age,
+        });
+
+        Ok(())
+    }
+
+    fn max_deviation_bps(&self) -> u16 {
+        self.max_deviation_bps.get().to::<u16>()
+    }
+
+    fn set_max_deviation_bps(&mut self, max_deviation_bps: u16) -> Result<(), Self::Error> {
+        meter!("setMaxDeviationBps", u16);
+        self.check_active()?;
+        self.only_owner()?;
+        bounds::check_spread(max_deviation_bps)?;
+        self.max_deviation_bps.set(U16::from(max_deviation_bps));
+        let op_seq = self.record_change(
+            ChangeKind::MaxDeviation,
+            B256::ZERO,
+            U256::from(max_deviation_bps),
+        );
+
+        #[allow(deprecated)]
+        evm::log(MaxDeviationSet {
+            op_seq,
+            max_deviation_bps,
+        });
+
+        Ok(())
+    }
+
+    fn reporter_nonce(&self, reporter: Address) -> U256 {
+        self.next_report_nonce(reporter)
+    }
+
+    fn post_price(
+        &mut self,
+        price: U256,
+        timestamp: u64,
+        nonce: U256,
+        signature: Bytes,
+    ) -> Result<(), Self::Error> {
+        meter!("postPrice", U256, u64, U256, Bytes);
+        self.check_active()?;
+        self.record_price(price, timestamp, nonce, &signature)?;
+        self.aggregate_prices();
+        Ok(())
+    }
+
+    fn post_prices(&mut self, reports: Vec<SignedPriceReport>) -> Result<(), Self::Error> {
+        meter!("postPrices", Vec<SignedPriceReport>);
+        self.check_active()?;
+        let mut latest_timestamp = 0;
+        for SignedPriceReport {
+            price,
+            timestamp,
+            nonce,
+            signature,
+        } in reports
+        {
+            if timestamp <= latest_timestamp {
+                return Err(Error::InvalidPriceTimestamp(InvalidPriceTimestamp {
+                    timestamp,
+                    latest_timestamp,
+                }));
+            }
+            latest_timestamp = timestamp;
+            self.record_price(price, timestamp, nonce, &signature)?;
+        }
+        self.aggregate_prices();
+        Ok(())
+    }
+
+    fn latest_price(&self) -> Result<(U256, u64), Self::Error> {
+        self.check_view_unlocked()?;
+        self.fresh_price()
+    }
+
+    fn pool_price(&self, pool_id: B256) -> Result<(U256, u64), Self::Error> {
+        self.check_view_unlocked()?;
+        self.pool_fresh_price(pool_id)
+    }
+
+    fn pool_max_price_age(&self, pool_id: B256) -> u64 {
+        self.max_price_age_of(pool_id)
+    }
+
+    fn set_pool_max_price_age(
+        &mut self,
+        pool_id: B256,
+        max_price_age: u64,
+    ) -> Result<(), Self::Error> {
+        meter!("setPoolMaxPriceAge", B256, u64);
+        self.check_active()?;
+        self.only_fee_manager()?;
+        self.check_registered(pool_id)?;
+        self.set_max_price_age_of(pool_id, max_price_age);
+        let op_seq = self.record_change(
+            ChangeKind::PoolMaxPriceAge,
+            pool_id,
+            U256::from(max_price_age),
+        );
+
+        #[allow(deprecated)]
+        evm::log(PoolMaxPriceAgeSet {
+            op_seq,
+            pool_id,
+            max_price_age,
+        });
+
+        Ok(())
+    }
+}
+
+impl ConstantSumCurve {
+    /// Adds `reporter` to or removes it from the reporters, dropping its
+    /// latest report (but not its nonce) on removal.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::TooManyReporters`] - If [`MAX_REPORTERS`] are already
+    ///   registered.
+    pub(crate) fn update_reporter(
+        &mut self,
+        reporter: Address,
+        allowed: bool,
+    ) -> Result<(), Error> {
+        if self.reporters.get(reporter) == allowed {
+            return Ok(());
+        }
+        if allowed {
+            if self.reporter_list.len() >= usize::from(MAX_REPORTERS) {
+                return Err(Error::TooManyReporters(TooManyReporters {
+                    max_reporters: MAX_REPORTERS,
+                }));
+            }
+            self.reporters.setter(reporter).set(true);
+            self.reporter_list.push(reporter);
+            return Ok(());
+        }
+
+        self.reporters.setter(reporter).set(false);
+
+        let last = self.reporter_list.len() - 1;
+        let index = (0..last)
+            .find(|&i| self.reporter_list.get(i) == Some(reporter))
+            .unwrap_or(last);
+        if index != last {
+            let moved = self.reporter_list.get(last).unwrap_or_default();
+            if let Some(mut slot) = self.reporter_list.setter(index) {
+                slot.set(moved);
+            }
+        }
+        self.reporter_list.pop();
+        let mut report = self.reports.setter(reporter);
+        report.price.set(U256::ZERO);
+        report.timestamp.set(U64::ZERO);
+        Ok(())
+    }
+
+    /// Returns the nonce `reporter` must sign for its next report.
+    pub(crate) fn next_report_nonce(&self, reporter: Address) -> U256 {
+        self.reports.getter(reporter).nonce.get()
+    }
+
+    /// Verifies a price report signed by a reporter and stores it, without
+    /// updating the aggregate price.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidPriceTimestamp`] - If `timestamp` is in the future
+    ///   or not newer than the reporter's previous report.
+    /// * [`Error::StalePrice`] - If `timestamp` is older than the maximum
+    ///   price age.
+    /// * [`Error::InvalidSignature`] - If `signature` cannot be recovered.
+    /// * [`Error::Unauthorized`] - If the signer is not a reporter.
+    /// * [`Error::InvalidReportNonce`] - If `nonce` is not the reporter's
+    ///   next nonce.
+    ///
+    /// # Events
+    ///
+    /// * [`PricePosted`].
+    pub(crate) fn record_price(
+        &mut self,
+        price: U256,
+        timestamp: u64,
+        nonce: U256,
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        if timestamp > self.vm().block_timestamp() {
+            return Err(Error::InvalidPriceTimestamp(InvalidPriceTimestamp {
+                timestamp,
+                latest_timestamp: 0,
+            }));
+        }
+        self.check_price_age(timestamp)?;
+
+        let struct_hash = keccak256((PRICE_REPORT_TYPEHASH, price, timestamp, nonce).abi_encode());
+        let reporter = self.recover_signer(self.typed_data_digest(struct_hash), signature)?;
+        if !self.reporters.get(reporter) {
+            return Err(Error::Unauthorized(Unauthorized { account: reporter }));
+        }
+        let expected = self.next_report_nonce(reporter);
+        if nonce != expected {
+            return Err(Error::InvalidReportNonce(InvalidReportNonce {
+                reporter,
+                expected,
+            }));
+        }
+        let latest_timestamp = self.reports.getter(reporter).timestamp.get().to::<u64>();
+        if timestamp <= latest_timestamp {
+            return Err(Error::InvalidPriceTimestamp(InvalidPriceTimestamp {
+                timestamp,
+                latest_timestamp,
+            }));
+        }
+
+        let mut report = self.reports.setter(reporter);
+        report.price.set(price);
+        report.timestamp.set(U64::from(timestamp));
+        report.nonce.set(nonce + U256::from(1));
+
+        #[allow(deprecated)]
+        evm::log(PricePosted {
+            reporter,
+            price,
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the aggregate price and its timestamp.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::StalePrice`] - If no price was aggregated or the aggregate
+    ///   is older than the maximum price age.
+    pub(crate) fn fresh_price(&self) -> Result<(U256, u64), Error> {
+        let timestamp = self.price_timestamp.get().to::<u64>();
+        if timestamp == 0 {
+            return Err(Error::StalePrice(StalePrice {
+                timestamp,
+                max_price_age: self.max_price_age.get().to::<u64>(),
+            }));
+        }
+        self.check_price_age(timestamp)?;
+        Ok((self.price.get(), timestamp))
+    }
+
+    /// Returns the aggregate price and its timestamp for the pool `pool_id`,
+    /// bounded by the pool's maximum price age if it is pegged to the
+    /// oracle, or by the contract-wide one otherwise.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PriceTooOld`] - If the aggregate is older than the pool's
+    ///   maximum price age, or no price was aggregated.
+    /// * [`Error::StalePrice`] - If the pool is not pegged to the oracle and
+    ///   the aggregate is stale.
+    pub(crate) fn pool_fresh_price(&self, pool_id: B256) -> Result<(U256, u64), Error> {
+        let max_price_age = self.max_price_age_of(pool_id);
+        if max_price_age == 0 {
+            return self.fresh_price();
+        }
+        let timestamp = self.price_timestamp.get().to::<u64>();
+        let age = self.vm().block_timestamp().saturating_sub(timestamp);
+        if timestamp == 0 || age > max_price_age {
+            return Err(Error::PriceTooOld(PriceTooOld {
+                age: U256::from(age),
+                max: U256::from(max_price_age),
+            }));
+        }
+        Ok((self.price.get(), timestamp))
+    }
+
+    /// Checks that the oracle price is fresh enough to swap in the pool
+    /// `pool_id`, if it is pegged to the oracle.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PriceTooOld`] - If the aggregate is older than the pool's
+    ///   maximum price age, or no price was aggregated.
+    pub(crate) fn check_pegged_price(&self, pool_id: B256) -> Result<(), Error> {
+        if self.max_price_age_of(pool_id) != 0 {
+            self.pool_fresh_price(pool_id)?;
+        }
+        Ok(())
+    }
+
+    /// Stores the median of the fresh reports within the maximum deviation
+    /// of their median as the aggregate price.
+    ///
+    /// # Events
+    ///
+    /// * [`PriceAggregated`].
+    pub(crate) fn aggregate_prices(&mut self) {
+        let mut reports: Vec<(U256, u64)> = (0..self.reporter_list.len())
+            .filter_map(|i| self.reporter_list.get(i))
+            .map(|reporter| {
+                let report = self.reports.getter(reporter);
+                (report.price.get(), report.timestamp.get().to::<u64>())
+            })
+            .filter(|&(_, timestamp)| timestamp != 0 && self.check_price_age(timestamp).is_ok())
+            .collect();
+        reports.sort_unstable_by_key(|&(price, _)| price);
+        let Some(median_price) = median(&reports) else {
+            return;
+        };
+
+        let max_deviation_bps = self.max_deviation_bps();
+        if max_deviation_bps != 0 {
+            let max_deviation = math::mul_div(
+                median_price,
+                U256::from(max_deviation_bps),
+                U256::from(FEE_DENOMINATOR),
+            )
+            .unwrap_or(U256::MAX);
+            reports.retain(|&(price, _)| price.abs_diff(median_price) <= max_deviation);
+        }
+        // Keep the previous aggregate if every report deviates too much.
+        let Some(price) = median(&reports) else {
+            return;
+        };
+        let timestamp = reports
+            .iter()
+            .map(|&(_, timestamp)| timestamp)
+            .min()
+            .unwrap_or_default();
+        let reporters = reports.len() as u8;
+        self.price.set(price);
+        self.price_timestamp.set(U64::from(timestamp));
+
+        #[allow(deprecated)]
+        evm::log(PriceAggregated {
+            price,
+            timestamp,
+            reporters,
+        });
+    }
+
+    /// Checks that a price posted at `timestamp` is not older than the
+    /// maximum price age.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::StalePrice`] - If the price is older than the maximum price
+    ///   age.
+    fn check_price_age(&self, timestamp: u64) -> Result<(), Error> {
+        let max_price_age = self.max_price_age.get().to::<u64>();
+        if self.vm().block_timestamp().saturating_sub(timestamp) > max_price_age {
+            return Err(Error::StalePrice(StalePrice {
+                timestamp,
+                max_price_age,
+            }));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the median price of `reports` sorted by price, averaging the two
+/// middle prices (rounded down) if their number is even, or `None` if there
+/// are no reports.
+pub fn median(reports: &[(U256, u64)]) -> Option<U256> {
+    let len = reports.len();
+    if len == 0 {
+        return None;
+    }
+    let upper = reports[len / 2].0;
+    if len % 2 == 1 {
+        return Some(upper);
+    }
+    let lower = reports[len / 2 - 1].0;
+    Some(lower + (upper - lower) / U256::from(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{aliases::U160, uint, I256};
+
+    use super::*;
+    use crate::{
+        testing::{deploy, deploy_multi_pool, hook, pool_key, POOLS},
+        FeeManagerSet, PoolNotRegistered, SwapParams,
+    };
+
+    #[motsu::test]
+    fn oracle_rejects_missing_and_future_prices(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+
+        let err = contract
+            .sender(alice)
+            .latest_price()
+            .expect_err("should not have a price");
+        assert!(matches!(
+            err,
+            Error::StalePrice(StalePrice { timestamp: 0, .. })
+        ));
+        let err = contract
+            .sender(alice)
+            .post_price(
+                uint!(1_U256),
+                u64::MAX,
+                U256::ZERO,
+                Bytes::from(vec![0u8; 65]),
+            )
+            .expect_err("should reject a future price");
+        assert!(matches!(
+            err,
+            Error::InvalidPriceTimestamp(InvalidPriceTimestamp {
+                timestamp: u64::MAX,
+                latest_timestamp: 0
+            })
+        ));
+    }
+
+    #[motsu::test]
+    fn batched_prices_need_increasing_timestamps(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+
+        let report = |timestamp| SignedPriceReport {
+            price: uint!(1_U256),
+            timestamp,
+            nonce: U256::ZERO,
+            signature: vec![0u8; 65].into(),
+        };
+        let err = contract
+            .sender(alice)
+            .post_prices(vec![report(0)])
+            .expect_err("should reject a zero timestamp");
+        assert!(matches!(
+            err,
+            Error::InvalidPriceTimestamp(InvalidPriceTimestamp {
+                timestamp: 0,
+                latest_timestamp: 0
+            })
+        ));
+        contract
+            .sender(alice)
+            .post_prices(vec![])
+            .expect("should accept an empty batch");
+    }
+
+    #[motsu::test]
+    fn registers_and_removes_reporters(
+        alice: Address,
+        bob: Address,
+        charlie: Address,
+        dave: Address,
+    ) {
+        let contract = hook();
+        deploy(&contract, alice);
+        for reporter in [bob, charlie, dave] {
+            contract
+                .sender(alice)
+                .set_reporter(reporter, true)
+                .expect("should register reporter");
+        }
+        contract
+            .sender(alice)
+            .set_reporter(charlie, false)
+            .expect("should remove reporter");
+        assert_eq!(vec![bob, dave], contract.sender(alice).reporters());
+        assert!(!contract.sender(alice).is_reporter(charlie));
+    }
+
+    #[test]
+    fn computes_median_of_sorted_reports() {
+        let reports = |prices: &[u64]| -> Vec<(U256, u64)> {
+            prices.iter().map(|&price| (U256::from(price), 1)).collect()
+        };
+        assert_eq!(None, median(&reports(&[])));
+        assert_eq!(Some(uint!(5_U256)), median(&reports(&[1, 5, 100])));
+        assert_eq!(Some(uint!(7_U256)), median(&reports(&[1, 5, 10, 100])));
+    }
+
+    #[motsu::test]
+    fn pegged_pools_need_fresh_prices(alice: Address, bob: Address, charlie: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let now = contract.sender(alice).vm().block_timestamp();
+        let swap = |index: usize| {
+            let (currency0, currency1, fee) = POOLS[index];
+            contract.swap(
+                alice,
+                pool_key(currency0, currency1, fee),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified: I256::MINUS_ONE,
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                Bytes::default(),
+            )
+        };
+
+        contract
+            .sender(alice)
+            .set_fee_manager(bob)
+            .expect("should set fee manager");
+        contract.assert_emitted(&FeeManagerSet {
+            op_seq: 4,
+            fee_manager: bob,
+        });
+        assert_eq!(bob, contract.sender(alice).fee_manager());
+        let err = contract
+            .sender(charlie)
+            .set_pool_max_price_age(pool_ids[0], 600)
+            .expect_err("should reject other accounts");
+        assert!(matches!(err, Error::Unauthorized(_)));
+        let err = contract
+            .sender(bob)
+            .set_pool_max_price_age(B256::ZERO, 600)
+            .expect_err("should reject an unregistered pool");
+        assert!(matches!(err, Error::PoolNotRegistered(_)));
+
+        contract
+            .sender(bob)
+            .set_pool_max_price_age(pool_ids[0], 600)
+            .expect("should peg pool");
+        contract.assert_emitted(&PoolMaxPriceAgeSet {
+            op_seq: 5,
+            pool_id: pool_ids[0],
+            max_price_age: 600,
+        });
+        assert_eq!(600, contract.sender(alice).pool_max_price_age(pool_ids[0]));
+
+        // Without a price, the pegged pool cannot swap, unlike the others.
+        let err = swap(0).expect_err("should need a price");
+        assert!(matches!(
+            err,
+            Error::PriceTooOld(PriceTooOld { age, max })
+                if age == U256::from(now) && max == uint!(600_U256)
+        ));
+        let err = contract
+            .sender(alice)
+            .pool_price(pool_ids[0])
+            .expect_err("should need a price");
+        assert!(matches!(err, Error::PriceTooOld(_)));
+        swap(1).expect("should swap in an unpegged pool");
+
+        contract.sender(alice).price.set(math::WAD);
+        contract.sender(alice).price_timestamp.set(U64::from(now));
+        assert_eq!(
+            (math::WAD, now),
+            contract
+                .sender(alice)
+                .pool_price(pool_ids[0])
+                .expect("should read a fresh price")
+        );
+        swap(0).expect("should swap with a fresh price");
+    }
+}
@@ -0,0 +1,987 @@
+//! Uniswap V4 pools served by the hook, keyed by their `PoolId`.
+//!
+//! The pool manager calls `beforeInitialize` when a pool using the hook is
+//! initialized. The hook then records the pool's `PoolKey` under its
+//! `PoolId` (see [`pool_id`]), and rejects pools with an unsupported fee
+//! tier. Per-pool state lives next to the key, so a single deployment can
+//! serve many pools.
+//!
+//! Each registered pool has its own reserves, swap fee and pause flag, used
+//! by swaps through `beforeSwap`. The QuoterV2-style quotes find a pool by
+//! its pair and fee tier, as the first pool registered with them. Pools
+//! with their own maximum price age are pegged to the oracle: they only swap
+//! while its price is fresh enough.
+//! The legacy [`IUniswapV4Curve`] interface trades against the default pool
+//! [`DEFAULT_POOL_ID`], whose state is kept in the contract-wide fields.
+//!
+//! [`IUniswapV4Curve`]: crate::IUniswapV4Curve
+use alloy_primitives::{
+    aliases::{I24, U16, U24, U64},
+    keccak256, uint, Address, B256, U256,
+};
+use alloy_sol_types::SolValue;
+use stylus_sdk::{
+    evm,
+    prelude::*,
+    storage::{
+        StorageAddress, StorageBool, StorageSigned, StorageU16, StorageU256, StorageU64,
+        StorageUint,
+    },
+};
+
+use crate::{
+    bounds, changelog::ChangeKind, math, AmountOverflow, ConstantSumCurve, ContractPaused, Error,
+    PoolAlreadyRegistered, PoolEmpty, PoolFeeSet, PoolKey, PoolNotEmpty, PoolNotFound,
+    PoolNotRegistered, PoolPaused, PoolPausedSet, PoolRegistered, UnsupportedFeeTier,
+};
+
+/// `PoolId` of the default pool, traded through the legacy
+/// `IUniswapV4Curve` interface. No `PoolKey` hashes to it.
+pub const DEFAULT_POOL_ID: B256 = B256::ZERO;
+
+/// Fee tiers of the pools the hook can serve, in hundredths of a basis
+/// point: 0.01%, 0.05%, 0.3% and 1%.
+pub const SUPPORTED_FEE_TIERS: [U24; 4] = [
+    uint!(100_U24),
+    uint!(500_U24),
+    uint!(3_000_U24),
+    uint!(10_000_U24),
+];
+
+/// A registered pool: its `currency0`, `currency1`, fee and tick spacing.
+pub type PoolKeyView = (Address, Address, U24, I24);
+
+/// State of a registered pool.
+#[storage]
+pub struct RegisteredPool {
+    currency0: StorageAddress,
+    currency1: StorageAddress,
+    fee: StorageUint<24, 1>,
+    tick_spacing: StorageSigned<24, 1>,
+    volume0: StorageU256,
+    volume1: StorageU256,
+    last_price: StorageU256,
+    reserve0: StorageU256,
+    reserve1: StorageU256,
+    fee_bps: StorageU16,
+    paused: StorageBool,
+    max_price_age: StorageU64,
+}
+
+/// Returns the `PoolId` of `key`, i.e. the hash of its ABI encoding, like
+/// v4's `PoolIdLibrary.toId`.
+pub fn pool_id(key: &PoolKey) -> B256 {
+    keccak256(key.abi_encode())
+}
+
+/// Returns the key of the pools of `currency0` and `currency1` with `fee` in
+/// the pool index, i.e. the hash of their packed encoding.
+fn index_key(currency0: Address, currency1: Address, fee: U24) -> B256 {
+    keccak256(
+        [
+            currency0.as_slice(),
+            currency1.as_slice(),
+            &fee.to_be_bytes::<3>(),
+        ]
+        .concat(),
+    )
+}
+
+/// Interface of the Uniswap V4 pools served by the hook.
+pub trait IPool {
+    /// The error type associated to the trait implementation.
+    type Error: Into<alloc::vec::Vec<u8>>;
+
+    /// Returns the `PoolId` of the pool `key`.
+    fn pool_id(&self, key: PoolKey) -> B256;
+
+    /// Returns the pool `pool_id` served by the hook: its `currency0`,
+    /// `currency1`, fee and tick spacing, or zeroes until it is
+    /// initialized.
+    fn pool_key(&self, pool_id: B256) -> PoolKeyView;
+
+    /// Returns the reserves of `currency0` and `currency1` in the pool
+    /// `pool_id`, updated by every swap of the pool manager.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    fn pool_reserves(&self, pool_id: B256) -> Result<(U256, U256), Self::Error>;
+
+    /// Returns the price of `currency0` in `currency1` in the pool `pool_id`
+    /// as an 18-decimal fixed-point number, before the swap fee.
+    ///
+    /// The constant-sum curve trades at the exchange rate of the pair
+    /// whatever its reserves, so this is the price of any swap that the
+    /// reserves can cover.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `pool_id` - The registered pool, or [`DEFAULT_POOL_ID`] once
+    ///   its currencies are set.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolNotRegistered`] - If the pool is not registered, or
+    ///   the currencies of the default pool are not set.
+    /// * [`Error::AmountOverflow`] - If the price does not fit into
+    ///   `uint256`.
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    fn get_price(&self, pool_id: B256) -> Result<U256, Self::Error>;
+
+    /// Seeds the reserves of `currency0` and `currency1` in the registered
+    /// pool `pool_id` while it is still empty. Once seeded, the reserves only
+    /// move through swaps, [`Self::deposit`] and [`Self::withdraw`].
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `pool_id` - The `PoolId` of the pool.
+    /// * `reserve0` - The new reserve of `currency0`.
+    /// * `reserve1` - The new reserve of `currency1`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::PoolNotRegistered`] - If the pool is not registered.
+    /// * [`Error::PoolNotEmpty`] - If the pool already holds reserves.
+    ///
+    /// # Events
+    ///
+    /// * [`PoolReservesSet`](crate::PoolReservesSet).
+    fn set_pool_reserves(
+        &mut self,
+        pool_id: B256,
+        reserve0: U256,
+        reserve1: U256,
+    ) -> Result<(), Self::Error>;
+
+    /// Deposits `amount0` of `currency0` and `amount1` of `currency1` from
+    /// the owner into the reserves of the registered pool `pool_id`.
+    ///
+    /// ERC-20 tokens are pulled with `transferFrom`, so the owner approves
+    /// them first. Native ETH is paid as the value of the call.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `pool_id` - The `PoolId` of the pool.
+    /// * `amount0` - The amount of `currency0` to deposit.
+    /// * `amount1` - The amount of `currency1` to deposit.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::PoolNotRegistered`] - If the pool is not registered.
+    /// * [`Error::UnexpectedNativeValue`] - If value is sent for a pool of
+    ///   two ERC-20 tokens.
+    /// * [`Error::NativeValueMismatch`] - If the value sent differs from the
+    ///   native amount.
+    /// * [`Error::AmountOverflow`] - If a reserve does not fit into
+    ///   `uint256`.
+    /// * [`Error::TransferFailed`] - If an ERC-20 token cannot be pulled.
+    /// * [`Error::Reentrancy`] - If another operation holds the lock.
+    ///
+    /// # Events
+    ///
+    /// * [`PoolReservesSet`](crate::PoolReservesSet).
+    fn deposit(&mut self, pool_id: B256, amount0: U256, amount1: U256) -> Result<(), Self::Error>;
+
+    /// Withdraws `amount0` of `currency0` and `amount1` of `currency1` from
+    /// the reserves of the registered pool `pool_id` to `to`. Native ETH is
+    /// sent as a plain value transfer.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `pool_id` - The `PoolId` of the pool.
+    /// * `amount0` - The amount of `currency0` to withdraw.
+    /// * `amount1` - The amount of `currency1` to withdraw.
+    /// * `to` - The recipient of the currencies.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::PoolNotRegistered`] - If the pool is not registered.
+    /// * [`Error::InsufficientLiquidity`] - If a reserve cannot cover its
+    ///   amount.
+    /// * [`Error::TransferFailed`] - If a currency cannot be sent.
+    /// * [`Error::Reentrancy`] - If another operation holds the lock.
+    ///
+    /// # Events
+    ///
+    /// * [`PoolReservesSet`](crate::PoolReservesSet).
+    fn withdraw(
+        &mut self,
+        pool_id: B256,
+        amount0: U256,
+        amount1: U256,
+        to: Address,
+    ) -> Result<(), Self::Error>;
+
+    /// Returns the swap fee of the pool `pool_id` in basis points.
+    fn pool_fee(&self, pool_id: B256) -> u16;
+
+    /// Sets the swap fee of the registered pool `pool_id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `pool_id` - The `PoolId` of the pool.
+    /// * `fee_bps` - The fee in basis points.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::PoolNotRegistered`] - If the pool is not registered.
+    /// * [`Error::InvalidFee`] - If `fee_bps` exceeds [`bounds::MAX_FEE_BPS`].
+    ///
+    /// # Events
+    ///
+    /// * [`PoolFeeSet`].
+    fn set_pool_fee(&mut self, pool_id: B256, fee_bps: u16) -> Result<(), Self::Error>;
+
+    /// Returns true if swaps in the pool `pool_id` are paused on their own,
+    /// regardless of the contract-wide pause.
+    fn pool_paused(&self, pool_id: B256) -> bool;
+
+    /// Pauses or unpauses swaps in the registered pool `pool_id`, leaving
+    /// the other pools untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `pool_id` - The `PoolId` of the pool.
+    /// * `paused` - True to pause swaps in the pool.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::PoolNotRegistered`] - If the pool is not registered.
+    ///
+    /// # Events
+    ///
+    /// * [`PoolPausedSet`].
+    fn set_pool_paused(&mut self, pool_id: B256, paused: bool) -> Result<(), Self::Error>;
+
+    /// Returns the cumulative volume of `currency0` and `currency1` swapped
+    /// in the pool `pool_id`.
+    fn volume(&self, pool_id: B256) -> (U256, U256);
+
+    /// Returns the price of the last swap in the pool `pool_id`, in units
+    /// of `currency1` per unit of `currency0` as an 18-decimal fixed-point
+    /// number.
+    fn last_price(&self, pool_id: B256) -> U256;
+}
+
+#[public]
+impl IPool for ConstantSumCurve {
+    type Error = Error;
+
+    fn pool_id(&self, key: PoolKey) -> B256 {
+        pool_id(&key)
+    }
+
+    fn pool_key(&self, pool_id: B256) -> PoolKeyView {
+        self.registered_pool(pool_id)
+    }
+
+    fn pool_reserves(&self, pool_id: B256) -> Result<(U256, U256), Self::Error> {
+        self.check_view_unlocked()?;
+        Ok(self.reserves_of(pool_id))
+    }
+
+    fn get_price(&self, pool_id: B256) -> Result<U256, Self::Error> {
+        self.check_view_unlocked()?;
+        let (currency0, currency1) = if pool_id == DEFAULT_POOL_ID {
+            self.default_currencies()?
+        } else {
+            self.check_registered(pool_id)?;
+            let (currency0, currency1, ..) = self.registered_pool(pool_id);
+            (currency0, currency1)
+        };
+        self.price_wad(currency0, currency1)
+    }
+
+    fn set_pool_reserves(
+        &mut self,
+        pool_id: B256,
+        reserve0: U256,
+        reserve1: U256,
+    ) -> Result<(), Self::Error> {
+        meter!("setPoolReserves", B256, U256, U256);
+        self.check_active()?;
+        self.only_owner()?;
+        self.check_registered(pool_id)?;
+        self.check_empty(pool_id)?;
+        self.update_pool_reserves(pool_id, reserve0, reserve1);
+        Ok(())
+    }
+
+    #[payable]
+    fn deposit(&mut self, pool_id: B256, amount0: U256, amount1: U256) -> Result<(), Self::Error> {
+        meter!("deposit", B256, U256, U256);
+        self.check_active()?;
+        self.only_owner()?;
+        self.check_registered(pool_id)?;
+        let (currency0, currency1, _, _) = self.registered_pool(pool_id);
+        self.check_native_value(pool_id, (currency0, amount0), (currency1, amount1))?;
+
+        let owner = self.vm().msg_sender();
+        self.with_lock(|curve| {
+            let (reserve0, reserve1) = curve.reserves_of(pool_id);
+            let overflow = || Error::AmountOverflow(AmountOverflow {});
+            let reserve0 = reserve0.checked_add(amount0).ok_or_else(overflow)?;
+            let reserve1 = reserve1.checked_add(amount1).ok_or_else(overflow)?;
+            curve.pull_currency(currency0, owner, amount0)?;
+            curve.pull_currency(currency1, owner, amount1)?;
+            curve.update_pool_reserves(pool_id, reserve0, reserve1);
+            Ok(())
+        })
+    }
+
+    fn withdraw(
+        &mut self,
+        pool_id: B256,
+        amount0: U256,
+        amount1: U256,
+        to: Address,
+    ) -> Result<(), Self::Error> {
+        meter!("withdraw", B256, U256, U256, Address);
+        self.check_active()?;
+        self.only_owner()?;
+        self.check_registered(pool_id)?;
+
+        let (currency0, currency1, _, _) = self.registered_pool(pool_id);
+        self.with_lock(|curve| {
+            let (reserve0, reserve1) = curve.reserves_of(pool_id);
+            Self::check_liquidity(reserve0, amount0)?;
+            Self::check_liquidity(reserve1, amount1)?;
+            curve.update_pool_reserves(pool_id, reserve0 - amount0, reserve1 - amount1);
+            curve.push_currency(currency0, to, amount0)?;
+            curve.push_currency(currency1, to, amount1)
+        })
+    }
+
+    fn pool_fee(&self, pool_id: B256) -> u16 {
+        self.fee_of(pool_id)
+    }
+
+    fn set_pool_fee(&mut self, pool_id: B256, fee_bps: u16) -> Result<(), Self::Error> {
+        meter!("setPoolFee", B256, u16);
+        self.check_active()?;
+        self.only_owner()?;
+        self.check_registered(pool_id)?;
+        bounds::check_fee(fee_bps)?;
+        self.set_fee_of(pool_id, fee_bps);
+        let op_seq = self.record_change(ChangeKind::PoolFee, pool_id, U256::from(fee_bps));
+
+        #[allow(deprecated)]
+        evm::log(PoolFeeSet {
+            op_seq,
+            pool_id,
+            fee_bps,
+        });
+
+        Ok(())
+    }
+
+    fn pool_paused(&self, pool_id: B256) -> bool {
+        self.paused_of(pool_id)
+    }
+
+    fn set_pool_paused(&mut self, pool_id: B256, paused: bool) -> Result<(), Self::Error> {
+        meter!("setPoolPaused", B256, bool);
+        self.check_active()?;
+        self.only_owner()?;
+        self.check_registered(pool_id)?;
+        self.set_paused_of(pool_id, paused);
+        let op_seq = self.record_change(
+            ChangeKind::PoolPaused,
+            pool_id,
+            U256::from(u8::from(paused)),
+        );
+
+        #[allow(deprecated)]
+        evm::log(PoolPausedSet {
+            op_seq,
+            pool_id,
+            paused,
+        });
+
+        Ok(())
+    }
+
+    fn volume(&self, pool_id: B256) -> (U256, U256) {
+        self.pool_volume(pool_id)
+    }
+
+    fn last_price(&self, pool_id: B256) -> U256 {
+        self.pool_last_price(pool_id)
+    }
+}
+
+impl ConstantSumCurve {
+    /// Returns the pool registered under `pool_id`, or zeroes if none is.
+    pub(crate) fn registered_pool(&self, pool_id: B256) -> PoolKeyView {
+        let pool = self.pools.getter(pool_id);
+        (
+            pool.currency0.get(),
+            pool.currency1.get(),
+            pool.fee.get(),
+            pool.tick_spacing.get(),
+        )
+    }
+
+    /// Records the pool `key` under its `PoolId`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolAlreadyRegistered`] - If the pool is already
+    ///   registered.
+    /// * [`Error::UnsupportedFeeTier`] - If the fee of `key` is not one of
+    ///   [`SUPPORTED_FEE_TIERS`].
+    ///
+    /// # Events
+    ///
+    /// * [`PoolRegistered`].
+    pub(crate) fn register_pool(&mut self, key: &PoolKey) -> Result<(), Error> {
+        let pool_id = pool_id(key);
+        let fee_bps = self.fee();
+        let mut pool = self.pools.setter(pool_id);
+        // `currency1` sorts after `currency0`, so it is only zero until the
+        // pool is registered.
+        if !pool.currency1.get().is_zero() {
+            return Err(Error::PoolAlreadyRegistered(PoolAlreadyRegistered {
+                pool_id,
+            }));
+        }
+        if !SUPPORTED_FEE_TIERS.contains(&key.fee) {
+            return Err(Error::UnsupportedFeeTier(UnsupportedFeeTier {
+                fee: key.fee,
+            }));
+        }
+
+        pool.currency0.set(key.currency0);
+        pool.currency1.set(key.currency1);
+        pool.fee.set(key.fee);
+        pool.tick_spacing.set(key.tick_spacing);
+        // New pools start with the default pool's swap fee.
+        pool.fee_bps.set(U16::from(fee_bps));
+        // Quoters find a pool by its pair and fee, which the first pool
+        // registered with them keeps.
+        let mut index = self
+            .pool_index
+            .setter(index_key(key.currency0, key.currency1, key.fee));
+        if index.get().is_zero() {
+            index.set(pool_id);
+        }
+
+        #[allow(deprecated)]
+        evm::log(PoolRegistered {
+            pool_id,
+            currency0: key.currency0,
+            currency1: key.currency1,
+            fee: key.fee,
+            tick_spacing: key.tick_spacing,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the `PoolId` of the pool of `token_in` and `token_out` with
+    /// `fee`, or [`DEFAULT_POOL_ID`] if `fee` is zero.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolNotFound`] - If no pool of the pair with `fee` is
+    ///   registered.
+    pub(crate) fn resolve_pool(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        fee: U24,
+    ) -> Result<B256, Error> {
+        if fee.is_zero() {
+            return Ok(DEFAULT_POOL_ID);
+        }
+        let (currency0, currency1) = if token_in < token_out {
+            (token_in, token_out)
+        } else {
+            (token_out, token_in)
+        };
+        let pool_id = self.pool_index.get(index_key(currency0, currency1, fee));
+        if pool_id.is_zero() {
+            return Err(Error::PoolNotFound(PoolNotFound {
+                currency0,
+                currency1,
+                fee,
+            }));
+        }
+        Ok(pool_id)
+    }
+
+    /// Returns the cumulative volume of `currency0` and `currency1` swapped
+    /// in the pool `pool_id`.
+    pub(crate) fn pool_volume(&self, pool_id: B256) -> (U256, U256) {
+        let pool = self.pools.getter(pool_id);
+        (pool.volume0.get(), pool.volume1.get())
+    }
+
+    /// Returns the price of the last swap in the pool `pool_id`.
+    pub(crate) fn pool_last_price(&self, pool_id: B256) -> U256 {
+        self.pools.getter(pool_id).last_price.get()
+    }
+
+    /// Adds a swap of `volume0` and `volume1` to the volume of the pool
+    /// `pool_id`, and records its price.
+    pub(crate) fn account_swap(&mut self, pool_id: B256, volume0: U256, volume1: U256) {
+        let mut pool = self.pools.setter(pool_id);
+        let total0 = pool.volume0.get().saturating_add(volume0);
+        let total1 = pool.volume1.get().saturating_add(volume1);
+        pool.volume0.set(total0);
+        pool.volume1.set(total1);
+        if !volume0.is_zero() {
+            // `volume1 * WAD / volume0` fits, as both fit into `uint128`.
+            pool.last_price
+                .set(math::mul_div(volume1, math::WAD, volume0).unwrap_or_default());
+        }
+    }
+
+    /// Checks that the pool `pool_id` is registered.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolNotRegistered`] - If the pool is not registered.
+    pub(crate) fn check_registered(&self, pool_id: B256) -> Result<(), Error> {
+        if self.pools.getter(pool_id).currency1.get().is_zero() {
+            return Err(Error::PoolNotRegistered(PoolNotRegistered { pool_id }));
+        }
+        Ok(())
+    }
+
+    /// Checks that the registered pool `pool_id` holds a reserve of either
+    /// currency, before any swap math or oracle read runs against it.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolEmpty`] - If both reserves of the pool are zero.
+    pub(crate) fn check_not_empty(&self, pool_id: B256) -> Result<(), Error> {
+        let (reserve0, reserve1) = self.reserves_of(pool_id);
+        if reserve0.is_zero() && reserve1.is_zero() {
+            return Err(Error::PoolEmpty(PoolEmpty { pool_id }));
+        }
+        Ok(())
+    }
+
+    /// Checks that the registered pool `pool_id` holds no reserve of either
+    /// currency, so its reserves can still be seeded.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolNotEmpty`] - If either reserve of the pool is nonzero.
+    pub(crate) fn check_empty(&self, pool_id: B256) -> Result<(), Error> {
+        let (reserve0, reserve1) = self.reserves_of(pool_id);
+        if !reserve0.is_zero() || !reserve1.is_zero() {
+            return Err(Error::PoolNotEmpty(PoolNotEmpty { pool_id }));
+        }
+        Ok(())
+    }
+
+    /// Returns the reserves of `currency0` and `currency1` in the pool
+    /// `pool_id`.
+    pub(crate) fn reserves_of(&self, pool_id: B256) -> (U256, U256) {
+        if pool_id == DEFAULT_POOL_ID {
+            return (self.reserve0.get(), self.reserve1.get());
+        }
+        let pool = self.pools.getter(pool_id);
+        (pool.reserve0.get(), pool.reserve1.get())
+    }
+
+    /// Stores the reserves of `currency0` and `currency1` in the pool
+    /// `pool_id`.
+    pub(crate) fn set_reserves_of(&mut self, pool_id: B256, reserve0: U256, reserve1: U256) {
+        if pool_id == DEFAULT_POOL_ID {
+            self.reserve0.set(reserve0);
+            self.reserve1.set(reserve1);
+            return;
+        }
+        let mut pool = self.pools.setter(pool_id);
+        pool.reserve0.set(reserve0);
+        pool.reserve1.set(reserve1);
+    }
+
+    /// Returns the swap fee of the pool `pool_id` in basis points.
+    pub(crate) fn fee_of(&self, pool_id: B256) -> u16 {
+        if pool_id == DEFAULT_POOL_ID {
+            return self.fee();
+        }
+        self.pools.getter(pool_id).fee_bps.get().to::<u16>()
+    }
+
+    /// Stores the swap fee of the registered pool `pool_id`.
+    pub(crate) fn set_fee_of(&mut self, pool_id: B256, fee_bps: u16) {
+        self.pools.setter(pool_id).fee_bps.set(U16::from(fee_bps));
+    }
+
+    /// Returns true if swaps in the registered pool `pool_id` are paused.
+    pub(crate) fn paused_of(&self, pool_id: B256) -> bool {
+        self.pools.getter(pool_id).paused.get()
+    }
+
+    /// Pauses or unpauses swaps in the registered pool `pool_id`.
+    pub(crate) fn set_paused_of(&mut self, pool_id: B256, paused: bool) {
+        self.pools.setter(pool_id).paused.set(paused);
+    }
+
+    /// Returns the maximum age of the oracle price for the pool `pool_id`,
+    /// or zero if the pool is not pegged to the oracle.
+    pub(crate) fn max_price_age_of(&self, pool_id: B256) -> u64 {
+        self.pools.getter(pool_id).max_price_age.get().to::<u64>()
+    }
+
+    /// Stores the maximum age of the oracle price for the registered pool
+    /// `pool_id`.
+    pub(crate) fn set_max_price_age_of(&mut self, pool_id: B256, max_price_age: u64) {
+        self.pools
+            .setter(pool_id)
+            .max_price_age
+            .set(U64::from(max_price_age));
+    }
+
+    /// Checks that swaps are paused neither contract-wide nor in the pool
+    /// `pool_id`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ContractPaused`] - If swaps are paused contract-wide.
+    /// * [`Error::PoolPaused`] - If swaps in the pool are paused.
+    pub(crate) fn check_pool_not_paused(&self, pool_id: B256) -> Result<(), Error> {
+        if self.paused.get() {
+            return Err(Error::ContractPaused(ContractPaused {}));
+        }
+        if pool_id != DEFAULT_POOL_ID && self.paused_of(pool_id) {
+            return Err(Error::PoolPaused(PoolPaused { pool_id }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{aliases::U160, I256};
+    use motsu::prelude::Funding;
+    use stylus_sdk::abi::Bytes;
+
+    use super::*;
+    use crate::{
+        currency, hooks,
+        testing::{
+            deploy_multi_pool, hook, pool_key, CURRENCY_1, CURRENCY_2, HOOK, POOLS, POOL_MANAGER,
+            RESERVE,
+        },
+        FeeCharged, InsufficientLiquidity, NativeValueMismatch, SwapParams, Unauthorized,
+        UnexpectedNativeValue, SQRT_PRICE_1_1,
+    };
+
+    #[motsu::test]
+    fn multi_pool_fixture_keeps_pools_apart(alice: Address, bob: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        assert_eq!(
+            (RESERVE, RESERVE),
+            contract
+                .sender(alice)
+                .get_reserves()
+                .expect("should read reserves")
+        );
+        for (pool_id, (currency0, currency1, fee)) in pool_ids.iter().zip(POOLS) {
+            assert_eq!(
+                (currency0, currency1, fee, I24::ONE),
+                contract.sender(alice).pool_key(*pool_id)
+            );
+        }
+
+        // `bob` swaps 1_000 `currency0` for 1_000 `currency1` in the first
+        // pool only.
+        let (currency0, currency1, fee) = POOLS[0];
+        contract
+            .sender(POOL_MANAGER)
+            .after_swap(
+                bob,
+                pool_key(currency0, currency1, fee),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified: I256::try_from(-1_000).expect("should fit"),
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                hooks::to_balance_delta(-1_000, 1_000),
+                Bytes::default(),
+            )
+            .expect("should account swap");
+        assert_eq!(
+            (uint!(1_000_U256), uint!(1_000_U256)),
+            contract.sender(alice).volume(pool_ids[0])
+        );
+        for pool_id in &pool_ids[1..] {
+            assert_eq!(
+                (U256::ZERO, U256::ZERO),
+                contract.sender(alice).volume(*pool_id)
+            );
+        }
+    }
+
+    #[motsu::test]
+    fn pools_keep_their_own_state(alice: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let swap = |index: usize| {
+            let (currency0, currency1, fee) = POOLS[index];
+            contract.swap(
+                alice,
+                pool_key(currency0, currency1, fee),
+                SwapParams {
+                    zero_for_one: true,
+                    amount_specified: I256::try_from(-10_000).expect("should fit"),
+                    sqrt_price_limit_x96: U160::ZERO,
+                },
+                Bytes::default(),
+            )
+        };
+
+        contract
+            .sender(alice)
+            .set_pool_fee(pool_ids[1], 100)
+            .expect("should set pool fee");
+        contract.assert_emitted(&PoolFeeSet {
+            op_seq: 4,
+            pool_id: pool_ids[1],
+            fee_bps: 100,
+        });
+        assert_eq!(100, contract.sender(alice).pool_fee(pool_ids[1]));
+        assert_eq!(0, contract.sender(alice).pool_fee(pool_ids[0]));
+
+        // The fee of the second pool is charged on its swaps only.
+        let (_, delta, _) = swap(0).expect("should swap in the first pool");
+        assert_eq!(-10_000, hooks::unspecified_delta(delta));
+        let (_, delta, _) = swap(1).expect("should swap in the second pool");
+        assert_eq!(-9_900, hooks::unspecified_delta(delta));
+        contract.assert_emitted(&FeeCharged {
+            currency: POOLS[1].0,
+            fee: uint!(100_U256),
+        });
+        assert_eq!(
+            (RESERVE + uint!(10_000_U256), RESERVE - uint!(9_900_U256)),
+            contract
+                .sender(alice)
+                .pool_reserves(pool_ids[1])
+                .expect("should read pool reserves")
+        );
+        assert_eq!(
+            (RESERVE, RESERVE),
+            contract
+                .sender(alice)
+                .pool_reserves(pool_ids[2])
+                .expect("should read pool reserves")
+        );
+
+        contract
+            .sender(alice)
+            .set_pool_paused(pool_ids[2], true)
+            .expect("should pause pool");
+        contract.assert_emitted(&PoolPausedSet {
+            op_seq: 5,
+            pool_id: pool_ids[2],
+            paused: true,
+        });
+        let err = swap(2).expect_err("should reject swaps in a paused pool");
+        assert!(matches!(
+            err,
+            Error::PoolPaused(PoolPaused { pool_id }) if pool_id == pool_ids[2]
+        ));
+        swap(0).expect("should swap in an unpaused pool");
+
+        let err = contract
+            .sender(alice)
+            .set_pool_reserves(B256::ZERO, RESERVE, RESERVE)
+            .expect_err("should reject an unregistered pool");
+        assert!(matches!(err, Error::PoolNotRegistered(_)));
+        let err = contract
+            .sender(alice)
+            .set_pool_reserves(pool_ids[0], RESERVE, U256::ONE)
+            .expect_err("should reject a seeded pool");
+        assert!(matches!(
+            err,
+            Error::PoolNotEmpty(PoolNotEmpty { pool_id }) if pool_id == pool_ids[0]
+        ));
+    }
+
+    #[motsu::test]
+    fn deposits_check_native_value(alice: Address, bob: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let key = pool_key(currency::NATIVE, CURRENCY_1, uint!(500_U24));
+        let native_pool = pool_id(&key);
+        contract
+            .sender(POOL_MANAGER)
+            .before_initialize(POOL_MANAGER, key, SQRT_PRICE_1_1)
+            .expect("should register native pool");
+        let amount = uint!(100_U256);
+        alice.fund(uint!(1_000_U256));
+        contract.mint(CURRENCY_1, alice, amount * uint!(2_U256));
+        contract.mint(CURRENCY_2, alice, amount);
+
+        let err = contract
+            .sender(bob)
+            .deposit(native_pool, amount, amount)
+            .expect_err("should reject non-owner");
+        assert!(matches!(err, Error::Unauthorized(_)));
+
+        // Pools of ERC-20 tokens reject any value.
+        let err = contract
+            .sender_and_value(alice, amount)
+            .deposit(pool_ids[0], amount, amount)
+            .expect_err("should reject value for ERC-20 pool");
+        assert!(matches!(
+            err,
+            Error::UnexpectedNativeValue(UnexpectedNativeValue { pool_id, value })
+                if pool_id == pool_ids[0] && value == amount
+        ));
+        contract
+            .sender(alice)
+            .deposit(pool_ids[0], amount, amount)
+            .expect("should deposit ERC-20 tokens");
+        assert_eq!(
+            (RESERVE + amount, RESERVE + amount),
+            contract
+                .sender(alice)
+                .pool_reserves(pool_ids[0])
+                .expect("should read pool reserves")
+        );
+        assert_eq!(U256::ZERO, contract.balance(CURRENCY_2, alice));
+        let err = contract
+            .sender(alice)
+            .deposit(pool_ids[0], U256::MAX, U256::ZERO)
+            .expect_err("should not overflow the reserve");
+        assert!(matches!(err, Error::AmountOverflow(_)));
+
+        // The value pays exactly the native amount.
+        let err = contract
+            .sender_and_value(alice, amount - U256::ONE)
+            .deposit(native_pool, amount, amount)
+            .expect_err("should reject short value");
+        assert!(matches!(
+            err,
+            Error::NativeValueMismatch(NativeValueMismatch { expected, value })
+                if expected == amount && value == amount - U256::ONE
+        ));
+        contract
+            .sender_and_value(alice, amount)
+            .deposit(native_pool, amount, amount)
+            .expect("should deposit native currency");
+        assert_eq!(
+            (amount, amount),
+            contract
+                .sender(alice)
+                .pool_reserves(native_pool)
+                .expect("should read pool reserves")
+        );
+        assert_eq!(amount, contract.balance(currency::NATIVE, HOOK));
+        assert_eq!(U256::ZERO, contract.balance(CURRENCY_1, alice));
+
+        let err = contract
+            .sender(alice)
+            .withdraw(native_pool, amount + U256::ONE, U256::ZERO, bob)
+            .expect_err("should not exceed the reserve");
+        assert!(matches!(err, Error::InsufficientLiquidity(_)));
+        contract
+            .sender(alice)
+            .withdraw(native_pool, amount, U256::ZERO, bob)
+            .expect("should withdraw native currency");
+        assert_eq!(
+            (U256::ZERO, amount),
+            contract
+                .sender(alice)
+                .pool_reserves(native_pool)
+                .expect("should read pool reserves")
+        );
+        assert_eq!(amount, contract.balance(currency::NATIVE, bob));
+        assert_eq!(U256::ZERO, contract.balance(currency::NATIVE, HOOK));
+
+        // Only the pool manager sends native currency outside deposits.
+        assert!(contract.sender(bob).receive().is_err());
+        assert!(contract.sender(POOL_MANAGER).receive().is_ok());
+    }
+
+    #[motsu::test]
+    fn get_price_scales_exchange_rate(alice: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let (currency0, currency1, _) = POOLS[0];
+        assert_eq!(
+            math::WAD,
+            contract
+                .sender(alice)
+                .get_price(pool_ids[0])
+                .expect("should price pool")
+        );
+
+        contract
+            .sender(alice)
+            .set_exchange_rate(currency1, currency0, uint!(3_U256), uint!(2_U256))
+            .expect("should set exchange rate");
+        // The rate is quoted from `currency1`, so `currency0` is worth 2/3.
+        assert_eq!(
+            uint!(666_666_666_666_666_666_U256),
+            contract
+                .sender(alice)
+                .get_price(pool_ids[0])
+                .expect("should price pool")
+        );
+        // Reserves do not move the price of a constant-sum pool.
+        contract
+            .sender(alice)
+            .withdraw(pool_ids[0], U256::ZERO, RESERVE - U256::ONE, alice)
+            .expect("should withdraw");
+        assert_eq!(
+            uint!(666_666_666_666_666_666_U256),
+            contract
+                .sender(alice)
+                .get_price(pool_ids[0])
+                .expect("should price pool")
+        );
+
+        let err = contract
+            .sender(alice)
+            .get_price(DEFAULT_POOL_ID)
+            .expect_err("should need the default currencies");
+        assert!(matches!(err, Error::PoolNotRegistered(_)));
+        contract
+            .sender(alice)
+            .set_currencies(currency0, currency1)
+            .expect("should set currencies");
+        assert_eq!(
+            uint!(666_666_666_666_666_666_U256),
+            contract
+                .sender(alice)
+                .get_price(DEFAULT_POOL_ID)
+                .expect("should price default pool")
+        );
+        let err = contract
+            .sender(alice)
+            .get_price(B256::repeat_byte(1))
+            .expect_err("should reject an unregistered pool");
+        assert!(matches!(err, Error::PoolNotRegistered(_)));
+    }
+}
@@ -0,0 +1,367 @@
+//! Bindings to the Uniswap V4 `PoolManager`, used to move the swapped
+//! currencies and to run the hook's own pool operations.
+//!
+//! `beforeSwap` returns a delta taking the whole swap from the pool, so the
+//! hook must clear it within the same unlock: it takes the input currency
+//! from the pool manager, and pays the output currency by syncing, sending
+//! the tokens and settling.
+//!
+//! The owner originates pool operations through `unlockPoolManager`: the
+//! hook unlocks the pool manager, which calls back `unlockCallback` with an
+//! [`UnlockAction`]. The action runs against the pool manager, and its delta
+//! is cleared against the reserves of the pool before the unlock returns.
+//!
+//! In claims mode, the hook receives currencies as ERC-6909 claims minted by
+//! the pool manager, and tracks its claim balance per currency. Payments
+//! burn claims first, and send ERC-20 tokens for the rest. Native ETH is
+//! paid as the value of `settle` instead, and taken through the hook's
+//! `receive` entrypoint.
+use alloy_primitives::{Address, I256, U256};
+use alloy_sol_types::{sol, SolCall, SolValue};
+use stylus_sdk::{abi::Bytes, call::Call, evm, function_selector, prelude::*};
+
+use crate::{
+    currency::{self, IERC20},
+    hooks, pool, ConstantSumCurve, Currency, Error, InsufficientLiquidity, InvalidLiquidityDelta,
+    InvalidUnlockAction, PoolManagerCallFailed, SettlementFailed, UnlockActionExecuted,
+    UnlockCallbackData,
+};
+
+sol_interface! {
+    /// Subset of Uniswap V4's `IPoolManager` used by the hook.
+    interface IPoolManager {
+        function unlock(bytes data) external returns (bytes result);
+        function take(address currency, address to, uint256 amount) external;
+        function settle() external payable returns (uint256 paid);
+        function sync(address currency) external;
+        function mint(address to, uint256 id, uint256 amount) external;
+        function burn(address from, uint256 id, uint256 amount) external;
+    }
+}
+
+sol! {
+    /// Pool operations of Uniswap V4's `IPoolManager`, encoded by hand as
+    /// they take structs. Tuples are `PoolKey` and `ModifyLiquidityParams`.
+    interface IPoolManagerOperations {
+        function modifyLiquidity(
+            (address,address,uint24,int24,address) key,
+            (int24,int24,int256,bytes32) params,
+            bytes hookData
+        ) external returns (int256 callerDelta, int256 feesAccrued);
+        function donate(
+            (address,address,uint24,int24,address) key,
+            uint256 amount0,
+            uint256 amount1,
+            bytes hookData
+        ) external returns (int256 delta);
+    }
+}
+
+/// Pool operation run by the hook in `unlockCallback`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum UnlockAction {
+    /// Adds the positive `liquidity_delta` of the callback's parameters.
+    AddLiquidity = 0,
+    /// Removes the negative `liquidity_delta` of the callback's parameters.
+    RemoveLiquidity = 1,
+    /// Donates `amount0` and `amount1` to the pool's in-range liquidity.
+    Donate = 2,
+}
+
+impl TryFrom<u8> for UnlockAction {
+    type Error = Error;
+
+    fn try_from(action: u8) -> Result<Self, Self::Error> {
+        match action {
+            0 => Ok(Self::AddLiquidity),
+            1 => Ok(Self::RemoveLiquidity),
+            2 => Ok(Self::Donate),
+            _ => Err(Error::InvalidUnlockAction(InvalidUnlockAction { action })),
+        }
+    }
+}
+
+impl UnlockAction {
+    /// Checks that `data` is valid for the action.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidLiquidityDelta`] - If the liquidity delta does not
+    ///   add liquidity for [`UnlockAction::AddLiquidity`], or does not remove
+    ///   any for [`UnlockAction::RemoveLiquidity`].
+    pub(crate) fn check(self, data: &UnlockCallbackData) -> Result<(), Error> {
+        let liquidity_delta = data.params.liquidity_delta;
+        let valid = match self {
+            Self::AddLiquidity => liquidity_delta.is_positive(),
+            Self::RemoveLiquidity => liquidity_delta.is_negative(),
+            Self::Donate => true,
+        };
+        if !valid {
+            return Err(Error::InvalidLiquidityDelta(InvalidLiquidityDelta {
+                liquidity_delta,
+            }));
+        }
+        Ok(())
+    }
+}
+
+impl ConstantSumCurve {
+    /// Clears the delta of a swap with the pool manager: takes `amount_in`
+    /// of `input` to the hook, and pays `amount_out` of `output`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::SettlementFailed`] - If a call to the pool manager or the
+    ///   transfer of `output` fails.
+    pub(crate) fn settle_swap(
+        &mut self,
+        input: Currency,
+        output: Currency,
+        amount_in: U256,
+        amount_out: U256,
+    ) -> Result<(), Error> {
+        self.take_currency(input, amount_in)?;
+        self.pay_currency(output, amount_out)
+    }
+
+    /// Unlocks the pool manager with `data`, so that it calls back
+    /// `unlockCallback` to run the pool operation, and returns the
+    /// operation's delta.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolManagerCallFailed`] - If the pool manager or the
+    ///   callback reverts, or the callback returns no delta.
+    pub(crate) fn unlock(&mut self, data: UnlockCallbackData) -> Result<I256, Error> {
+        let failed = || {
+            Error::PoolManagerCallFailed(PoolManagerCallFailed {
+                selector: function_selector!("unlock", Bytes).into(),
+            })
+        };
+        let manager = IPoolManager::new(self.pool_manager.get());
+        let call = Call::new_mutating(self);
+        let returned = manager
+            .unlock(self.vm(), call, Bytes::from(data.abi_encode()))
+            .map_err(|_| failed())?;
+        I256::abi_decode(&returned, true).map_err(|_| failed())
+    }
+
+    /// Runs `action` with `data` against the pool manager, clears its delta
+    /// and returns it.
+    ///
+    /// The hook pays what it owes from the reserves of the pool, and adds
+    /// what it is owed to them.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolManagerCallFailed`] - If the pool manager rejects the
+    ///   action.
+    /// * [`Error::InsufficientLiquidity`] - If the reserves of the pool cannot
+    ///   cover the delta.
+    /// * [`Error::SettlementFailed`] - If the delta cannot be cleared.
+    ///
+    /// # Events
+    ///
+    /// * [`UnlockActionExecuted`].
+    pub(crate) fn execute_unlock(
+        &mut self,
+        action: UnlockAction,
+        data: UnlockCallbackData,
+    ) -> Result<I256, Error> {
+        let pool_id = pool::pool_id(&data.key);
+        let (currency0, currency1) = (data.key.currency0, data.key.currency1);
+        let (selector, calldata) = match action {
+            UnlockAction::AddLiquidity | UnlockAction::RemoveLiquidity => {
+                let call = IPoolManagerOperations::modifyLiquidityCall {
+                    key: data.key.into(),
+                    params: data.params.into(),
+                    hookData: Default::default(),
+                };
+                (
+                    IPoolManagerOperations::modifyLiquidityCall::SELECTOR,
+                    call.abi_encode(),
+                )
+            }
+            UnlockAction::Donate => {
+                let call = IPoolManagerOperations::donateCall {
+                    key: data.key.into(),
+                    amount0: data.amount0,
+                    amount1: data.amount1,
+                    hookData: Default::default(),
+                };
+                (
+                    IPoolManagerOperations::donateCall::SELECTOR,
+                    call.abi_encode(),
+                )
+            }
+        };
+        let failed = || {
+            Error::PoolManagerCallFailed(PoolManagerCallFailed {
+                selector: selector.into(),
+            })
+        };
+
+        let manager = self.pool_manager.get();
+        let call = Call::new_mutating(self);
+        let returned = self
+            .vm()
+            .call(&call, manager, &calldata)
+            .map_err(|_| failed())?;
+        let delta = match action {
+            UnlockAction::AddLiquidity | UnlockAction::RemoveLiquidity => {
+                IPoolManagerOperations::modifyLiquidityCall::abi_decode_returns(&returned, true)
+                    .map_err(|_| failed())?
+                    .callerDelta
+            }
+            UnlockAction::Donate => {
+                IPoolManagerOperations::donateCall::abi_decode_returns(&returned, true)
+                    .map_err(|_| failed())?
+                    .delta
+            }
+        };
+
+        let (amount0, amount1) = hooks::balance_delta_amounts(delta);
+        let (reserve0, reserve1) = self.reserves_of(pool_id);
+        let reserve0 = Self::apply_delta(reserve0, amount0)?;
+        let reserve1 = Self::apply_delta(reserve1, amount1)?;
+        self.set_reserves_of(pool_id, reserve0, reserve1);
+        self.clear_delta(currency0, amount0)?;
+        self.clear_delta(currency1, amount1)?;
+
+        #[allow(deprecated)]
+        evm::log(UnlockActionExecuted {
+            pool_id,
+            action: action as u8,
+            delta,
+        });
+
+        Ok(delta)
+    }
+
+    /// Returns `reserve` after a delta of `amount` owed to the hook, or paid
+    /// by it if negative.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InsufficientLiquidity`] - If `reserve` cannot pay `amount`.
+    fn apply_delta(reserve: U256, amount: i128) -> Result<U256, Error> {
+        let magnitude = U256::from(amount.unsigned_abs());
+        if amount >= 0 {
+            return Ok(reserve.saturating_add(magnitude));
+        }
+        reserve
+            .checked_sub(magnitude)
+            .ok_or(Error::InsufficientLiquidity(InsufficientLiquidity {
+                available: reserve,
+                requested: magnitude,
+            }))
+    }
+
+    /// Clears a delta of `amount` of `currency` with the pool manager,
+    /// taking it if positive and paying it if negative.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::SettlementFailed`] - If the delta cannot be cleared.
+    fn clear_delta(&mut self, currency: Currency, amount: i128) -> Result<(), Error> {
+        let magnitude = U256::from(amount.unsigned_abs());
+        if amount >= 0 {
+            self.take_currency(currency, magnitude)
+        } else {
+            self.pay_currency(currency, magnitude)
+        }
+    }
+
+    /// Takes `amount` of `currency` from the pool manager to the hook, as
+    /// ERC-6909 claims in claims mode.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::SettlementFailed`] - If the pool manager rejects the take.
+    pub(crate) fn take_currency(&mut self, currency: Currency, amount: U256) -> Result<(), Error> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let claims = self.claims_mode.get();
+        if claims {
+            let balance = self.claim_balances.get(currency);
+            self.claim_balances
+                .setter(currency)
+                .set(balance.saturating_add(amount));
+        }
+
+        let manager = IPoolManager::new(self.pool_manager.get());
+        let hook = self.vm().contract_address();
+        let call = Call::new_mutating(self);
+        let taken = if claims {
+            manager.mint(self.vm(), call, hook, claim_id(currency), amount)
+        } else {
+            manager.take(self.vm(), call, currency, hook, amount)
+        };
+        taken.map_err(|_| settlement_failed(currency))?;
+        Ok(())
+    }
+
+    /// Pays `amount` of `currency` to the pool manager, burning the hook's
+    /// ERC-6909 claims first and paying the rest in ERC-20 tokens by
+    /// syncing, sending the tokens and settling. The rest of native ETH is
+    /// sent as the value of `settle`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::SettlementFailed`] - If a call to the pool manager or the
+    ///   transfer fails.
+    fn pay_currency(&mut self, currency: Currency, amount: U256) -> Result<(), Error> {
+        let balance = self.claim_balances.get(currency);
+        let burned = balance.min(amount);
+        if !burned.is_zero() {
+            self.claim_balances.setter(currency).set(balance - burned);
+        }
+
+        let manager_address = self.pool_manager.get();
+        let manager = IPoolManager::new(manager_address);
+        if !burned.is_zero() {
+            let hook = self.vm().contract_address();
+            let call = Call::new_mutating(self);
+            manager
+                .burn(self.vm(), call, hook, claim_id(currency), burned)
+                .map_err(|_| settlement_failed(currency))?;
+        }
+        let sent = amount - burned;
+        if !sent.is_zero() && currency::is_native(currency) {
+            let call = Call::new_payable(self, sent);
+            manager
+                .settle(self.vm(), call)
+                .map_err(|_| settlement_failed(currency))?;
+        } else if !sent.is_zero() {
+            let call = Call::new_mutating(self);
+            manager
+                .sync(self.vm(), call, currency)
+                .map_err(|_| settlement_failed(currency))?;
+            let call = Call::new_mutating(self);
+            let transferred = IERC20::new(currency)
+                .transfer(self.vm(), call, manager_address, sent)
+                .map_err(|_| settlement_failed(currency))?;
+            if !transferred {
+                return Err(settlement_failed(currency));
+            }
+            let call = Call::new_mutating(self);
+            manager
+                .settle(self.vm(), call)
+                .map_err(|_| settlement_failed(currency))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the ERC-6909 id of the pool manager's claims of `currency`, i.e.
+/// the currency's address as an integer.
+pub fn claim_id(currency: Currency) -> U256 {
+    U256::from_be_slice(currency.as_slice())
+}
+
+/// Returns [`Error::SettlementFailed`] for `currency`.
+fn settlement_failed(currency: Address) -> Error {
+    Error::SettlementFailed(SettlementFailed { currency })
+}
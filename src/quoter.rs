@@ -0,0 +1,319 @@
+//! Quotes compatible with Uniswap's `IQuoterV2`.
+//!
+//! Front-end SDKs quote v4 pools through `quoteExactInputSingle` and
+//! `quoteExactOutputSingle`. The hook answers the same calls for its pools,
+//! found by their pair and fee tier, and `quoteValidUntil` tells for how
+//! long a quote holds.
+use alloy_primitives::{aliases::U160, U256};
+use stylus_sdk::prelude::*;
+
+use crate::{
+    bootstrap::Phase, pool::DEFAULT_POOL_ID, ConstantSumCurve, Error, QuoteExactInputSingleParams,
+    QuoteExactOutputSingleParams,
+};
+
+/// Result of a QuoterV2-style quote: the quoted amount, the square root of
+/// the price after the swap, the number of initialized ticks crossed and the
+/// gas estimate.
+pub type QuoteResult = (U256, U160, u32, U256);
+
+/// Interface of the QuoterV2-compatible quotes.
+pub trait IQuoter {
+    /// The error type associated to the trait implementation.
+    type Error: Into<alloc::vec::Vec<u8>>;
+
+    /// Returns the amount of output tokens for an exact-input swap, with the
+    /// same signature as Uniswap's `IQuoterV2.quoteExactInputSingle`, so
+    /// front-end SDKs can quote against the curve without adapters.
+    ///
+    /// The pool is the registered pool of the pair with `fee`, or the default
+    /// pool if `fee` is zero. As the curve trades at a constant exchange
+    /// rate, `sqrt_price_limit_x96` is ignored, the price after the swap is
+    /// the square root of the exchange rate and no ticks are crossed. See
+    /// [`Self::quote_valid_until`] for how long the quote holds.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `params` - The quote parameters.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolNotFound`] - If no pool of the pair with `fee` is
+    ///   registered.
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::PoolEmpty`] - If both reserves of the pool are zero.
+    /// * [`Error::PriceTooOld`] - If the pool is pegged to the oracle and its
+    ///   price is too old.
+    /// * [`Error::ContractPaused`] - If swaps are paused.
+    /// * [`Error::PoolPaused`] - If swaps in the pool are paused.
+    /// * [`Error::InvalidTokenPair`] - If both tokens are the same.
+    /// * [`Error::ZeroAmount`] - If the amount is zero.
+    /// * [`Error::CurrencyNotAllowed`] - If either token cannot be swapped.
+    /// * [`Error::AmountTooSmall`] - If the amount is below the minimum swap
+    ///   amount.
+    /// * [`Error::InsufficientLiquidity`] - If a registered pool's output
+    ///   reserve is smaller than the quoted amount.
+    /// * [`Error::AmountOverflow`] - If the quoted amount or price does not
+    ///   fit into its type.
+    fn quote_exact_input_single(
+        &self,
+        params: QuoteExactInputSingleParams,
+    ) -> Result<QuoteResult, Self::Error>;
+
+    /// Returns the amount of input tokens for an exact-output swap, with the
+    /// same signature as Uniswap's `IQuoterV2.quoteExactOutputSingle`, so
+    /// front-end SDKs can quote against the curve without adapters.
+    ///
+    /// The pool is the registered pool of the pair with `fee`, or the default
+    /// pool if `fee` is zero. As the curve trades at a constant exchange
+    /// rate, `sqrt_price_limit_x96` is ignored, the price after the swap is
+    /// the square root of the exchange rate and no ticks are crossed. See
+    /// [`Self::quote_valid_until`] for how long the quote holds.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `params` - The quote parameters.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::PoolNotFound`] - If no pool of the pair with `fee` is
+    ///   registered.
+    /// * [`Error::ReadOnlyReentrancy`] - If an operation holds the lock.
+    /// * [`Error::ContractDecommissioned`] - If the contract is
+    ///   decommissioned.
+    /// * [`Error::PoolEmpty`] - If both reserves of the pool are zero.
+    /// * [`Error::PriceTooOld`] - If the pool is pegged to the oracle and its
+    ///   price is too old.
+    /// * [`Error::ContractPaused`] - If swaps are paused.
+    /// * [`Error::PoolPaused`] - If swaps in the pool are paused.
+    /// * [`Error::InvalidTokenPair`] - If both tokens are the same.
+    /// * [`Error::ZeroAmount`] - If the amount is zero.
+    /// * [`Error::CurrencyNotAllowed`] - If either token cannot be swapped.
+    /// * [`Error::AmountTooSmall`] - If the amount is below the minimum swap
+    ///   amount.
+    /// * [`Error::InsufficientLiquidity`] - If the output reserve of the pool
+    ///   is smaller than `amount`.
+    /// * [`Error::AmountOverflow`] - If the quoted amount or price does not
+    ///   fit into its type.
+    fn quote_exact_output_single(
+        &self,
+        params: QuoteExactOutputSingleParams,
+    ) -> Result<QuoteResult, Self::Error>;
+
+    /// Returns the last timestamp a quote made now can be relied upon: the
+    /// current block while the bootstrap auction moves the price every
+    /// second, and at most until the aggregate oracle price goes stale.
+    fn quote_valid_until(&self) -> u64;
+}
+
+#[public]
+impl IQuoter for ConstantSumCurve {
+    type Error = Error;
+
+    fn quote_exact_input_single(
+        &self,
+        params: QuoteExactInputSingleParams,
+    ) -> Result<QuoteResult, Self::Error> {
+        let gas_left = self.vm().evm_gas_left();
+        let QuoteExactInputSingleParams {
+            token_in,
+            token_out,
+            amount_in,
+            fee,
+            ..
+        } = params;
+        let pool_id = self.resolve_pool(token_in, token_out, fee)?;
+        self.check_quote(pool_id, token_in, token_out, amount_in)?;
+
+        let zero_for_one = token_in < token_out;
+        let (amount_out, _) =
+            self.calculate_amount_out(pool_id, amount_in, token_in, token_out, zero_for_one)?;
+        if pool_id != DEFAULT_POOL_ID {
+            let (_, reserve_out) = self.directed_reserves(pool_id, token_in, token_out);
+            Self::check_liquidity(reserve_out, amount_out)?;
+        }
+        let sqrt_price_x96 = if zero_for_one {
+            self.sqrt_price_x96(token_in, token_out)?
+        } else {
+            self.sqrt_price_x96(token_out, token_in)?
+        };
+        let gas_estimate = U256::from(gas_left - self.vm().evm_gas_left());
+
+        Ok((amount_out, sqrt_price_x96, 0, gas_estimate))
+    }
+
+    fn quote_exact_output_single(
+        &self,
+        params: QuoteExactOutputSingleParams,
+    ) -> Result<QuoteResult, Self::Error> {
+        let gas_left = self.vm().evm_gas_left();
+        let QuoteExactOutputSingleParams {
+            token_in,
+            token_out,
+            amount,
+            fee,
+            ..
+        } = params;
+        let pool_id = self.resolve_pool(token_in, token_out, fee)?;
+        self.check_quote(pool_id, token_in, token_out, amount)?;
+        let (_, reserve_out) = self.directed_reserves(pool_id, token_in, token_out);
+        Self::check_liquidity(reserve_out, amount)?;
+
+        let zero_for_one = token_in < token_out;
+        let (amount_in, _) =
+            self.calculate_amount_in(pool_id, amount, token_in, token_out, zero_for_one)?;
+        let sqrt_price_x96 = if zero_for_one {
+            self.sqrt_price_x96(token_in, token_out)?
+        } else {
+            self.sqrt_price_x96(token_out, token_in)?
+        };
+        let gas_estimate = U256::from(gas_left - self.vm().evm_gas_left());
+
+        Ok((amount_in, sqrt_price_x96, 0, gas_estimate))
+    }
+
+    fn quote_valid_until(&self) -> u64 {
+        let mut valid_until = u64::MAX;
+        if self.current_phase() == Phase::Bootstrapping {
+            valid_until = self.vm().block_timestamp();
+        }
+        let price_timestamp = self.price_timestamp.get().to::<u64>();
+        if price_timestamp != 0 {
+            let stale_after = price_timestamp.saturating_add(self.max_price_age.get().to::<u64>());
+            valid_until = valid_until.min(stale_after);
+        }
+        valid_until
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{
+        aliases::{U24, U64},
+        uint, Address,
+    };
+
+    use super::*;
+    use crate::{
+        math,
+        oracle::IOracle,
+        pool::IPool,
+        testing::{deploy, deploy_multi_pool, hook, seed_liquidity, POOLS, RESERVE},
+        InsufficientLiquidity, InvalidTokenPair, PoolNotFound, PoolPaused, ZeroAmount,
+        SQRT_PRICE_1_1,
+    };
+
+    #[motsu::test]
+    fn quotes_like_quoter_v2(alice: Address) {
+        let contract = hook();
+        let pool_ids = deploy_multi_pool(&contract, alice);
+        let (currency0, currency1, fee) = POOLS[0];
+        let quote_in = |token_in: Address, amount_in: U256, fee: U24| {
+            contract
+                .sender(alice)
+                .quote_exact_input_single(QuoteExactInputSingleParams {
+                    token_in,
+                    token_out: currency1,
+                    amount_in,
+                    fee,
+                    sqrt_price_limit_x96: U160::ZERO,
+                })
+        };
+        let amount = uint!(5_000_U256);
+
+        // A zero fee quotes the default pool.
+        let (amount_out, sqrt_price_x96_after, initialized_ticks_crossed, _) =
+            quote_in(currency0, amount, U24::ZERO).expect("should quote `amount_out`");
+        assert_eq!(amount, amount_out);
+        assert_eq!(SQRT_PRICE_1_1, sqrt_price_x96_after);
+        assert_eq!(0, initialized_ticks_crossed);
+
+        // The fee tier picks the registered pool, with its own swap fee.
+        contract
+            .sender(alice)
+            .set_pool_fee(pool_ids[0], 100)
+            .expect("should set pool fee");
+        let (amount_out, ..) = quote_in(currency0, amount, fee).expect("should quote `amount_out`");
+        assert_eq!(uint!(4_950_U256), amount_out);
+        let (amount_in, ..) = contract
+            .sender(alice)
+            .quote_exact_output_single(QuoteExactOutputSingleParams {
+                token_in: currency1,
+                token_out: currency0,
+                amount,
+                fee,
+                sqrt_price_limit_x96: U160::ZERO,
+            })
+            .expect("should quote `amount_in`");
+        // 5000 plus a fee of 1% of the input, rounded up.
+        assert_eq!(uint!(5_051_U256), amount_in);
+        let err = contract
+            .sender(alice)
+            .quote_exact_output_single(QuoteExactOutputSingleParams {
+                token_in: currency1,
+                token_out: currency0,
+                amount: RESERVE + U256::ONE,
+                fee,
+                sqrt_price_limit_x96: U160::ZERO,
+            })
+            .expect_err("should not quote beyond the reserve");
+        assert!(matches!(err, Error::InsufficientLiquidity(_)));
+
+        let err =
+            quote_in(currency0, amount, uint!(100_U24)).expect_err("should need a registered pool");
+        assert!(matches!(
+            err,
+            Error::PoolNotFound(PoolNotFound { currency0: c0, currency1: c1, fee })
+                if c0 == currency0 && c1 == currency1 && fee == uint!(100_U24)
+        ));
+        let err =
+            quote_in(currency1, amount, U24::ZERO).expect_err("should reject identical tokens");
+        assert!(matches!(err, Error::InvalidTokenPair(_)));
+        let err = quote_in(currency0, U256::ZERO, fee).expect_err("should reject a zero amount");
+        assert!(matches!(err, Error::ZeroAmount(_)));
+
+        // Quotes revert wherever the swap would.
+        contract
+            .sender(alice)
+            .set_pool_paused(pool_ids[0], true)
+            .expect("should pause pool");
+        let err = quote_in(currency0, amount, fee).expect_err("should reject a paused pool");
+        assert!(matches!(err, Error::PoolPaused(_)));
+        quote_in(currency0, amount, U24::ZERO).expect("should quote the default pool");
+    }
+
+    #[motsu::test]
+    fn quotes_expire_with_price_and_bootstrap(alice: Address) {
+        let contract = hook();
+        deploy(&contract, alice);
+        seed_liquidity(
+            &contract,
+            alice,
+            uint!(1_000_000_U256),
+            uint!(1_000_000_U256),
+        );
+        let now = contract.sender(alice).vm().block_timestamp();
+        let quote_valid_until = || contract.sender(alice).quote_valid_until();
+        assert_eq!(u64::MAX, quote_valid_until());
+
+        // The quote expires with the aggregate price.
+        contract
+            .sender(alice)
+            .set_max_price_age(600)
+            .expect("should set `max_price_age`");
+        contract.sender(alice).price_timestamp.set(U64::from(now));
+        assert_eq!(now + 600, quote_valid_until());
+
+        // The bootstrap auction moves the price every second.
+        let half = math::WAD / uint!(2_U256);
+        contract
+            .sender(alice)
+            .schedule_bootstrap(0, now + 1_000_000, half, half)
+            .expect("should schedule bootstrap");
+        assert_eq!(now, quote_valid_until());
+    }
+}
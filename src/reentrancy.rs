@@ -0,0 +1,70 @@
+//! Reentrancy lock of the operations and the price and reserve views.
+//!
+//! Swaps, deposits, withdrawals and pool operations call out to the pool
+//! manager and to tokens while the reserves are half-updated. These
+//! operations run with the lock held, in transient storage, so it is cleared
+//! at the end of the transaction whatever happens.
+//!
+//! Operations reentered while the lock is held revert with `Reentrancy`, so
+//! a token or pool manager callback cannot interleave two of them. A contract
+//! reentered from such a call could also read a price or reserves that no
+//! finished operation ever left, so the guarded views revert with
+//! `ReadOnlyReentrancy` meanwhile.
+use alloy_primitives::{b256, B256};
+use stylus_sdk::hostio;
+
+use crate::{ConstantSumCurve, Error, ReadOnlyReentrancy, Reentrancy};
+
+/// Transient storage slot of the lock,
+/// `keccak256("ConstantSumCurve.lock")`.
+const LOCK_SLOT: B256 = b256!("1701780e191e92e74ba2d798176bef12357e7e6b7cf5ab74d5fb76c6843d45a0");
+
+/// Value of the lock slot while an operation holds it.
+const LOCKED: B256 = b256!("0000000000000000000000000000000000000000000000000000000000000001");
+
+impl ConstantSumCurve {
+    /// Runs `f` with the lock held, and releases it afterwards.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Reentrancy`] - If an operation already holds the lock.
+    /// * Any error of `f`.
+    pub(crate) fn with_lock<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if self.locked() {
+            return Err(Error::Reentrancy(Reentrancy {}));
+        }
+        Self::store_lock(LOCKED);
+        let result = f(self);
+        Self::store_lock(B256::ZERO);
+        result
+    }
+
+    /// Checks that no operation holds the lock.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::ReadOnlyReentrancy`] - If the lock is held.
+    pub(crate) fn check_view_unlocked(&self) -> Result<(), Error> {
+        if self.locked() {
+            return Err(Error::ReadOnlyReentrancy(ReadOnlyReentrancy {}));
+        }
+        Ok(())
+    }
+
+    /// Returns whether an operation holds the lock.
+    pub(crate) fn locked(&self) -> bool {
+        let mut value = B256::ZERO;
+        // SAFETY: Both pointers point to 32 bytes.
+        unsafe { hostio::transient_load_bytes32(LOCK_SLOT.as_ptr(), value.as_mut_ptr()) };
+        value == LOCKED
+    }
+
+    /// Stores `value` in the lock slot.
+    fn store_lock(value: B256) {
+        // SAFETY: Both pointers point to 32 bytes.
+        unsafe { hostio::transient_store_bytes32(LOCK_SLOT.as_ptr(), value.as_ptr()) };
+    }
+}
@@ -0,0 +1,758 @@
+//! Scenario tests: long randomized sequences of interleaved operations on
+//! the pools of [`deploy_multi_pool`], checked against a model of the
+//! pools after every step.
+//!
+//! Operations are decoded from a seeded pseudo-random byte stream with
+//! [`arbitrary`], so a failing scenario replays deterministically from its
+//! seed. Besides swaps and pool settings, the owner deposits into and
+//! withdraws from the pools, adds and removes liquidity of the default pool,
+//! and calls the legacy `getAmount*` methods, with tokens minted to it. After
+//! every step, the reserves reported by the contract must match the model and
+//! the tokens held by the hook.
+//!
+//! Branching scenarios [`snapshot`] the contract, its mocks and the model,
+//! so several continuations can be played from the same state.
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use alloy_primitives::{aliases::U160, uint, Address, Bytes, B256, I256, U256};
+use arbitrary::{Arbitrary, Unstructured};
+
+use crate::{
+    hooks,
+    liquidity::{self, ILiquidity},
+    lp_token::ILpToken,
+    math,
+    pool::{self, IPool},
+    testing::{
+        deploy_multi_pool, hook, pool_key, restore, snapshot, Hook, CURRENCY_1, CURRENCY_2, HOOK,
+        POOLS, POOL_MANAGER, RESERVE,
+    },
+    ConstantSumCurve, Error, IUniswapV4Curve, InsufficientLiquidity, PoolEmpty, PoolPaused,
+    SwapParams,
+};
+
+/// Number of operations per scenario.
+const STEPS: usize = 2_000;
+
+/// An operation of a scenario. Pools are picked by index into [`POOLS`].
+#[derive(Arbitrary, Debug)]
+enum Op {
+    /// Swaps `amount + 1`, as an exact input or an exact output.
+    Swap {
+        pool: u8,
+        zero_for_one: bool,
+        exact_output: bool,
+        amount: u16,
+    },
+    /// Swaps one more token than the pool's output reserve.
+    Drain {
+        pool: u8,
+        zero_for_one: bool,
+        exact_output: bool,
+    },
+    /// Deposits `amount` of both currencies into a pool.
+    Deposit { pool: u8, amount: u16 },
+    /// Withdraws up to `amount` of both currencies from a pool.
+    Withdraw { pool: u8, amount: u16 },
+    /// Adds liquidity to the default pool.
+    AddLiquidity { amount0: u16, amount1: u16 },
+    /// Removes up to `shares` LP shares from the default pool.
+    RemoveLiquidity { shares: u16 },
+    /// Calls a legacy `getAmount*` method against the default pool.
+    LegacySwap {
+        zero_for_one: bool,
+        exact_output: bool,
+        amount: u16,
+    },
+    /// Sets the swap fee of a pool.
+    SetFee { pool: u8, fee_bps: u8 },
+    /// Pauses or unpauses swaps in a pool.
+    SetPaused { pool: u8, paused: bool },
+}
+
+/// Expected state of a pool.
+#[derive(Clone, Copy, Debug, Default)]
+struct PoolModel {
+    reserve0: U256,
+    reserve1: U256,
+    fee_bps: u16,
+    paused: bool,
+    volume0: U256,
+    volume1: U256,
+}
+
+/// Amounts of a currency flowing into and out of the pools.
+#[derive(Clone, Copy, Debug, Default)]
+struct Flows {
+    /// Reserves seeded by the deployment.
+    initial: U256,
+    /// Swap inputs, fees included, and deposits.
+    inflow: U256,
+    /// Swap outputs and withdrawals.
+    outflow: U256,
+}
+
+/// Expected state of the default pool and its LP shares.
+#[derive(Clone, Copy, Debug, Default)]
+struct LiquidityModel {
+    reserve0: U256,
+    reserve1: U256,
+    total_shares: U256,
+    /// Shares held by the owner.
+    shares: U256,
+}
+
+/// Expected state of the contract.
+#[derive(Clone)]
+struct Model {
+    pool_ids: Vec<B256>,
+    pools: [PoolModel; POOLS.len()],
+    liquidity: LiquidityModel,
+    flows: BTreeMap<Address, Flows>,
+}
+
+impl Model {
+    /// Returns the model of the deployment of [`deploy`].
+    fn new(pool_ids: Vec<B256>) -> Self {
+        let pool = PoolModel {
+            reserve0: RESERVE,
+            reserve1: RESERVE,
+            ..PoolModel::default()
+        };
+        let mut flows = BTreeMap::<Address, Flows>::new();
+        for (currency0, currency1, _) in POOLS {
+            flows.entry(currency0).or_default().initial += RESERVE;
+            flows.entry(currency1).or_default().initial += RESERVE;
+        }
+        for currency in [CURRENCY_1, CURRENCY_2] {
+            flows.entry(currency).or_default().initial += RESERVE;
+        }
+        Self {
+            pool_ids,
+            pools: [pool; POOLS.len()],
+            liquidity: LiquidityModel {
+                reserve0: RESERVE,
+                reserve1: RESERVE,
+                ..LiquidityModel::default()
+            },
+            flows,
+        }
+    }
+
+    /// Records `amount` of `currency` paid into the pools.
+    fn inflow(&mut self, currency: Address, amount: U256) {
+        self.flows.entry(currency).or_default().inflow += amount;
+    }
+
+    /// Records `amount` of `currency` paid out of the pools.
+    fn outflow(&mut self, currency: Address, amount: U256) {
+        self.flows.entry(currency).or_default().outflow += amount;
+    }
+}
+
+/// Returns `len` pseudo-random bytes derived from `seed` with xorshift64.
+fn entropy(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+/// Deploys the pools of [`deploy_multi_pool`] with the currencies of the
+/// default pool set to [`CURRENCY_1`] and [`CURRENCY_2`], and its seeded
+/// reserves backed by tokens minted to the hook, and returns their model.
+fn deploy(contract: &Hook, owner: Address) -> Model {
+    let model = Model::new(deploy_multi_pool(contract, owner));
+    contract
+        .sender(owner)
+        .set_currencies(CURRENCY_1, CURRENCY_2)
+        .expect("should set currencies");
+    contract.mint(CURRENCY_1, HOOK, RESERVE);
+    contract.mint(CURRENCY_2, HOOK, RESERVE);
+    model
+}
+
+/// Runs [`STEPS`] operations generated from `seed` as `owner` on a fresh
+/// deployment. With `drains`, every swap is replaced by a drain attempt.
+fn run(contract: &Hook, owner: Address, seed: u64, drains: bool) {
+    let mut model = deploy(contract, owner);
+    play(contract, owner, &mut model, seed, STEPS, drains);
+}
+
+/// Plays `steps` operations generated from `seed` as `owner`, checking the
+/// contract against `model` after each of them.
+fn play(contract: &Hook, owner: Address, model: &mut Model, seed: u64, steps: usize, drains: bool) {
+    let bytes = entropy(seed, steps * 16);
+    let mut u = Unstructured::new(&bytes);
+    for step in 0..steps {
+        let op = Op::arbitrary(&mut u).expect("should decode operation");
+        let op = match op {
+            Op::Swap {
+                pool,
+                zero_for_one,
+                exact_output,
+                ..
+            } if drains => Op::Drain {
+                pool,
+                zero_for_one,
+                exact_output,
+            },
+            op => op,
+        };
+        apply(contract, owner, model, op, step);
+        check(contract, owner, model, step);
+        check_conservation(contract, owner, model, step);
+    }
+}
+
+/// Applies `op` to the contract and to `model`.
+fn apply(contract: &Hook, owner: Address, model: &mut Model, op: Op, step: usize) {
+    match op {
+        Op::Swap {
+            pool,
+            zero_for_one,
+            exact_output,
+            amount,
+        } => {
+            let index = usize::from(pool) % POOLS.len();
+            let amount = U256::from(amount) + uint!(1_U256);
+            swap(
+                contract,
+                owner,
+                model,
+                index,
+                zero_for_one,
+                exact_output,
+                amount,
+                step,
+            );
+        }
+        Op::Drain {
+            pool,
+            zero_for_one,
+            exact_output,
+        } => {
+            let index = usize::from(pool) % POOLS.len();
+            let pool = &model.pools[index];
+            let reserve_out = if zero_for_one {
+                pool.reserve1
+            } else {
+                pool.reserve0
+            };
+            let amount = reserve_out + uint!(1_U256);
+            swap(
+                contract,
+                owner,
+                model,
+                index,
+                zero_for_one,
+                exact_output,
+                amount,
+                step,
+            );
+        }
+        Op::Deposit { pool, amount } => {
+            let index = usize::from(pool) % POOLS.len();
+            let (currency0, currency1, _) = POOLS[index];
+            let amount = U256::from(amount);
+            model.inflow(currency0, amount);
+            model.inflow(currency1, amount);
+            let pool = &mut model.pools[index];
+            pool.reserve0 += amount;
+            pool.reserve1 += amount;
+            contract.mint(currency0, owner, amount);
+            contract.mint(currency1, owner, amount);
+            contract
+                .sender(owner)
+                .deposit(model.pool_ids[index], amount, amount)
+                .unwrap_or_else(|err| panic!("step {step}: deposit failed: {err:?}"));
+        }
+        Op::Withdraw { pool, amount } => {
+            let index = usize::from(pool) % POOLS.len();
+            let (currency0, currency1, _) = POOLS[index];
+            let pool = &mut model.pools[index];
+            let amount0 = pool.reserve0.min(U256::from(amount));
+            let amount1 = pool.reserve1.min(U256::from(amount));
+            pool.reserve0 -= amount0;
+            pool.reserve1 -= amount1;
+            model.outflow(currency0, amount0);
+            model.outflow(currency1, amount1);
+            contract
+                .sender(owner)
+                .withdraw(model.pool_ids[index], amount0, amount1, owner)
+                .unwrap_or_else(|err| panic!("step {step}: withdrawal failed: {err:?}"));
+        }
+        Op::AddLiquidity { amount0, amount1 } => {
+            let (amount0, amount1) = (U256::from(amount0), U256::from(amount1));
+            add_liquidity(contract, owner, model, amount0, amount1, step);
+        }
+        Op::RemoveLiquidity { shares } => {
+            let shares = model.liquidity.shares.min(U256::from(shares));
+            remove_liquidity(contract, owner, model, shares, step);
+        }
+        Op::LegacySwap {
+            zero_for_one,
+            exact_output,
+            amount,
+        } => {
+            let amount = U256::from(amount) + uint!(1_U256);
+            legacy_swap(contract, owner, zero_for_one, exact_output, amount, step);
+        }
+        Op::SetFee { pool, fee_bps } => {
+            let index = usize::from(pool) % POOLS.len();
+            model.pools[index].fee_bps = u16::from(fee_bps);
+            contract
+                .sender(owner)
+                .set_pool_fee(model.pool_ids[index], u16::from(fee_bps))
+                .expect("should set pool fee");
+        }
+        Op::SetPaused { pool, paused } => {
+            let index = usize::from(pool) % POOLS.len();
+            model.pools[index].paused = paused;
+            contract
+                .sender(owner)
+                .set_pool_paused(model.pool_ids[index], paused)
+                .expect("should set pool pause");
+        }
+    }
+}
+
+/// Adds `amount0` and `amount1` of liquidity to the default pool as
+/// `owner`, and checks the minted shares against `model`.
+fn add_liquidity(
+    contract: &Hook,
+    owner: Address,
+    model: &mut Model,
+    amount0: U256,
+    amount1: U256,
+    step: usize,
+) {
+    contract.mint(CURRENCY_1, owner, amount0);
+    contract.mint(CURRENCY_2, owner, amount1);
+    let result = contract.sender(owner).add_liquidity(amount0, amount1);
+
+    // Shares are valued at the 1:1 default rate.
+    let lp = &mut model.liquidity;
+    let value = amount0 + amount1;
+    let first = lp.total_shares.is_zero();
+    let reserves_value = lp.reserve0 + lp.reserve1;
+    let shares = if first {
+        value.saturating_sub(liquidity::MINIMUM_LIQUIDITY)
+    } else if reserves_value.is_zero() {
+        value
+    } else {
+        math::mul_div(value, lp.total_shares, reserves_value).expect("should fit")
+    };
+    if shares.is_zero() {
+        let err = result.expect_err("should mint some shares");
+        assert!(
+            matches!(err, Error::InsufficientLiquidityMinted(_)),
+            "step {step}: unexpected error {err:?}"
+        );
+        // The tokens stay with the owner.
+        return;
+    }
+    assert_eq!(
+        shares,
+        result.unwrap_or_else(|err| panic!("step {step}: adding liquidity failed: {err:?}")),
+        "step {step}: unexpected shares"
+    );
+    if first {
+        lp.total_shares += liquidity::MINIMUM_LIQUIDITY;
+    }
+    lp.total_shares += shares;
+    lp.shares += shares;
+    lp.reserve0 += amount0;
+    lp.reserve1 += amount1;
+    model.inflow(CURRENCY_1, amount0);
+    model.inflow(CURRENCY_2, amount1);
+}
+
+/// Removes `shares` of liquidity from the default pool as `owner`, and
+/// checks the paid out amounts against `model`.
+fn remove_liquidity(contract: &Hook, owner: Address, model: &mut Model, shares: U256, step: usize) {
+    let result = contract
+        .sender(owner)
+        .remove_liquidity(shares, U256::ZERO, U256::ZERO);
+
+    let lp = &mut model.liquidity;
+    let part = |reserve: U256| {
+        if lp.total_shares.is_zero() {
+            U256::ZERO
+        } else {
+            reserve * shares / lp.total_shares
+        }
+    };
+    let (amount0, amount1) = (part(lp.reserve0), part(lp.reserve1));
+    if amount0.is_zero() && amount1.is_zero() {
+        let err = result.expect_err("should pay something out");
+        assert!(
+            matches!(err, Error::InsufficientLiquidityBurned(_)),
+            "step {step}: unexpected error {err:?}"
+        );
+        return;
+    }
+    assert_eq!(
+        (amount0, amount1),
+        result.unwrap_or_else(|err| panic!("step {step}: removing liquidity failed: {err:?}")),
+        "step {step}: unexpected payout"
+    );
+    lp.total_shares -= shares;
+    lp.shares -= shares;
+    lp.reserve0 -= amount0;
+    lp.reserve1 -= amount1;
+    model.outflow(CURRENCY_1, amount0);
+    model.outflow(CURRENCY_2, amount1);
+}
+
+/// Calls the legacy `getAmount*` method for `amount` as `owner`, and checks
+/// that it returns the quote of the matching view. The default pool moves
+/// no currencies, so [`check`] then finds its reserves unchanged.
+fn legacy_swap(
+    contract: &Hook,
+    owner: Address,
+    zero_for_one: bool,
+    exact_output: bool,
+    amount: U256,
+    step: usize,
+) {
+    let (input, output) = if zero_for_one {
+        (CURRENCY_1, CURRENCY_2)
+    } else {
+        (CURRENCY_2, CURRENCY_1)
+    };
+    let curve = contract.sender(owner);
+    let (quote, result) = if exact_output {
+        (
+            curve.quote_amount_in(amount, input, output, zero_for_one),
+            curve.get_amount_in_for_exact_output(amount, input, output, zero_for_one),
+        )
+    } else {
+        (
+            curve.quote_amount_out(amount, input, output, zero_for_one),
+            curve.get_amount_out_from_exact_input(amount, input, output, zero_for_one),
+        )
+    };
+    match (quote, result) {
+        (Ok(quote), Ok(amount)) => assert_eq!(quote, amount, "step {step}: quote diverged"),
+        (Err(_), Err(_)) => {}
+        (quote, result) => panic!("step {step}: quoted {quote:?}, swapped {result:?}"),
+    }
+}
+
+/// Swaps `amount` in the pool at `index` through `beforeSwap` and
+/// `afterSwap` as [`POOL_MANAGER`], and checks the result against `model`.
+#[allow(clippy::too_many_arguments)]
+fn swap(
+    contract: &Hook,
+    owner: Address,
+    model: &mut Model,
+    index: usize,
+    zero_for_one: bool,
+    exact_output: bool,
+    amount: U256,
+    step: usize,
+) {
+    let pool_id = model.pool_ids[index];
+    let (currency0, currency1, fee) = POOLS[index];
+    let key = pool_key(currency0, currency1, fee);
+    let specified = I256::try_from(amount).expect("should fit");
+    let params = SwapParams {
+        zero_for_one,
+        amount_specified: if exact_output { specified } else { -specified },
+        sqrt_price_limit_x96: U160::ZERO,
+    };
+    let result = contract.swap(owner, key.clone(), params.clone(), Bytes::default());
+
+    let pool = &mut model.pools[index];
+    let (amount_in, amount_out) = if exact_output {
+        let amount_in = ConstantSumCurve::add_fee(amount, pool.fee_bps).expect("should add fee");
+        (amount_in, amount)
+    } else {
+        let amount_out =
+            ConstantSumCurve::deduct_fee(amount, pool.fee_bps).expect("should deduct fee");
+        (amount, amount_out)
+    };
+    // Swaps trade at most 1:1, so they never shrink the pool's reserves.
+    assert!(amount_out <= amount_in, "step {step}: pool pays out more");
+
+    let reserve_out = if zero_for_one {
+        pool.reserve1
+    } else {
+        pool.reserve0
+    };
+    if pool.reserve0.is_zero() && pool.reserve1.is_zero() {
+        let err = result.expect_err("should reject swaps in an empty pool");
+        assert!(
+            matches!(err, Error::PoolEmpty(PoolEmpty { pool_id: empty }) if empty == pool_id),
+            "step {step}: unexpected error {err:?}"
+        );
+        return;
+    }
+    if pool.paused {
+        let err = result.expect_err("should reject swaps in a paused pool");
+        assert!(
+            matches!(err, Error::PoolPaused(PoolPaused { pool_id: paused }) if paused == pool_id),
+            "step {step}: unexpected error {err:?}"
+        );
+        return;
+    }
+    if amount_out > reserve_out {
+        let err = result.expect_err("should reject a drain");
+        assert!(
+            matches!(
+                err,
+                Error::InsufficientLiquidity(InsufficientLiquidity { available, requested })
+                    if available == reserve_out && requested == amount_out
+            ),
+            "step {step}: unexpected error {err:?}"
+        );
+        return;
+    }
+
+    let (_, delta, _) = result.unwrap_or_else(|err| panic!("step {step}: swap failed: {err:?}"));
+    let (owed_in, owed_out) = (
+        i128::try_from(amount_in).expect("should fit"),
+        i128::try_from(amount_out).expect("should fit"),
+    );
+    let (expected_specified, expected_unspecified) = if exact_output {
+        (-owed_out, owed_in)
+    } else {
+        (owed_in, -owed_out)
+    };
+    assert_eq!(
+        (expected_specified, expected_unspecified),
+        (
+            hooks::specified_delta(delta),
+            hooks::unspecified_delta(delta)
+        ),
+        "step {step}: unexpected delta"
+    );
+
+    let (input, output) = if zero_for_one {
+        pool.reserve0 += amount_in;
+        pool.reserve1 -= amount_out;
+        (currency0, currency1)
+    } else {
+        pool.reserve1 += amount_in;
+        pool.reserve0 -= amount_out;
+        (currency1, currency0)
+    };
+
+    // The swapper pays `amount_in` and receives `amount_out`.
+    let (amount0, amount1) = if zero_for_one {
+        (-owed_in, owed_out)
+    } else {
+        (owed_out, -owed_in)
+    };
+    let (_, fee) = contract
+        .sender(POOL_MANAGER)
+        .after_swap(
+            owner,
+            key.clone(),
+            params.clone(),
+            hooks::to_balance_delta(amount0, amount1),
+            Bytes::default(),
+        )
+        .expect("should account swap");
+    contract.settle_after_swap(&key, &params, fee);
+    pool.volume0 += U256::from(amount0.unsigned_abs());
+    pool.volume1 += U256::from(amount1.unsigned_abs());
+    model.inflow(input, amount_in);
+    model.outflow(output, amount_out);
+}
+
+/// Checks every pool and the LP shares against their model.
+fn check(contract: &Hook, owner: Address, model: &Model, step: usize) {
+    for (pool_id, pool) in model.pool_ids.iter().zip(&model.pools) {
+        let pool_id = *pool_id;
+        assert_eq!(
+            (pool.reserve0, pool.reserve1),
+            contract
+                .sender(owner)
+                .pool_reserves(pool_id)
+                .expect("should read pool reserves"),
+            "step {step}: reserves of {pool_id} diverged"
+        );
+        assert_eq!(
+            pool.fee_bps,
+            contract.sender(owner).pool_fee(pool_id),
+            "step {step}: fee of {pool_id} diverged"
+        );
+        assert_eq!(
+            pool.paused,
+            contract.sender(owner).pool_paused(pool_id),
+            "step {step}: pause of {pool_id} diverged"
+        );
+        assert_eq!(
+            (pool.volume0, pool.volume1),
+            contract.sender(owner).volume(pool_id),
+            "step {step}: volume of {pool_id} diverged"
+        );
+    }
+    let lp = &model.liquidity;
+    assert_eq!(
+        (lp.reserve0, lp.reserve1),
+        contract
+            .sender(owner)
+            .get_reserves()
+            .expect("should read reserves"),
+        "step {step}: reserves of the default pool diverged"
+    );
+    assert_eq!(
+        (lp.total_shares, lp.shares),
+        (
+            contract
+                .sender(owner)
+                .total_supply()
+                .expect("should read total supply"),
+            contract.sender(owner).balance_of(owner)
+        ),
+        "step {step}: LP shares diverged"
+    );
+}
+
+/// Checks that no currency leaves the pools beyond what they were seeded
+/// with and paid, and that the reserves reported by the contract and the
+/// tokens held by the hook are exactly the difference.
+///
+/// No rewards are minted, so there is no tolerance: a single token paid out
+/// of thin air fails the check.
+fn check_conservation(contract: &Hook, owner: Address, model: &Model, step: usize) {
+    let curve = contract.sender(owner);
+    let mut reserves: Vec<(Address, Address, B256)> = POOLS
+        .iter()
+        .zip(&model.pool_ids)
+        .map(|((currency0, currency1, _), pool_id)| (*currency0, *currency1, *pool_id))
+        .collect();
+    reserves.push((CURRENCY_1, CURRENCY_2, pool::DEFAULT_POOL_ID));
+    let reported: Vec<(Address, Address, (U256, U256))> = reserves
+        .into_iter()
+        .map(|(currency0, currency1, pool_id)| {
+            let reserves = curve
+                .pool_reserves(pool_id)
+                .expect("should read pool reserves");
+            (currency0, currency1, reserves)
+        })
+        .collect();
+    drop(curve);
+
+    for (currency, flows) in &model.flows {
+        let funded = flows.initial + flows.inflow;
+        assert!(
+            flows.outflow <= funded,
+            "step {step}: {currency} paid out {} of {funded}",
+            flows.outflow
+        );
+
+        let held = reported
+            .iter()
+            .map(|(currency0, currency1, (reserve0, reserve1))| {
+                if currency0 == currency {
+                    *reserve0
+                } else if currency1 == currency {
+                    *reserve1
+                } else {
+                    U256::ZERO
+                }
+            })
+            .fold(U256::ZERO, |total, reserve| total + reserve);
+        assert_eq!(
+            funded - flows.outflow,
+            held,
+            "step {step}: {currency} reported by the pools diverged"
+        );
+        assert_eq!(
+            held,
+            contract.balance(*currency, HOOK),
+            "step {step}: {currency} held by the hook diverged"
+        );
+    }
+}
+
+#[motsu::test]
+fn swap_storm_keeps_invariants(alice: Address) {
+    let contract = hook();
+    run(&contract, alice, 0x5eed_0001, false);
+}
+
+#[motsu::test]
+fn swap_storm_with_another_seed_keeps_invariants(alice: Address) {
+    let contract = hook();
+    run(&contract, alice, 0x5eed_0002, false);
+}
+
+#[motsu::test]
+fn drain_attempts_never_move_reserves(alice: Address) {
+    let contract = hook();
+    run(&contract, alice, 0x5eed_0003, true);
+}
+
+#[motsu::test]
+fn branches_replay_from_a_snapshot(alice: Address) {
+    let contract = hook();
+    let mut model = deploy(&contract, alice);
+    play(&contract, alice, &mut model, 0x5eed_0004, STEPS / 4, false);
+
+    let fork = snapshot(&contract, &model.pool_ids, &[alice]);
+    let forked = model.clone();
+    let change_count = contract.sender(alice).change_count();
+
+    // Branch A: a swap storm.
+    play(&contract, alice, &mut model, 0x5eed_0005, STEPS / 4, false);
+    assert_ne!(change_count, contract.sender(alice).change_count());
+
+    // Branch B: drain attempts, from the same state.
+    restore(&contract, &fork);
+    let mut model = forked;
+    check(&contract, alice, &model, 0);
+    assert_eq!(change_count, contract.sender(alice).change_count());
+    play(&contract, alice, &mut model, 0x5eed_0005, STEPS / 4, true);
+}
+
+#[motsu::test]
+fn value_is_conserved_per_currency(alice: Address) {
+    let contract = hook();
+    let mut model = deploy(&contract, alice);
+    let (currency0, currency1, _) = POOLS[0];
+
+    // An exact input paying out one more token than the pool holds.
+    let reserve_out = model.pools[0].reserve1;
+    swap(
+        &contract,
+        alice,
+        &mut model,
+        0,
+        true,
+        false,
+        reserve_out + uint!(1_U256),
+        0,
+    );
+    check_conservation(&contract, alice, &model, 0);
+
+    // Emptying the pool leaves nothing to pay out.
+    swap(&contract, alice, &mut model, 0, true, true, reserve_out, 1);
+    assert_eq!(U256::ZERO, model.pools[0].reserve1);
+    assert_eq!(model.flows[&currency1].outflow, reserve_out);
+    assert_eq!(
+        model.flows[&currency0].inflow,
+        model.flows[&currency1].outflow
+    );
+    swap(
+        &contract,
+        alice,
+        &mut model,
+        0,
+        true,
+        false,
+        uint!(1_U256),
+        2,
+    );
+    check(&contract, alice, &model, 2);
+    check_conservation(&contract, alice, &model, 2);
+}
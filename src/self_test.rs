@@ -0,0 +1,110 @@
+//! Post-deploy self-test of the curve's quoting path.
+//!
+//! Right after deploying, the owner runs `selfTest()` once. It prices a
+//! canary pair that no pool trades, at a dummy exchange rate: exact-input
+//! and exact-output quotes in both directions, whose round trips must never
+//! favour the swapper. The dummy rate is unset again, and the passing run is
+//! recorded, so deploy scripts can assert `selfTestPassed()`.
+use alloy_primitives::{address, uint, Address, U256};
+use stylus_sdk::evm;
+
+use crate::{
+    pool::DEFAULT_POOL_ID, AmountInCalculated, AmountOutCalculated, ConstantSumCurve, Error,
+    SelfTestFailed, FEE_DENOMINATOR,
+};
+
+/// Lower currency of the canary pair.
+pub const CANARY0: Address = address!("00000000000000000000000000000000000c0de0");
+
+/// Higher currency of the canary pair.
+pub const CANARY1: Address = address!("00000000000000000000000000000000000c0de1");
+
+/// Dummy exchange rate of the canary pair, as `(numerator, denominator)`.
+pub const CANARY_RATE: (U256, U256) = (uint!(3_U256), uint!(2_U256));
+
+/// Amount quoted by each check of the self-test: a multiple of
+/// [`FEE_DENOMINATOR`], so that any fee deducts from it exactly.
+pub const CANARY_AMOUNT: U256 = uint!(1_000_000_U256);
+
+const _: () = assert!(1_000_000 % FEE_DENOMINATOR as u64 == 0);
+
+impl ConstantSumCurve {
+    /// Quotes [`CANARY_AMOUNT`] of the canary pair in both directions at
+    /// [`CANARY_RATE`], then unsets the rate.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::SelfTestFailed`] - If a quote fails, or its round trip
+    ///   yields more than [`CANARY_AMOUNT`]. `check` is `0` and `1` for the
+    ///   exact-input and exact-output quotes selling [`CANARY0`], and `2`
+    ///   and `3` for those selling [`CANARY1`].
+    ///
+    /// # Events
+    ///
+    /// * [`AmountOutCalculated`].
+    /// * [`AmountInCalculated`].
+    pub(crate) fn run_self_test(&mut self) -> Result<(), Error> {
+        let (numerator, denominator) = CANARY_RATE;
+        self.exchange_rates
+            .setter(CANARY0)
+            .setter(CANARY1)
+            .set_rate(numerator, denominator);
+        let passed = self
+            .check_canary(CANARY0, CANARY1, 0)
+            .and_then(|()| self.check_canary(CANARY1, CANARY0, 2));
+        self.exchange_rates
+            .setter(CANARY0)
+            .setter(CANARY1)
+            .set_rate(U256::ZERO, U256::ZERO);
+        passed
+    }
+
+    /// Quotes [`CANARY_AMOUNT`] of `input` for `output`, and back from the
+    /// quoted output, as checks `check` and `check + 1`.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::SelfTestFailed`] - If a quote fails or its round trip
+    ///   yields more than [`CANARY_AMOUNT`].
+    ///
+    /// # Events
+    ///
+    /// * [`AmountOutCalculated`].
+    /// * [`AmountInCalculated`].
+    fn check_canary(&mut self, input: Address, output: Address, check: u8) -> Result<(), Error> {
+        let failed = |check: u8| Error::SelfTestFailed(SelfTestFailed { check });
+        let zero_for_one = input < output;
+        let (amount_out, _) = self
+            .calculate_amount_out(DEFAULT_POOL_ID, CANARY_AMOUNT, input, output, zero_for_one)
+            .map_err(|_| failed(check))?;
+        if amount_out.is_zero() {
+            return Err(failed(check));
+        }
+
+        #[allow(deprecated)]
+        evm::log(AmountOutCalculated {
+            amount_in: CANARY_AMOUNT,
+            input,
+            output,
+            zero_for_one,
+        });
+
+        // Buying back the quoted output costs at most the quoted input.
+        let (amount_in, _) = self
+            .calculate_amount_in(DEFAULT_POOL_ID, amount_out, input, output, zero_for_one)
+            .map_err(|_| failed(check + 1))?;
+        if amount_in > CANARY_AMOUNT {
+            return Err(failed(check + 1));
+        }
+
+        #[allow(deprecated)]
+        evm::log(AmountInCalculated {
+            amount_out,
+            input,
+            output,
+            zero_for_one,
+        });
+
+        Ok(())
+    }
+}
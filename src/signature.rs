@@ -0,0 +1,93 @@
+//! EIP-712 signature verification.
+//!
+//! Signatures are 65 bytes long, laid out as `r || s || v`, and recovered
+//! through the `ecrecover` precompile. Malleable signatures (high `s`) are
+//! rejected.
+use alloy_primitives::{address, b256, keccak256, uint, Address, B256, U256};
+use alloy_sol_types::SolValue;
+use stylus_sdk::{
+    call::{self, Call},
+    prelude::*,
+};
+
+use crate::{ConstantSumCurve, Error, InvalidSignature};
+
+/// Address of the `ecrecover` precompile.
+const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+
+/// Type hash of the EIP-712 domain,
+/// `keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)")`.
+pub const DOMAIN_TYPEHASH: B256 =
+    b256!("8b73c3c69bb8fe3d512ecc4cf759cc79239f7b179b0ffacaa9a75d522b39400f");
+
+/// Name of the EIP-712 domain.
+pub const DOMAIN_NAME: &str = "ConstantSumCurve";
+
+/// Version of the EIP-712 domain.
+pub const DOMAIN_VERSION: &str = "1";
+
+/// Half of the order of the secp256k1 curve, the largest accepted `s`.
+const SECP256K1_N_DIV_2: U256 =
+    uint!(0x7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF5D576E7357A4501DDFE92F46681B20A0_U256);
+
+impl ConstantSumCurve {
+    /// Returns the EIP-712 domain separator of the contract.
+    pub(crate) fn domain_separator(&self) -> B256 {
+        keccak256(
+            (
+                DOMAIN_TYPEHASH,
+                keccak256(DOMAIN_NAME),
+                keccak256(DOMAIN_VERSION),
+                U256::from(self.vm().chain_id()),
+                self.vm().contract_address(),
+            )
+                .abi_encode(),
+        )
+    }
+
+    /// Returns the EIP-712 digest of a message with `struct_hash`.
+    pub(crate) fn typed_data_digest(&self, struct_hash: B256) -> B256 {
+        let mut data = [0u8; 66];
+        data[..2].copy_from_slice(&[0x19, 0x01]);
+        data[2..34].copy_from_slice(self.domain_separator().as_slice());
+        data[34..].copy_from_slice(struct_hash.as_slice());
+        keccak256(data)
+    }
+
+    /// Returns the signer of `digest`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - Read access to the contract's state.
+    /// * `digest` - The signed digest.
+    /// * `signature` - The `r || s || v` signature.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidSignature`] - If `signature` is malformed, malleable
+    ///   or cannot be recovered.
+    pub(crate) fn recover_signer(&self, digest: B256, signature: &[u8]) -> Result<Address, Error> {
+        let invalid = || Error::InvalidSignature(InvalidSignature {});
+        if signature.len() != 65 {
+            return Err(invalid());
+        }
+        let r = B256::from_slice(&signature[..32]);
+        let s = B256::from_slice(&signature[32..64]);
+        let v = signature[64];
+        if U256::from_be_bytes(s.0) > SECP256K1_N_DIV_2 || !(v == 27 || v == 28) {
+            return Err(invalid());
+        }
+
+        let calldata = (digest, U256::from(v), r, s).abi_encode();
+        #[allow(deprecated)]
+        let output = call::static_call(Call::new(), ECRECOVER, &calldata).map_err(|_| invalid())?;
+        if output.len() != 32 {
+            return Err(invalid());
+        }
+        let signer = Address::from_slice(&output[12..]);
+        if signer.is_zero() {
+            return Err(invalid());
+        }
+        Ok(signer)
+    }
+}
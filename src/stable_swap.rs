@@ -0,0 +1,422 @@
+//! StableSwap Curve calculation for Uniswap V4 Hooks.
+//!
+//! Prices swaps along Curve's StableSwap invariant for two currencies,
+//! `A * n^n * (x + y) + D = A * D * n^n + D^(n + 1) / (n^n * x * y)`, which
+//! trades close to 1:1 around balanced reserves and degrades to a
+//! constant-product curve as they drift apart. The amplification
+//! coefficient `A` is stored in the contract's state.
+//!
+//! This curve is deployed instead of [`crate::ConstantSumCurve`] when built
+//! with `--cfg curve="stable-swap"`.
+use alloc::string::String;
+
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    evm,
+    prelude::*,
+    storage::{StorageAddress, StorageMap, StorageString, StorageU256},
+};
+
+use crate::{
+    math, AmountInCalculated, AmountOutCalculated, AmountOverflow, Currency, Error,
+    IUniswapV4Curve, InsufficientLiquidity, InvalidAmplification, InvariantNotConverged,
+    Unauthorized,
+};
+
+sol! {
+    /// Emitted when the reserve of `currency` is set by the owner.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event ReserveSet(address indexed currency, uint256 reserve);
+
+    /// Emitted when the amplification coefficient is changed.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event AmplificationSet(uint256 amplification);
+}
+
+/// Number of currencies of a pool.
+pub const N_COINS: u64 = 2;
+
+/// Maximum amplification coefficient.
+pub const MAX_AMPLIFICATION: u64 = 1_000_000;
+
+/// Maximum number of Newton iterations before giving up.
+pub const MAX_ITERATIONS: usize = 255;
+
+/// StableSwap curve pricing swaps with the reserves of each currency.
+#[storage]
+#[cfg_attr(curve = "stable-swap", entrypoint)]
+pub struct StableSwapCurve {
+    version: StorageString,
+    owner: StorageAddress,
+    amplification: StorageU256,
+    reserves: StorageMap<Currency, StorageU256>,
+}
+
+// SAFETY: Unless it is the entrypoint, the contract is only instantiated as
+// top-level storage in tests.
+#[cfg(not(curve = "stable-swap"))]
+unsafe impl TopLevelStorage for StableSwapCurve {}
+
+#[public]
+#[implements(IUniswapV4Curve<Error = Error>)]
+impl StableSwapCurve {
+    /// Initializes the contract.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `version` - The version of the curve.
+    /// * `owner` - The owner of the contract.
+    /// * `amplification` - The amplification coefficient.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidAmplification`] - If `amplification` is zero or
+    ///   above [`MAX_AMPLIFICATION`].
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        version: String,
+        owner: Address,
+        amplification: U256,
+    ) -> Result<(), Error> {
+        self.version.set_str(version);
+        self.owner.set(owner);
+        self.update_amplification(amplification)
+    }
+
+    /// Returns the owner of the contract.
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Returns the amplification coefficient.
+    pub fn amplification(&self) -> U256 {
+        self.amplification.get()
+    }
+
+    /// Sets the amplification coefficient.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `amplification` - The new amplification coefficient.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::InvalidAmplification`] - If `amplification` is zero or
+    ///   above [`MAX_AMPLIFICATION`].
+    ///
+    /// # Events
+    ///
+    /// * [`AmplificationSet`].
+    pub fn set_amplification(&mut self, amplification: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.update_amplification(amplification)
+    }
+
+    /// Returns the reserve of `currency`.
+    pub fn reserve(&self, currency: Currency) -> U256 {
+        self.reserves.get(currency)
+    }
+
+    /// Sets the reserve of `currency`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `currency` - The currency to update.
+    /// * `reserve` - The new reserve.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`ReserveSet`].
+    pub fn set_reserve(&mut self, currency: Currency, reserve: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.reserves.setter(currency).set(reserve);
+
+        #[allow(deprecated)]
+        evm::log(ReserveSet { currency, reserve });
+
+        Ok(())
+    }
+}
+
+#[public]
+impl IUniswapV4Curve for StableSwapCurve {
+    type Error = Error;
+
+    fn version(&self) -> String {
+        self.version.get_string()
+    }
+
+    fn get_amount_in_for_exact_output(
+        &mut self,
+        amount_out: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Self::Error> {
+        let reserve_in = self.reserves.get(input);
+        let reserve_out = self.reserves.get(output);
+        let amount_in = Self::calculate_amount_in(
+            reserve_in,
+            reserve_out,
+            amount_out,
+            self.amplification.get(),
+        )?;
+        self.reserves.setter(input).set(reserve_in + amount_in);
+        self.reserves.setter(output).set(reserve_out - amount_out);
+
+        #[allow(deprecated)]
+        evm::log(AmountInCalculated {
+            amount_out,
+            input,
+            output,
+            zero_for_one,
+        });
+
+        Ok(amount_in)
+    }
+
+    fn get_amount_out_from_exact_input(
+        &mut self,
+        amount_in: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Self::Error> {
+        let reserve_in = self.reserves.get(input);
+        let reserve_out = self.reserves.get(output);
+        let amount_out = Self::calculate_amount_out(
+            reserve_in,
+            reserve_out,
+            amount_in,
+            self.amplification.get(),
+        )?;
+        self.reserves.setter(input).set(reserve_in + amount_in);
+        self.reserves.setter(output).set(reserve_out - amount_out);
+
+        #[allow(deprecated)]
+        evm::log(AmountOutCalculated {
+            amount_in,
+            input,
+            output,
+            zero_for_one,
+        });
+
+        Ok(amount_out)
+    }
+}
+
+impl StableSwapCurve {
+    /// Returns the amount of input tokens for an exact-output swap, rounded
+    /// up, keeping the invariant constant.
+    ///
+    /// # Arguments
+    ///
+    /// * `reserve_in` - The reserve of the input token.
+    /// * `reserve_out` - The reserve of the output token.
+    /// * `amount_out` - The amount of output tokens the user expects to receive.
+    /// * `amplification` - The amplification coefficient.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InsufficientLiquidity`] - If `amount_out` does not leave
+    ///   any output reserve.
+    /// * [`Error::InvariantNotConverged`] - If the invariant cannot be
+    ///   solved for the reserves.
+    /// * [`Error::AmountOverflow`] - If an intermediate value does not fit
+    ///   into `uint256`.
+    pub fn calculate_amount_in(
+        reserve_in: U256,
+        reserve_out: U256,
+        amount_out: U256,
+        amplification: U256,
+    ) -> Result<U256, Error> {
+        if amount_out >= reserve_out {
+            return Err(Error::InsufficientLiquidity(InsufficientLiquidity {
+                available: reserve_out,
+                requested: amount_out,
+            }));
+        }
+        let d = get_d(reserve_in, reserve_out, amplification)?;
+        let reserve_in_after = get_y(reserve_out - amount_out, d, amplification)?;
+        // Round against the swapper, as Newton's method may undershoot.
+        reserve_in_after
+            .saturating_sub(reserve_in)
+            .checked_add(U256::from(1))
+            .ok_or_else(overflow)
+    }
+
+    /// Returns the amount of output tokens for an exact-input swap, rounded
+    /// down, keeping the invariant constant.
+    ///
+    /// # Arguments
+    ///
+    /// * `reserve_in` - The reserve of the input token.
+    /// * `reserve_out` - The reserve of the output token.
+    /// * `amount_in` - The amount of input tokens.
+    /// * `amplification` - The amplification coefficient.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvariantNotConverged`] - If the invariant cannot be
+    ///   solved for the reserves.
+    /// * [`Error::AmountOverflow`] - If an intermediate value does not fit
+    ///   into `uint256`.
+    pub fn calculate_amount_out(
+        reserve_in: U256,
+        reserve_out: U256,
+        amount_in: U256,
+        amplification: U256,
+    ) -> Result<U256, Error> {
+        let d = get_d(reserve_in, reserve_out, amplification)?;
+        let reserve_in_after = reserve_in.checked_add(amount_in).ok_or_else(overflow)?;
+        let reserve_out_after = get_y(reserve_in_after, d, amplification)?;
+        // Round against the swapper, as Newton's method may overshoot.
+        Ok(reserve_out
+            .saturating_sub(reserve_out_after)
+            .saturating_sub(U256::from(1)))
+    }
+
+    /// Checks that the caller is the owner.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    fn only_owner(&self) -> Result<(), Error> {
+        let account = self.vm().msg_sender();
+        if account != self.owner.get() {
+            return Err(Error::Unauthorized(Unauthorized { account }));
+        }
+        Ok(())
+    }
+
+    /// Sets the amplification coefficient, without access control.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InvalidAmplification`] - If `amplification` is zero or
+    ///   above [`MAX_AMPLIFICATION`].
+    ///
+    /// # Events
+    ///
+    /// * [`AmplificationSet`].
+    fn update_amplification(&mut self, amplification: U256) -> Result<(), Error> {
+        if amplification.is_zero() || amplification > U256::from(MAX_AMPLIFICATION) {
+            return Err(Error::InvalidAmplification(InvalidAmplification {
+                amplification,
+            }));
+        }
+        self.amplification.set(amplification);
+
+        #[allow(deprecated)]
+        evm::log(AmplificationSet { amplification });
+
+        Ok(())
+    }
+}
+
+/// Returns the invariant `D` of the reserves `x0` and `x1`, solved with
+/// Newton's method.
+///
+/// # Errors
+///
+/// * [`Error::InvariantNotConverged`] - If a reserve or `amplification` is
+///   zero, or the iteration does not converge.
+/// * [`Error::AmountOverflow`] - If an intermediate value does not fit into
+///   `uint256`.
+pub fn get_d(x0: U256, x1: U256, amplification: U256) -> Result<U256, Error> {
+    let sum = x0.checked_add(x1).ok_or_else(overflow)?;
+    if sum.is_zero() {
+        return Ok(U256::ZERO);
+    }
+    if x0.is_zero() || x1.is_zero() || amplification.is_zero() {
+        return Err(not_converged());
+    }
+    let n = U256::from(N_COINS);
+    let ann = amplification.checked_mul(n).ok_or_else(overflow)?;
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // `d_p = D^(n + 1) / (n^n * x0 * x1)`.
+        let mut d_p = d;
+        for x in [x0, x1] {
+            let x_n = x.checked_mul(n).ok_or_else(overflow)?;
+            d_p = math::mul_div(d_p, d, x_n).ok_or_else(overflow)?;
+        }
+        let d_prev = d;
+        let numerator = ann
+            .checked_mul(sum)
+            .and_then(|ann_sum| ann_sum.checked_add(d_p.checked_mul(n)?))
+            .ok_or_else(overflow)?;
+        let denominator = (ann - U256::from(1))
+            .checked_mul(d)
+            .and_then(|a| a.checked_add(d_p.checked_mul(n + U256::from(1))?))
+            .ok_or_else(overflow)?;
+        d = math::mul_div(numerator, d, denominator).ok_or_else(overflow)?;
+        if d.abs_diff(d_prev) <= U256::from(1) {
+            return Ok(d);
+        }
+    }
+    Err(not_converged())
+}
+
+/// Returns the reserve of one currency that keeps the invariant `d` when
+/// the reserve of the other one is `x`, solved with Newton's method.
+///
+/// # Errors
+///
+/// * [`Error::InvariantNotConverged`] - If `x` or `amplification` is zero,
+///   or the iteration does not converge.
+/// * [`Error::AmountOverflow`] - If an intermediate value does not fit into
+///   `uint256`.
+pub fn get_y(x: U256, d: U256, amplification: U256) -> Result<U256, Error> {
+    if x.is_zero() || amplification.is_zero() {
+        return Err(not_converged());
+    }
+    let n = U256::from(N_COINS);
+    let ann = amplification.checked_mul(n).ok_or_else(overflow)?;
+    // `c = D^(n + 1) / (n^n * x * ann)`.
+    let x_n = x.checked_mul(n).ok_or_else(overflow)?;
+    let c = math::mul_div(d, d, x_n).ok_or_else(overflow)?;
+    let ann_n = ann.checked_mul(n).ok_or_else(overflow)?;
+    let c = math::mul_div(c, d, ann_n).ok_or_else(overflow)?;
+    let b = x.checked_add(d / ann).ok_or_else(overflow)?;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        let numerator = y
+            .checked_mul(y)
+            .and_then(|y_y| y_y.checked_add(c))
+            .ok_or_else(overflow)?;
+        let denominator = y
+            .checked_mul(U256::from(2))
+            .and_then(|y_2| y_2.checked_add(b))
+            .and_then(|sum| sum.checked_sub(d))
+            .filter(|denominator| !denominator.is_zero())
+            .ok_or_else(not_converged)?;
+        y = numerator / denominator;
+        if y.abs_diff(y_prev) <= U256::from(1) {
+            return Ok(y);
+        }
+    }
+    Err(not_converged())
+}
+
+fn overflow() -> Error {
+    Error::AmountOverflow(AmountOverflow {})
+}
+
+fn not_converged() -> Error {
+    Error::InvariantNotConverged(InvariantNotConverged {})
+}
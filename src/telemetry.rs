@@ -0,0 +1,66 @@
+//! Gas usage telemetry, enabled by the `telemetry` feature.
+//!
+//! State-changing entrypoints start a [`GasMeter`] with [`meter!`], which
+//! emits [`GasUsed`] once the entrypoint returns. View functions are not
+//! metered, since they cannot emit events under a static call.
+#![cfg_attr(not(feature = "telemetry"), allow(unused))]
+
+use alloy_primitives::{FixedBytes, U256};
+use alloy_sol_types::sol;
+use stylus_sdk::evm;
+
+sol! {
+    /// Emitted when a metered entrypoint returns.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event GasUsed(bytes4 indexed selector, uint256 gas);
+}
+
+/// Measures the gas used between its creation and its drop.
+pub(crate) struct GasMeter {
+    selector: FixedBytes<4>,
+    gas_left: u64,
+}
+
+impl GasMeter {
+    /// Starts metering the entrypoint identified by `selector`.
+    pub(crate) fn start(selector: [u8; 4]) -> Self {
+        #[allow(deprecated)]
+        let gas_left = evm::gas_left();
+        Self {
+            selector: FixedBytes(selector),
+            gas_left,
+        }
+    }
+}
+
+impl Drop for GasMeter {
+    fn drop(&mut self) {
+        #[allow(deprecated)]
+        let gas = self.gas_left.saturating_sub(evm::gas_left());
+
+        #[allow(deprecated)]
+        evm::log(GasUsed {
+            selector: self.selector,
+            gas: U256::from(gas),
+        });
+    }
+}
+
+/// Meters the rest of the enclosing entrypoint, given its Solidity name and
+/// argument types as accepted by [`stylus_sdk::function_selector`].
+#[cfg(feature = "telemetry")]
+macro_rules! meter {
+    ($name:literal $(, $ty:ty)* $(,)?) => {
+        let _meter = $crate::telemetry::GasMeter::start(
+            stylus_sdk::function_selector!($name $(, $ty)*),
+        );
+    };
+}
+
+/// Meters the rest of the enclosing entrypoint, given its Solidity name and
+/// argument types as accepted by [`stylus_sdk::function_selector`].
+#[cfg(not(feature = "telemetry"))]
+macro_rules! meter {
+    ($($tt:tt)*) => {};
+}
@@ -0,0 +1,597 @@
+//! Fixtures shared by the motsu tests.
+//!
+//! Subsystem tests start from the same deployment: a curve at [`HOOK`]
+//! owned by the caller, optionally with seeded reserves and the standard
+//! [`POOLS`] registered as if initialized by the pool manager. The tokens
+//! and the pool manager it calls are mock contracts at their fixed
+//! addresses, see [`hook`].
+//!
+//! [`snapshot`] and [`restore`] capture and roll back the state that pool
+//! operations move, so a test can explore several branches from the same
+//! state, and fail if a branch touched anything else.
+use alloc::{string::String, vec::Vec};
+use core::ops::Deref;
+
+use alloy_primitives::{
+    address,
+    aliases::{I24, U24},
+    keccak256, uint, Address, FixedBytes, B256, I256, U256,
+};
+use motsu::prelude::{Contract, Funding};
+use stylus_sdk::{abi::Bytes, prelude::*, storage::StorageType, stylus_core::host::StorageAccess};
+
+use crate::{
+    changelog::{ChangeRecord, CHANGELOG_CAPACITY},
+    currency,
+    exchange_rate::ExchangeRate,
+    hooks, layout,
+    lp_token::ILpToken,
+    mocks::{MockErc20, MockPoolManager},
+    multisig::Proposal,
+    oracle::Report,
+    pool::{self, IPool, RegisteredPool},
+    ConstantSumCurve, Error, PoolKey, SwapParams, SQRT_PRICE_1_1,
+};
+
+pub(crate) const CURRENCY_1: Address = address!("A11CEacF9aa32246d767FCCD72e02d6bCbcC375d");
+pub(crate) const CURRENCY_2: Address = address!("B0B0cB49ec2e96DF5F5fFB081acaE66A2cBBc2e2");
+pub(crate) const CURRENCY_3: Address = address!("c0ffee254729296a45a3885639ac7e10f9d54979");
+
+/// Address of the mock Uniswap V4 pool manager calling the hooks.
+pub(crate) const POOL_MANAGER: Address = address!("5FbDB2315678afecb367f032d93F642f64180aa3");
+
+/// Address of the curve, whose lowest bits encode
+/// [`crate::hooks::hook_permissions`] like a deployed hook's.
+pub(crate) const HOOK: Address = address!("4c3a7e1d2b9f0a6e8d5c4b3a2f1e0d9c8b7a3acc");
+
+/// Balance of each currency held by the pool manager, paying what hooks
+/// take from it.
+pub(crate) const MANAGER_FLOAT: U256 = uint!(1_000_000_000_000_U256);
+
+/// Pools registered by [`initialize_pools`]: their sorted currencies and fee
+/// tier.
+pub(crate) const POOLS: [(Address, Address, U24); 3] = [
+    (CURRENCY_1, CURRENCY_2, uint!(500_U24)),
+    (CURRENCY_1, CURRENCY_3, uint!(3_000_U24)),
+    (CURRENCY_2, CURRENCY_3, uint!(3_000_U24)),
+];
+
+/// Reserves of each currency seeded by [`deploy_multi_pool`].
+pub(crate) const RESERVE: U256 = uint!(1_000_000_U256);
+
+/// Curve at [`HOOK`] next to the mock contracts it calls: tokens at
+/// [`CURRENCY_1`], [`CURRENCY_2`] and [`CURRENCY_3`], and the pool manager
+/// at [`POOL_MANAGER`]. Dereferences to the curve.
+pub(crate) struct Hook {
+    curve: Contract<ConstantSumCurve>,
+    /// The mock pool manager.
+    pub(crate) manager: Contract<MockPoolManager>,
+    tokens: [(Address, Contract<MockErc20>); 3],
+}
+
+impl Deref for Hook {
+    type Target = Contract<ConstantSumCurve>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.curve
+    }
+}
+
+impl Hook {
+    /// Returns the mock token at `currency`.
+    pub(crate) fn token(&self, currency: Address) -> &Contract<MockErc20> {
+        self.tokens
+            .iter()
+            .find(|(address, _)| *address == currency)
+            .map(|(_, token)| token)
+            .expect("currency should be a mock token")
+    }
+
+    /// Mints `amount` of `currency` to `to`, funding it for native ETH.
+    pub(crate) fn mint(&self, currency: Address, to: Address, amount: U256) {
+        if currency::is_native(currency) {
+            to.fund(amount);
+        } else {
+            self.token(currency).sender(to).mint(to, amount);
+        }
+    }
+
+    /// Calls `beforeSwap` from the pool manager for a swap of `sender` in
+    /// the pool `key`, and settles its delta with
+    /// [`Self::settle_before_swap`] if it succeeds.
+    pub(crate) fn swap(
+        &self,
+        sender: Address,
+        key: PoolKey,
+        params: SwapParams,
+        hook_data: Bytes,
+    ) -> Result<(FixedBytes<4>, I256, U24), Error> {
+        let result = self.curve.sender(POOL_MANAGER).before_swap(
+            sender,
+            key.clone(),
+            params.clone(),
+            hook_data,
+        );
+        if let Ok((_, delta, _)) = result {
+            self.settle_before_swap(&key, &params, delta);
+        }
+        result
+    }
+
+    /// Accounts the `BeforeSwapDelta` returned by the curve for `params` in
+    /// the pool `key`, as the pool manager does after `beforeSwap`, and
+    /// checks that the curve already cleared it with its takes and
+    /// payments.
+    pub(crate) fn settle_before_swap(&self, key: &PoolKey, params: &SwapParams, delta: I256) {
+        let exact_input = params.amount_specified.is_negative();
+        let (specified, unspecified) = if exact_input == params.zero_for_one {
+            (key.currency0, key.currency1)
+        } else {
+            (key.currency1, key.currency0)
+        };
+        for (currency, amount) in [
+            (specified, hooks::specified_delta(delta)),
+            (unspecified, hooks::unspecified_delta(delta)),
+        ] {
+            let amount = I256::try_from(amount).expect("amount should fit into int256");
+            self.manager
+                .sender(POOL_MANAGER)
+                .account(HOOK, currency, amount)
+                .expect("should account the hook's delta");
+        }
+        self.check_settled(&[key.currency0, key.currency1]);
+    }
+
+    /// Accounts the `fee` returned by `afterSwap` for `params` in the pool
+    /// `key` to the curve's unspecified currency, as the pool manager does,
+    /// and checks that the curve already took it.
+    pub(crate) fn settle_after_swap(&self, key: &PoolKey, params: &SwapParams, fee: i128) {
+        let exact_input = params.amount_specified.is_negative();
+        let unspecified = if exact_input == params.zero_for_one {
+            key.currency1
+        } else {
+            key.currency0
+        };
+        let fee = I256::try_from(fee).expect("fee should fit into int256");
+        self.manager
+            .sender(POOL_MANAGER)
+            .account(HOOK, unspecified, fee)
+            .expect("should account the hook's fee");
+        self.check_settled(&[key.currency0, key.currency1]);
+    }
+
+    /// Checks that the curve owes the pool manager nothing in `currencies`,
+    /// and is owed nothing.
+    pub(crate) fn check_settled(&self, currencies: &[Address]) {
+        for currency in currencies {
+            assert_eq!(
+                I256::ZERO,
+                self.manager
+                    .sender(POOL_MANAGER)
+                    .currency_delta(HOOK, *currency),
+                "{currency} should be settled"
+            );
+        }
+    }
+
+    /// Returns the balance of `currency` of `account`, native ETH included.
+    pub(crate) fn balance(&self, currency: Address, account: Address) -> U256 {
+        if currency::is_native(currency) {
+            account.balance()
+        } else {
+            self.token(currency).sender(account).balance_of(account)
+        }
+    }
+}
+
+/// Returns a curve at [`HOOK`], yet to be deployed, with the mocks it calls.
+/// The pool manager holds [`MANAGER_FLOAT`] of each currency.
+pub(crate) fn hook() -> Hook {
+    let hook = Hook {
+        curve: Contract::new_at(HOOK),
+        manager: Contract::new_at(POOL_MANAGER),
+        tokens: [CURRENCY_1, CURRENCY_2, CURRENCY_3]
+            .map(|currency| (currency, Contract::new_at(currency))),
+    };
+    for currency in [currency::NATIVE, CURRENCY_1, CURRENCY_2, CURRENCY_3] {
+        hook.mint(currency, POOL_MANAGER, MANAGER_FLOAT);
+    }
+    hook
+}
+
+/// Deploys the curve owned by `owner` and called by [`POOL_MANAGER`], with
+/// a 1:1 default rate and no fee.
+pub(crate) fn deploy(contract: &Contract<ConstantSumCurve>, owner: Address) {
+    contract
+        .sender(owner)
+        .constructor(
+            String::from("1.0.0"),
+            owner,
+            POOL_MANAGER,
+            U256::ZERO,
+            U256::ZERO,
+            0,
+        )
+        .expect("should initialize contract");
+}
+
+/// Seeds the reserves of the curve.
+pub(crate) fn seed_liquidity(
+    contract: &Contract<ConstantSumCurve>,
+    owner: Address,
+    reserve0: U256,
+    reserve1: U256,
+) {
+    contract
+        .sender(owner)
+        .set_reserves(reserve0, reserve1)
+        .expect("should set reserves");
+}
+
+/// Returns the key of the pool of `currency0` and `currency1` with `fee`.
+pub(crate) fn pool_key(currency0: Address, currency1: Address, fee: U24) -> PoolKey {
+    PoolKey {
+        currency0,
+        currency1,
+        fee,
+        tick_spacing: I24::ONE,
+        hooks: Address::ZERO,
+    }
+}
+
+/// Registers [`POOLS`] as the pool manager would on initialization, and
+/// returns their `PoolId`s.
+pub(crate) fn initialize_pools(
+    contract: &Contract<ConstantSumCurve>,
+    manager: Address,
+) -> Vec<B256> {
+    POOLS
+        .into_iter()
+        .map(|(currency0, currency1, fee)| {
+            let key = pool_key(currency0, currency1, fee);
+            let pool_id = pool::pool_id(&key);
+            contract
+                .sender(manager)
+                .before_initialize(manager, key, SQRT_PRICE_1_1)
+                .expect("should register pool");
+            pool_id
+        })
+        .collect()
+}
+
+/// Deploys the curve owned by `owner` with [`POOLS`] registered, seeds
+/// [`RESERVE`] of both currencies into the default pool and each of
+/// [`POOLS`], and returns their `PoolId`s. The reserves of [`POOLS`] are
+/// backed by tokens minted to the hook.
+pub(crate) fn deploy_multi_pool(contract: &Hook, owner: Address) -> Vec<B256> {
+    deploy(contract, owner);
+    seed_liquidity(contract, owner, RESERVE, RESERVE);
+    let pool_ids = initialize_pools(contract, POOL_MANAGER);
+    for ((currency0, currency1, _), pool_id) in POOLS.into_iter().zip(&pool_ids) {
+        contract.mint(currency0, HOOK, RESERVE);
+        contract.mint(currency1, HOOK, RESERVE);
+        contract
+            .sender(owner)
+            .set_pool_reserves(*pool_id, RESERVE, RESERVE)
+            .expect("should set pool reserves");
+    }
+    pool_ids
+}
+
+/// Accounts whose entries [`snapshot`] captures besides the given ones.
+const TRACKED: [Address; 3] = [Address::ZERO, HOOK, POOL_MANAGER];
+
+/// Currencies whose entries [`snapshot`] captures.
+const CURRENCIES: [Address; 4] = [currency::NATIVE, CURRENCY_1, CURRENCY_2, CURRENCY_3];
+
+/// Slot of the `balances` of [`MockErc20`].
+const TOKEN_BALANCES_SLOT: u8 = 0;
+
+/// Slot of the `total_supply` of [`MockErc20`].
+const TOKEN_SUPPLY_SLOT: u8 = 1;
+
+/// Slot of the `deltas` of [`MockPoolManager`].
+const MANAGER_DELTAS_SLOT: u8 = 0;
+
+/// Slot of the `nonzero_deltas` of [`MockPoolManager`].
+const MANAGER_NONZERO_DELTAS_SLOT: u8 = 1;
+
+/// Slot of the `claims` of [`MockPoolManager`].
+const MANAGER_CLAIMS_SLOT: u8 = 2;
+
+/// Number of top-level slots of [`MockPoolManager`].
+const MANAGER_SLOT_COUNT: u8 = 6;
+
+/// Raw storage words of a deployment, captured by [`snapshot`].
+pub(crate) struct Snapshot {
+    accounts: Vec<Address>,
+    curve: Vec<(U256, B256)>,
+    manager: Vec<(U256, B256)>,
+    tokens: Vec<Vec<(U256, B256)>>,
+    untouched: Vec<(U256, B256)>,
+    native: Vec<(Address, U256)>,
+}
+
+/// Captures the state that swaps, deposits, withdrawals and liquidity
+/// changes of `accounts` move: the curve's top-level fields, its changelog,
+/// the pools `pool_ids`, the LP shares and claims of the tracked accounts,
+/// and the mock tokens and pool manager.
+///
+/// Tracked accounts are `accounts`, the hook, the pool manager and the zero
+/// address. Entries of maps keyed by untracked accounts live at slots that
+/// cannot be enumerated, so all shares and tokens must be held by tracked
+/// accounts. The curve's other maps, vectors and strings, and native
+/// balances, are not rolled back: [`restore`] panics if they changed.
+pub(crate) fn snapshot(contract: &Hook, pool_ids: &[B256], accounts: &[Address]) -> Snapshot {
+    let accounts: Vec<Address> = TRACKED
+        .into_iter()
+        .chain(accounts.iter().copied())
+        .collect();
+    check_tracked(contract, &accounts);
+    let (curve, untouched) = {
+        let curve = contract.sender(Address::ZERO);
+        let vm = curve.vm();
+        (
+            load(vm, curve_slots(pool_ids, &accounts)),
+            load(vm, untouched_slots(vm, &accounts)),
+        )
+    };
+    let manager = load(
+        contract.manager.sender(Address::ZERO).vm(),
+        manager_slots(&accounts),
+    );
+    let tokens = contract
+        .tokens
+        .iter()
+        .map(|(_, token)| load(token.sender(Address::ZERO).vm(), token_slots(&accounts)))
+        .collect();
+    Snapshot {
+        curve,
+        manager,
+        tokens,
+        untouched,
+        native: accounts
+            .iter()
+            .map(|account| (*account, account.balance()))
+            .collect(),
+        accounts,
+    }
+}
+
+/// Writes the state captured by [`snapshot`] back into the deployment.
+///
+/// # Panics
+///
+/// If an untracked account holds shares or tokens, or if state that is not
+/// rolled back changed since the snapshot.
+pub(crate) fn restore(contract: &Hook, snapshot: &Snapshot) {
+    check_tracked(contract, &snapshot.accounts);
+    for (account, balance) in &snapshot.native {
+        assert_eq!(
+            *balance,
+            account.balance(),
+            "native balance of {account} is not rolled back, and should not change"
+        );
+    }
+    {
+        let curve = contract.sender(Address::ZERO);
+        let vm = curve.vm();
+        for (slot, word) in &snapshot.untouched {
+            assert_eq!(
+                *word,
+                vm.storage_load_bytes32(*slot),
+                "slot {slot} is not rolled back, and should not change"
+            );
+        }
+        store(vm, &snapshot.curve);
+    }
+    store(
+        contract.manager.sender(Address::ZERO).vm(),
+        &snapshot.manager,
+    );
+    for ((_, token), words) in contract.tokens.iter().zip(&snapshot.tokens) {
+        store(token.sender(Address::ZERO).vm(), words);
+    }
+}
+
+/// Checks that the tracked `accounts` hold all LP shares and mock tokens,
+/// and all nonzero deltas of the pool manager.
+fn check_tracked(contract: &Hook, accounts: &[Address]) {
+    let shares = accounts.iter().fold(U256::ZERO, |total, account| {
+        total + contract.sender(*account).balance_of(*account)
+    });
+    assert_eq!(
+        contract
+            .sender(Address::ZERO)
+            .total_supply()
+            .expect("should read total supply"),
+        shares,
+        "LP shares should be held by {accounts:?}"
+    );
+    for (currency, token) in &contract.tokens {
+        let token = token.sender(Address::ZERO);
+        let balances = accounts.iter().fold(U256::ZERO, |total, account| {
+            total.saturating_add(token.balance_of(*account))
+        });
+        assert_eq!(
+            token.total_supply(),
+            balances,
+            "{currency} should be held by {accounts:?}"
+        );
+    }
+    let manager = contract.manager.sender(POOL_MANAGER);
+    let deltas = accounts
+        .iter()
+        .flat_map(|account| CURRENCIES.map(|currency| (*account, currency)))
+        .filter(|(account, currency)| !manager.currency_delta(*account, *currency).is_zero())
+        .count();
+    assert_eq!(
+        B256::from(U256::from(deltas)),
+        manager
+            .vm()
+            .storage_load_bytes32(U256::from(MANAGER_NONZERO_DELTAS_SLOT)),
+        "deltas should be owed by {accounts:?}"
+    );
+}
+
+/// Returns the slots of the curve that [`restore`] rolls back, laid out as
+/// the `#[storage]` macro does.
+fn curve_slots(pool_ids: &[B256], accounts: &[Address]) -> Vec<U256> {
+    let end = layout::storage_layout()
+        .last()
+        .map_or(0, |field| field.slot + field.bytes.div_ceil(32));
+    let mut slots: Vec<U256> = (0..end).map(U256::from).collect();
+    for seq in 0..CHANGELOG_CAPACITY {
+        let key = B256::from(U256::from(seq));
+        slots.extend(entry(key, root("changelog"), ChangeRecord::REQUIRED_SLOTS));
+    }
+    for pool_id in pool_ids {
+        slots.extend(entry(
+            *pool_id,
+            root("pools"),
+            RegisteredPool::REQUIRED_SLOTS,
+        ));
+    }
+    for account in accounts {
+        slots.extend(entry(key(*account), root("shares"), 1));
+    }
+    for currency in CURRENCIES {
+        slots.extend(entry(key(currency), root("claim_balances"), 1));
+    }
+    slots
+}
+
+/// Returns the slots of the curve that [`restore`] checks instead of
+/// rolling back: the contents of its vectors and strings, its proposals,
+/// and the entries of its other maps keyed by tracked accounts and
+/// currencies.
+fn untouched_slots(vm: &(impl StorageAccess + ?Sized), accounts: &[Address]) -> Vec<U256> {
+    let word = |name: &str| U256::from_be_bytes(vm.storage_load_bytes32(root(name)).0);
+    let contents = |name: &str| U256::from_be_bytes(keccak256(root(name).to_be_bytes::<32>()).0);
+
+    let mut slots = Vec::new();
+    // Long strings store `2 * len + 1` at their slot, and their bytes at the
+    // hash of it.
+    let version = word("version");
+    if version.bit(0) {
+        let len = (version >> 1).to::<usize>();
+        slots.extend((0..len.div_ceil(32)).map(|offset| contents("version") + U256::from(offset)));
+    }
+    let reporters = word("reporter_list").to::<usize>();
+    slots.extend((0..reporters).map(|offset| contents("reporter_list") + U256::from(offset)));
+    for id in 0..word("proposal_count").to::<usize>() {
+        let proposal = entry(
+            B256::from(U256::from(id)),
+            root("proposals"),
+            Proposal::REQUIRED_SLOTS,
+        );
+        let approved = proposal.clone().last().expect("proposal should have slots");
+        slots.extend(proposal);
+        for account in accounts {
+            slots.extend(entry(key(*account), approved, 1));
+        }
+    }
+    for account in accounts {
+        for name in ["routers", "signers", "reporters"] {
+            slots.extend(entry(key(*account), root(name), 1));
+        }
+        slots.extend(entry(
+            key(*account),
+            root("reports"),
+            Report::REQUIRED_SLOTS,
+        ));
+        for spender in accounts {
+            slots.extend(nested(*account, *spender, root("allowances"), 1));
+        }
+    }
+    for currency in CURRENCIES {
+        slots.extend(entry(key(currency), root("listed_currencies"), 1));
+        for other in CURRENCIES {
+            slots.extend(nested(
+                currency,
+                other,
+                root("exchange_rates"),
+                ExchangeRate::REQUIRED_SLOTS,
+            ));
+        }
+    }
+    slots
+}
+
+/// Returns the slots of a mock token holding the balances of `accounts`.
+fn token_slots(accounts: &[Address]) -> Vec<U256> {
+    let mut slots = vec![U256::from(TOKEN_SUPPLY_SLOT)];
+    for account in accounts {
+        slots.extend(entry(key(*account), U256::from(TOKEN_BALANCES_SLOT), 1));
+    }
+    slots
+}
+
+/// Returns the slots of the mock pool manager holding the deltas and claims
+/// of `accounts`.
+fn manager_slots(accounts: &[Address]) -> Vec<U256> {
+    let mut slots: Vec<U256> = (0..MANAGER_SLOT_COUNT).map(U256::from).collect();
+    for account in accounts {
+        for currency in CURRENCIES {
+            let deltas = U256::from(MANAGER_DELTAS_SLOT);
+            slots.extend(nested(*account, currency, deltas, 1));
+            // Claims ids are the currencies' addresses.
+            let claims = U256::from(MANAGER_CLAIMS_SLOT);
+            slots.extend(nested(*account, currency, claims, 1));
+        }
+    }
+    slots
+}
+
+/// Returns the slot of the curve's field `name`.
+fn root(name: &str) -> U256 {
+    let field = layout::storage_layout()
+        .into_iter()
+        .find(|field| field.name == name)
+        .expect("field should exist");
+    U256::from(field.slot)
+}
+
+/// Returns the padded map key of `address`.
+fn key(address: Address) -> B256 {
+    B256::left_padding_from(address.as_slice())
+}
+
+/// Returns the `slots` of the entry `key` of the map at `root`, which start
+/// at the hash of the padded key and the map's slot.
+fn entry(key: B256, root: U256, slots: usize) -> impl Iterator<Item = U256> + Clone {
+    let base = U256::from_be_bytes(keccak256([key.0, root.to_be_bytes()].concat()).0);
+    (0..slots).map(move |offset| base + U256::from(offset))
+}
+
+/// Returns the `slots` of the entry `inner` of the entry `outer` of the
+/// nested map at `root`.
+fn nested(
+    outer: Address,
+    inner: Address,
+    root: U256,
+    slots: usize,
+) -> impl Iterator<Item = U256> + Clone {
+    let map = entry(key(outer), root, 1)
+        .next()
+        .expect("entry should have a slot");
+    entry(key(inner), map, slots)
+}
+
+/// Reads the words at `slots`.
+fn load(vm: &(impl StorageAccess + ?Sized), slots: Vec<U256>) -> Vec<(U256, B256)> {
+    slots
+        .into_iter()
+        .map(|slot| (slot, vm.storage_load_bytes32(slot)))
+        .collect()
+}
+
+/// Writes `words` back.
+fn store(vm: &(impl StorageAccess + ?Sized), words: &[(U256, B256)]) {
+    for (slot, word) in words {
+        // SAFETY: the words were read from the same contract, so writing
+        // them back leaves its storage consistent.
+        unsafe { vm.storage_cache_bytes32(*slot, *word) };
+    }
+    vm.flush_cache(false);
+}
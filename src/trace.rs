@@ -0,0 +1,77 @@
+//! Decision-path tracing, enabled by the `trace` feature.
+//!
+//! Entrypoints run their body through [`capture`], and [`trace!`] records
+//! the validations passed and intermediates computed along the way. When the
+//! body fails, its error is wrapped into [`Error::Traced`] carrying the
+//! original revert data followed by the recorded trace.
+#![cfg_attr(not(feature = "trace"), allow(unused))]
+
+use crate::Error;
+
+#[cfg(feature = "trace")]
+pub(crate) use buffer::record;
+
+#[cfg(feature = "trace")]
+mod buffer {
+    use alloc::{string::String, vec::Vec};
+    use core::cell::UnsafeCell;
+
+    /// Trace of the current call.
+    struct Buffer(UnsafeCell<Vec<String>>);
+
+    // SAFETY: Stylus contracts run on a single thread.
+    unsafe impl Sync for Buffer {}
+
+    static BUFFER: Buffer = Buffer(UnsafeCell::new(Vec::new()));
+
+    /// Appends `step` to the trace.
+    pub(crate) fn record(step: String) {
+        // SAFETY: The buffer is never borrowed across calls.
+        unsafe { (*BUFFER.0.get()).push(step) }
+    }
+
+    /// Takes the trace, leaving the buffer empty.
+    pub(super) fn take() -> Vec<String> {
+        // SAFETY: The buffer is never borrowed across calls.
+        unsafe { core::mem::take(&mut *BUFFER.0.get()) }
+    }
+}
+
+#[cfg(feature = "trace")]
+alloy_sol_types::sol! {
+    /// Wraps the revert data of a failed call with its trace.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    error Traced(bytes revert_data, string[] trace);
+}
+
+/// Runs `f` with an empty trace, attaching the recorded trace to its error.
+pub(crate) fn capture<T>(f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    #[cfg(feature = "trace")]
+    {
+        buffer::take();
+        f().map_err(|err| {
+            Error::Traced(Traced {
+                revert_data: alloc::vec::Vec::<u8>::from(err).into(),
+                trace: buffer::take(),
+            })
+        })
+    }
+
+    #[cfg(not(feature = "trace"))]
+    f()
+}
+
+/// Records a step of the decision path, formatted like [`format!`].
+#[cfg(feature = "trace")]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        $crate::trace::record(format!($($arg)*))
+    };
+}
+
+/// Records a step of the decision path, formatted like [`format!`].
+#[cfg(not(feature = "trace"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {};
+}
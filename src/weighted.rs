@@ -0,0 +1,322 @@
+//! Weighted Curve calculation for Uniswap V4 Hooks.
+//!
+//! Prices swaps along Balancer's weighted constant-mean invariant,
+//! `reserve_0^weight_0 * reserve_1^weight_1 = k`, so that each currency
+//! keeps its configured share of the pool's value. Weights are fixed-point
+//! numbers between [`MIN_WEIGHT`] and [`MAX_WEIGHT`], and only their ratio
+//! affects prices.
+//!
+//! This curve is deployed instead of [`crate::ConstantSumCurve`] when built
+//! with `--cfg curve="weighted"`.
+use alloc::string::String;
+
+use alloy_primitives::{uint, Address, U256};
+use alloy_sol_types::sol;
+use stylus_sdk::{
+    evm,
+    prelude::*,
+    storage::{StorageAddress, StorageMap, StorageString, StorageU256},
+};
+
+use crate::{
+    math::{self, WAD},
+    AmountInCalculated, AmountOutCalculated, AmountOverflow, Currency, Error, IUniswapV4Curve,
+    InsufficientLiquidity, InvalidWeight, Unauthorized,
+};
+
+sol! {
+    /// Emitted when the reserve of `currency` is set by the owner.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event ReserveSet(address indexed currency, uint256 reserve);
+
+    /// Emitted when the weight of `currency` is set by the owner.
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    event WeightSet(address indexed currency, uint256 weight);
+}
+
+/// Minimum weight of a currency, 1%.
+pub const MIN_WEIGHT: U256 = uint!(10_000_000_000_000_000_U256);
+
+/// Maximum weight of a currency, 100%.
+pub const MAX_WEIGHT: U256 = WAD;
+
+/// Weighted curve pricing swaps with the reserves and weights of each
+/// currency.
+#[storage]
+#[cfg_attr(curve = "weighted", entrypoint)]
+pub struct WeightedCurve {
+    version: StorageString,
+    owner: StorageAddress,
+    weights: StorageMap<Currency, StorageU256>,
+    reserves: StorageMap<Currency, StorageU256>,
+}
+
+// SAFETY: Unless it is the entrypoint, the contract is only instantiated as
+// top-level storage in tests.
+#[cfg(not(curve = "weighted"))]
+unsafe impl TopLevelStorage for WeightedCurve {}
+
+#[public]
+#[implements(IUniswapV4Curve<Error = Error>)]
+impl WeightedCurve {
+    /// Initializes the contract.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `version` - The version of the curve.
+    /// * `owner` - The owner of the contract.
+    #[constructor]
+    pub fn constructor(&mut self, version: String, owner: Address) {
+        self.version.set_str(version);
+        self.owner.set(owner);
+    }
+
+    /// Returns the owner of the contract.
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Returns the weight of `currency`, or zero if it is not set.
+    pub fn weight(&self, currency: Currency) -> U256 {
+        self.weights.get(currency)
+    }
+
+    /// Sets the weight of `currency`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `currency` - The currency to update.
+    /// * `weight` - The new weight.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    /// * [`Error::InvalidWeight`] - If `weight` is not between
+    ///   [`MIN_WEIGHT`] and [`MAX_WEIGHT`].
+    ///
+    /// # Events
+    ///
+    /// * [`WeightSet`].
+    pub fn set_weight(&mut self, currency: Currency, weight: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        check_weight(currency, weight)?;
+        self.weights.setter(currency).set(weight);
+
+        #[allow(deprecated)]
+        evm::log(WeightSet { currency, weight });
+
+        Ok(())
+    }
+
+    /// Returns the reserve of `currency`.
+    pub fn reserve(&self, currency: Currency) -> U256 {
+        self.reserves.get(currency)
+    }
+
+    /// Sets the reserve of `currency`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - Write access to the contract's state.
+    /// * `currency` - The currency to update.
+    /// * `reserve` - The new reserve.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    ///
+    /// # Events
+    ///
+    /// * [`ReserveSet`].
+    pub fn set_reserve(&mut self, currency: Currency, reserve: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.reserves.setter(currency).set(reserve);
+
+        #[allow(deprecated)]
+        evm::log(ReserveSet { currency, reserve });
+
+        Ok(())
+    }
+}
+
+#[public]
+impl IUniswapV4Curve for WeightedCurve {
+    type Error = Error;
+
+    fn version(&self) -> String {
+        self.version.get_string()
+    }
+
+    fn get_amount_in_for_exact_output(
+        &mut self,
+        amount_out: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Self::Error> {
+        let weight_in = self.weights.get(input);
+        let weight_out = self.weights.get(output);
+        check_weight(input, weight_in)?;
+        check_weight(output, weight_out)?;
+        let reserve_in = self.reserves.get(input);
+        let reserve_out = self.reserves.get(output);
+        let amount_in =
+            Self::calculate_amount_in(reserve_in, weight_in, reserve_out, weight_out, amount_out)?;
+        self.reserves.setter(input).set(reserve_in + amount_in);
+        self.reserves.setter(output).set(reserve_out - amount_out);
+
+        #[allow(deprecated)]
+        evm::log(AmountInCalculated {
+            amount_out,
+            input,
+            output,
+            zero_for_one,
+        });
+
+        Ok(amount_in)
+    }
+
+    fn get_amount_out_from_exact_input(
+        &mut self,
+        amount_in: U256,
+        input: Currency,
+        output: Currency,
+        zero_for_one: bool,
+    ) -> Result<U256, Self::Error> {
+        let weight_in = self.weights.get(input);
+        let weight_out = self.weights.get(output);
+        check_weight(input, weight_in)?;
+        check_weight(output, weight_out)?;
+        let reserve_in = self.reserves.get(input);
+        let reserve_out = self.reserves.get(output);
+        let amount_out =
+            Self::calculate_amount_out(reserve_in, weight_in, reserve_out, weight_out, amount_in)?;
+        self.reserves.setter(input).set(reserve_in + amount_in);
+        self.reserves.setter(output).set(reserve_out - amount_out);
+
+        #[allow(deprecated)]
+        evm::log(AmountOutCalculated {
+            amount_in,
+            input,
+            output,
+            zero_for_one,
+        });
+
+        Ok(amount_out)
+    }
+}
+
+impl WeightedCurve {
+    /// Returns the amount of input tokens for an exact-output swap, rounded
+    /// up, keeping the weighted invariant constant:
+    /// `reserve_in * ((reserve_out / (reserve_out - amount_out))^(weight_out / weight_in) - 1)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reserve_in` - The reserve of the input token.
+    /// * `weight_in` - The weight of the input token.
+    /// * `reserve_out` - The reserve of the output token.
+    /// * `weight_out` - The weight of the output token.
+    /// * `amount_out` - The amount of output tokens the user expects to receive.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::InsufficientLiquidity`] - If `amount_out` does not leave
+    ///   any output reserve.
+    /// * [`Error::AmountOverflow`] - If the power, the input amount or the
+    ///   new input reserve does not fit into `uint256`.
+    pub fn calculate_amount_in(
+        reserve_in: U256,
+        weight_in: U256,
+        reserve_out: U256,
+        weight_out: U256,
+        amount_out: U256,
+    ) -> Result<U256, Error> {
+        if amount_out >= reserve_out {
+            return Err(Error::InsufficientLiquidity(InsufficientLiquidity {
+                available: reserve_out,
+                requested: amount_out,
+            }));
+        }
+        let overflow = || Error::AmountOverflow(AmountOverflow {});
+        // Rounding the base and exponent up rounds the power up, as the base
+        // is at least one.
+        let base =
+            math::mul_div_up(reserve_out, WAD, reserve_out - amount_out).ok_or_else(overflow)?;
+        let exponent = math::mul_div_up(weight_out, WAD, weight_in).ok_or_else(overflow)?;
+        let power = math::pow_wad_up(base, exponent).ok_or_else(overflow)?;
+        let amount_in = math::mul_div_up(reserve_in, power - WAD, WAD).ok_or_else(overflow)?;
+        reserve_in.checked_add(amount_in).ok_or_else(overflow)?;
+        Ok(amount_in)
+    }
+
+    /// Returns the amount of output tokens for an exact-input swap, rounded
+    /// down, keeping the weighted invariant constant:
+    /// `reserve_out * (1 - (reserve_in / (reserve_in + amount_in))^(weight_in / weight_out))`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reserve_in` - The reserve of the input token.
+    /// * `weight_in` - The weight of the input token.
+    /// * `reserve_out` - The reserve of the output token.
+    /// * `weight_out` - The weight of the output token.
+    /// * `amount_in` - The amount of input tokens.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::AmountOverflow`] - If the new input reserve does not fit
+    ///   into `uint256`.
+    pub fn calculate_amount_out(
+        reserve_in: U256,
+        weight_in: U256,
+        reserve_out: U256,
+        weight_out: U256,
+        amount_in: U256,
+    ) -> Result<U256, Error> {
+        let overflow = || Error::AmountOverflow(AmountOverflow {});
+        let reserve_in_after = reserve_in.checked_add(amount_in).ok_or_else(overflow)?;
+        if reserve_in_after.is_zero() {
+            return Ok(U256::ZERO);
+        }
+        // Rounding the base up and the exponent down rounds the power up, as
+        // the base is at most one.
+        let base = math::mul_div_up(reserve_in, WAD, reserve_in_after).ok_or_else(overflow)?;
+        let exponent = math::mul_div(weight_in, WAD, weight_out).ok_or_else(overflow)?;
+        let power = math::pow_wad_up(base, exponent)
+            .ok_or_else(overflow)?
+            .min(WAD);
+        // `reserve_out * (WAD - power) / WAD` is at most `reserve_out`, so it
+        // always fits.
+        Ok(math::mul_div(reserve_out, WAD - power, WAD).unwrap_or_default())
+    }
+
+    /// Checks that the caller is the owner.
+    ///
+    /// # Errors
+    ///
+    /// * [`Error::Unauthorized`] - If the caller is not the owner.
+    fn only_owner(&self) -> Result<(), Error> {
+        let account = self.vm().msg_sender();
+        if account != self.owner.get() {
+            return Err(Error::Unauthorized(Unauthorized { account }));
+        }
+        Ok(())
+    }
+}
+
+/// Checks that `weight` is between [`MIN_WEIGHT`] and [`MAX_WEIGHT`].
+///
+/// # Errors
+///
+/// * [`Error::InvalidWeight`] - If `weight` is out of bounds, including
+///   unset.
+fn check_weight(currency: Currency, weight: U256) -> Result<(), Error> {
+    if weight < MIN_WEIGHT || weight > MAX_WEIGHT {
+        return Err(Error::InvalidWeight(InvalidWeight { currency, weight }));
+    }
+    Ok(())
+}